@@ -1,6 +1,8 @@
 use miniloop::executor::Executor;
 use miniloop::helpers::yield_me;
-use miniloop::task::Task;
+use miniloop::task::{Handle, Task};
+
+use core::pin::pin;
 
 fn sleep(s: u64) {
     std::thread::sleep(std::time::Duration::from_secs(s));
@@ -35,27 +37,27 @@ fn main() {
     let mut executor = Executor::<4>::new();
     executor.set_pending_callback(pending_print);
 
-    let mut task1 = Task::new("hello", async {
+    let task1 = pin!(Task::new("hello", async {
         dummy_func("hello").await;
-    });
-    let mut handle1 = task1.create_handle();
-    let mut task2 = Task::new("world", async {
+    }));
+    let mut handle1 = Handle::new();
+    let task2 = pin!(Task::new("world", async {
         dummy_func("world").await;
-    });
-    let mut handle2 = task2.create_handle();
-    let mut task3 = Task::new("hi", async {
+    }));
+    let mut handle2 = Handle::new();
+    let task3 = pin!(Task::new("hi", async {
         dummy_func("hi").await;
-    });
-    let mut handle3 = task3.create_handle();
-    let mut task4 = Task::new("rust", async {
+    }));
+    let mut handle3 = Handle::new();
+    let task4 = pin!(Task::new("rust", async {
         dummy_func("rust").await;
-    });
-    let mut handle4 = task4.create_handle();
+    }));
+    let mut handle4 = Handle::new();
 
-    let _ = executor.spawn(&mut task1, &mut handle1);
-    let _ = executor.spawn(&mut task2, &mut handle2);
-    let _ = executor.spawn(&mut task3, &mut handle3);
-    let _ = executor.spawn(&mut task4, &mut handle4);
+    let _ = executor.spawn(task1, &mut handle1);
+    let _ = executor.spawn(task2, &mut handle2);
+    let _ = executor.spawn(task3, &mut handle3);
+    let _ = executor.spawn(task4, &mut handle4);
 
     executor.run();
     println!("Done!");
@@ -1,7 +1,8 @@
 use miniloop::executor::Executor;
 use miniloop::helpers::yield_me;
-use miniloop::task::Task;
+use miniloop::task::{Handle, Task};
 
+use core::pin::pin;
 use core::time::Duration;
 
 fn is_expired(start: u64, delay_s: u64) -> bool {
@@ -49,13 +50,13 @@ fn pending_print(task_name: &str) {
 fn main() {
     let mut executor = Executor::<2>::new();
     executor.set_pending_callback(pending_print);
-    let mut task1 = Task::new("foo", foo());
-    let mut handle1 = task1.create_handle();
-    let mut task2 = Task::new("bar", async { bar().await });
-    let mut handle2 = task2.create_handle();
+    let task1 = pin!(Task::new("foo", foo()));
+    let mut handle1 = Handle::new();
+    let task2 = pin!(Task::new("bar", async { bar().await }));
+    let mut handle2 = Handle::new();
 
-    let _ = executor.spawn(&mut task1, &mut handle1);
-    let _ = executor.spawn(&mut task2, &mut handle2);
+    let _ = executor.spawn(task1, &mut handle1);
+    let _ = executor.spawn(task2, &mut handle2);
     executor.run();
 
     assert!(handle1.value.is_some_and(|v| v.is_ok_and(|s| s == "Hello")));
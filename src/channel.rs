@@ -0,0 +1,328 @@
+//! # Channel implementation
+//!
+//! This module provides [`Channel`], a bounded single-producer, single-consumer queue that lets
+//! two tasks running on the same executor pass values to each other. The values are stored in a
+//! ring buffer of `N` slots inside the channel itself, so it works without heap allocation.
+//!
+//! The channel is split into a [`Sender`] and a [`Receiver`]. Sending into a full channel and
+//! receiving from an empty one do not fail: the returned futures stay pending, yielding to the
+//! executor just like [`yield_me`](crate::helpers::yield_me), until the other half frees a slot or
+//! stores a value and wakes them.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! use miniloop::channel::Channel;
+//!
+//! let mut channel = Channel::<u32, 2>::new();
+//! let (mut sender, mut receiver) = channel.split();
+//! let producer = pin!(Task::new("producer", async move {
+//!     for value in 1..=4 {
+//!         sender.send(value).await;
+//!     }
+//! }));
+//! let mut producer_handle = Handle::new();
+//! let consumer = pin!(Task::new("consumer", async move {
+//!     let mut sum = 0;
+//!
+//!     for _ in 1..=4 {
+//!         sum += receiver.recv().await;
+//!     }
+//!
+//!     sum
+//! }));
+//! let mut consumer_handle = Handle::new();
+//! let mut executor = Executor::<2>::new();
+//! executor.spawn(producer, &mut producer_handle).expect("Failed to spawn task");
+//! executor.spawn(consumer, &mut consumer_handle).expect("Failed to spawn task");
+//! executor.run();
+//!
+//! assert_eq!(consumer_handle.value, Some(10));
+//! ```
+//!
+//! # Backpressure
+//!
+//! A channel keeps the highest number of values it has held, see [`Channel::peak_depth`]. A
+//! consumer that falls behind shows up as a channel that stays full: given to
+//! [`Executor::set_depth_monitor`](crate::executor::Executor::set_depth_monitor), the channel is
+//! checked at the end of every scheduling pass and calls the warning set with
+//! [`Channel::set_stuck_warning`] once it has been full for the given number of passes in a row.
+use crate::sync::WaitList;
+
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A bounded channel holding up to `N` values of type `T`.
+///
+/// The state of the channel is shared through [`Cell`]s, as the executor is single-threaded and
+/// only one of its halves is polled at a time. The halves are created by [`Channel::split`], which
+/// borrows the channel mutably, so there is a single sender and a single receiver at any time.
+///
+/// A channel with no slots (`N == 0`) is rejected at compile time.
+pub struct Channel<T, const N: usize> {
+    /// The storage for the values.
+    buffer: [Cell<Option<T>>; N],
+    /// The position of the oldest value in the buffer.
+    start: Cell<usize>,
+    /// The number of values in the buffer.
+    len: Cell<usize>,
+    /// The waker of the sender waiting for a free slot.
+    sender: WaitList<1>,
+    /// The waker of the receiver waiting for a value.
+    receiver: WaitList<1>,
+    /// The highest number of values the channel has held.
+    peak_depth: Cell<usize>,
+    /// The number of consecutive scheduling passes the channel has ended full.
+    full_passes: Cell<u32>,
+    /// The number of full passes after which `stuck_callback` is called.
+    stuck_after: u32,
+    /// An optional callback warning about a consumer that does not keep up.
+    stuck_callback: Option<fn(u32)>,
+}
+
+/// A queue checked by the executor at the end of each scheduling pass.
+///
+/// See [`Executor::set_depth_monitor`](crate::executor::Executor::set_depth_monitor).
+pub trait DepthMonitor {
+    /// Called by the executor at the end of every scheduling pass.
+    fn end_pass(&self);
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Channel<T, N> {
+    /// Creates an empty channel.
+    #[must_use]
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "a `Channel` needs at least one slot") };
+
+        Self {
+            buffer: [const { Cell::new(None) }; N],
+            start: Cell::new(0),
+            len: Cell::new(0),
+            sender: WaitList::new(),
+            receiver: WaitList::new(),
+            peak_depth: Cell::new(0),
+            full_passes: Cell::new(0),
+            stuck_after: 0,
+            stuck_callback: None,
+        }
+    }
+
+    /// Sets a callback warning about a consumer that does not keep up with the producer.
+    ///
+    /// The callback is called once the channel has been full at the end of `passes` scheduling
+    /// passes in a row, and again only after the channel has had a free slot at the end of a
+    /// pass. It is only checked while the channel is monitored by an executor, see
+    /// [`Executor::set_depth_monitor`](crate::executor::Executor::set_depth_monitor).
+    ///
+    /// # Parameters
+    ///
+    /// * `passes`:
+    ///   The number of passes the channel has to stay full for, at least 1.
+    /// * `cb`:
+    ///   A function receiving the number of passes the channel has been full for.
+    pub fn set_stuck_warning(&mut self, passes: u32, cb: fn(u32)) {
+        self.stuck_after = passes.max(1);
+        self.stuck_callback = Some(cb);
+    }
+
+    /// Splits the channel into its sending and receiving halves.
+    ///
+    /// Values left in the channel by a previous pair of halves are received by the new one.
+    pub fn split(&mut self) -> (Sender<'_, T, N>, Receiver<'_, T, N>) {
+        let channel = &*self;
+
+        (Sender { channel }, Receiver { channel })
+    }
+
+    /// Returns the number of values in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    /// Returns `true` if the channel holds no values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+
+    /// Returns the highest number of values the channel has held at once.
+    ///
+    /// A peak depth of `N` means the sender has found the channel full at least once.
+    #[must_use]
+    pub fn peak_depth(&self) -> usize {
+        self.peak_depth.get()
+    }
+
+    /// Stores `value` after the newest value and wakes the waiting receiver, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `value` back if the channel is full.
+    fn push(&self, value: T) -> Result<(), T> {
+        let len = self.len.get();
+
+        if len == N {
+            return Err(value);
+        }
+
+        self.buffer[(self.start.get() + len) % N].set(Some(value));
+        self.len.set(len + 1);
+        self.peak_depth.set(self.peak_depth.get().max(len + 1));
+
+        self.receiver.wake_all();
+
+        Ok(())
+    }
+
+    /// Removes the oldest value and wakes the waiting sender, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(value)` with the oldest value.
+    /// * `None` if the channel is empty.
+    fn pop(&self) -> Option<T> {
+        let len = self.len.get();
+
+        if len == 0 {
+            return None;
+        }
+
+        let start = self.start.get();
+        self.start.set((start + 1) % N);
+        self.len.set(len - 1);
+
+        self.sender.wake_all();
+
+        self.buffer[start].take()
+    }
+}
+
+impl<T, const N: usize> DepthMonitor for Channel<T, N> {
+    fn end_pass(&self) {
+        if self.len.get() < N {
+            self.full_passes.set(0);
+            return;
+        }
+
+        let passes = self.full_passes.get().saturating_add(1);
+        self.full_passes.set(passes);
+
+        if passes == self.stuck_after
+            && let Some(cb) = self.stuck_callback
+        {
+            cb(passes);
+        }
+    }
+}
+
+/// The sending half of a [`Channel`].
+pub struct Sender<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<'a, T, const N: usize> Sender<'a, T, N> {
+    /// Returns the channel this half belongs to, e.g. to read its depth or to monitor it while
+    /// the channel is split.
+    #[must_use]
+    pub fn channel(&self) -> &'a Channel<T, N> {
+        self.channel
+    }
+
+    /// Sends `value` into the channel.
+    ///
+    /// The returned future resolves once the value is stored, waiting for the receiver to free a
+    /// slot if the channel is full. Dropping the future before it resolves drops the value.
+    pub fn send(&mut self, value: T) -> SendFuture<'_, T, N> {
+        SendFuture {
+            channel: self.channel,
+            value: Some(value),
+        }
+    }
+}
+
+/// A future returned by [`Sender::send`].
+#[must_use = "the value is not sent unless the future is awaited"]
+pub struct SendFuture<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+    /// The value to send, `None` once it is stored.
+    value: Option<T>,
+}
+
+impl<T, const N: usize> Unpin for SendFuture<'_, T, N> {}
+
+impl<T, const N: usize> Future for SendFuture<'_, T, N> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let value = this
+            .value
+            .take()
+            .expect("`SendFuture` polled after completion");
+
+        match this.channel.push(value) {
+            Ok(()) => Poll::Ready(()),
+            Err(value) => {
+                this.value = Some(value);
+                this.channel.sender.register(cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The receiving half of a [`Channel`].
+pub struct Receiver<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<'a, T, const N: usize> Receiver<'a, T, N> {
+    /// Returns the channel this half belongs to, e.g. to read its depth or to monitor it while
+    /// the channel is split.
+    #[must_use]
+    pub fn channel(&self) -> &'a Channel<T, N> {
+        self.channel
+    }
+
+    /// Receives the oldest value from the channel.
+    ///
+    /// The returned future resolves once a value is available, waiting for the sender if the
+    /// channel is empty. The channel has no notion of being closed, so the future never resolves
+    /// if the sender is dropped while the channel is empty.
+    #[must_use]
+    pub fn recv(&mut self) -> RecvFuture<'_, T, N> {
+        RecvFuture {
+            channel: self.channel,
+        }
+    }
+}
+
+/// A future returned by [`Receiver::recv`].
+pub struct RecvFuture<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Future for RecvFuture<'_, T, N> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(value) = self.channel.pop() {
+            return Poll::Ready(value);
+        }
+
+        self.channel.receiver.register(cx.waker());
+        Poll::Pending
+    }
+}
@@ -0,0 +1,138 @@
+//! # Completion queue implementation
+//!
+//! This module provides [`CompletionQueue`], a bounded queue the executor pushes a [`Completion`]
+//! into each time a task completes. Unlike the completion callback, which runs inline while the
+//! executor is in the middle of a pass, the queue lets the completions be handled later by a
+//! consumer of its own: the code driving the executor between runs, or another task polled by the
+//! same executor. The queue stores its entries in a buffer provided by the caller, so it works
+//! without heap allocation.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! use miniloop::completion_queue::CompletionQueue;
+//! use miniloop::event_log::OverflowPolicy;
+//!
+//! let mut buffer = [None; 4];
+//! let queue = CompletionQueue::new(&mut buffer, OverflowPolicy::Stop);
+//! let task = pin!(Task::new("task", async {}));
+//! let mut handle = Handle::new();
+//! let mut executor = Executor::<1>::new();
+//! executor.set_completion_queue(&queue);
+//! let id = executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//! executor.run();
+//!
+//! let completion = queue.pop().expect("No completion");
+//! assert_eq!(completion.id, id);
+//! assert_eq!(completion.name, Some("task"));
+//! assert!(queue.pop().is_none());
+//! ```
+use crate::event_log::OverflowPolicy;
+use crate::executor::TaskId;
+
+use core::cell::Cell;
+
+/// A task completion pushed into a [`CompletionQueue`] by the executor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Completion<'a> {
+    /// The completed task.
+    pub id: TaskId,
+    /// The name of the completed task, if it has one.
+    pub name: Option<&'a str>,
+}
+
+/// A bounded first-in, first-out queue of task completions.
+///
+/// The queue is shared by reference between the executor, which pushes completions into it, and
+/// a single consumer popping them.
+pub struct CompletionQueue<'a> {
+    /// The storage for the completions.
+    buffer: &'a [Cell<Option<Completion<'a>>>],
+    /// The position of the oldest completion in the buffer.
+    start: Cell<usize>,
+    /// The number of completions in the buffer.
+    len: Cell<usize>,
+    /// What happens once the buffer is full.
+    policy: OverflowPolicy,
+}
+
+impl<'a> CompletionQueue<'a> {
+    /// Creates an empty queue storing its completions in `buffer`.
+    ///
+    /// The previous content of `buffer` is discarded.
+    ///
+    /// # Parameters
+    ///
+    /// * `buffer`:
+    ///   The storage for the completions. Its length is the capacity of the queue.
+    /// * `policy`:
+    ///   What to do with new completions once the buffer is full.
+    pub fn new(buffer: &'a mut [Option<Completion<'a>>], policy: OverflowPolicy) -> Self {
+        buffer.fill(None);
+
+        Self {
+            buffer: Cell::from_mut(buffer).as_slice_of_cells(),
+            start: Cell::new(0),
+            len: Cell::new(0),
+            policy,
+        }
+    }
+
+    /// Pushes a completion according to the overflow policy.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the completion has been stored.
+    /// * `false` if the completion has been dropped because the queue is full.
+    pub fn push(&self, completion: Completion<'a>) -> bool {
+        let capacity = self.buffer.len();
+        let start = self.start.get();
+        let len = self.len.get();
+
+        if len < capacity {
+            self.buffer[(start + len) % capacity].set(Some(completion));
+            self.len.set(len + 1);
+            true
+        } else if capacity > 0 && self.policy == OverflowPolicy::OverwriteOldest {
+            self.buffer[start].set(Some(completion));
+            self.start.set((start + 1) % capacity);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes the oldest completion from the queue.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(completion)` with the oldest completion.
+    /// * `None` if the queue is empty.
+    pub fn pop(&self) -> Option<Completion<'a>> {
+        let len = self.len.get();
+
+        if len == 0 {
+            return None;
+        }
+
+        let start = self.start.get();
+        self.start.set((start + 1) % self.buffer.len());
+        self.len.set(len - 1);
+        self.buffer[start].take()
+    }
+
+    /// Returns the number of queued completions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    /// Returns `true` if no completions are queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+}
@@ -0,0 +1,134 @@
+//! # Coroutine implementation
+//!
+//! A generator, or coroutine, is a function that can suspend itself several times, handing an
+//! intermediate value to its caller each time, before it finally returns. Futures are suspended
+//! at every `.await` already, so a coroutine can be built on top of a task: this module provides
+//! [`Coroutine`], a slot shared between a task and the code driving the executor. The task hands
+//! a value over with [`Coroutine::yield_value`] and stays suspended until the owner takes the
+//! value out with [`Coroutine::take`], so no value is lost between two runs of the executor.
+//!
+//! The yielded values are distinct from the output of the task: they form a stream of
+//! intermediate results, while the [`Handle`](crate::task::Handle) of the task receives its final
+//! output once it completes.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! use miniloop::coroutine::Coroutine;
+//!
+//! let coroutine = Coroutine::new();
+//! let task = pin!(Task::new("counter", async {
+//!     for value in 1..=2 {
+//!         coroutine.yield_value(value).await;
+//!     }
+//! }));
+//! let mut handle = Handle::new();
+//! let mut executor = Executor::<1>::new();
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//!
+//! while executor.run_until_predicate(&coroutine, Coroutine::has_value) {
+//!     println!("Yielded {:?}", coroutine.take());
+//! }
+//! ```
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A slot passing the values yielded by a task to its owner.
+///
+/// The slot holds at most a single value. A task yielding while the previous value has not been
+/// taken yet waits until the slot is free.
+pub struct Coroutine<T> {
+    /// The value yielded last, until the owner takes it.
+    value: Cell<Option<T>>,
+}
+
+impl<T> Default for Coroutine<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Coroutine<T> {
+    /// Creates a new `Coroutine` without a yielded value.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            value: Cell::new(None),
+        }
+    }
+
+    /// Hands `value` over to the owner and suspends the task until the owner takes it.
+    ///
+    /// # Parameters
+    ///
+    /// * `value`:
+    ///   The intermediate value to surface, stored once the previous value has been taken.
+    pub fn yield_value(&self, value: T) -> YieldValue<'_, T> {
+        YieldValue {
+            coroutine: self,
+            value: Some(value),
+        }
+    }
+
+    /// Takes the yielded value out of the slot, resuming the task that yielded it.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(value)` with the value yielded last.
+    /// * `None` if no value is waiting to be taken.
+    pub fn take(&self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Returns `true` if a yielded value is waiting to be taken.
+    ///
+    /// The signature fits [`Executor::run_until_predicate`](crate::executor::Executor::run_until_predicate),
+    /// which then runs the executor until the next value is yielded.
+    #[must_use]
+    pub fn has_value(&self) -> bool {
+        let value = self.value.take();
+        let has_value = value.is_some();
+        self.value.set(value);
+        has_value
+    }
+}
+
+/// A future handing a value over to the owner of a [`Coroutine`], created by
+/// [`Coroutine::yield_value`].
+pub struct YieldValue<'a, T> {
+    /// The slot the value is handed over through.
+    coroutine: &'a Coroutine<T>,
+    /// The value to hand over, `None` once it is stored in the slot.
+    value: Option<T>,
+}
+
+impl<T> Future for YieldValue<'_, T> {
+    type Output = ();
+
+    /// Stores the value as soon as the slot is free and completes once the owner has taken it.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` if the owner has taken the value.
+    /// * `Poll::Pending` if the value is waiting for the slot or for the owner.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `YieldValue` is never pinned structurally, the value is only moved out of it.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if this.value.is_none() && !this.coroutine.has_value() {
+            return Poll::Ready(());
+        }
+
+        if this.value.is_some() && !this.coroutine.has_value() {
+            this.coroutine.value.set(this.value.take());
+        }
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
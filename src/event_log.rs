@@ -0,0 +1,132 @@
+//! # Event log implementation
+//!
+//! This module provides [`EventLog`], a ring buffer the executor records its scheduling decisions
+//! into. Unlike callbacks, which have to process each event the moment it happens, the log keeps
+//! a trace that can be inspected or replayed after the executor has run. The log stores its events
+//! in a buffer provided by the caller, so it works without heap allocation.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! use miniloop::event_log::{EventKind, OverflowPolicy};
+//!
+//! let mut buffer = [None; 8];
+//! let task = pin!(Task::new("task", async {}));
+//! let mut handle = Handle::new();
+//! let mut executor = Executor::<1>::new();
+//! executor.set_event_log(&mut buffer, OverflowPolicy::OverwriteOldest);
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//! executor.run();
+//!
+//! let kinds = executor.events().map(|event| event.kind);
+//! assert!(kinds.eq([EventKind::Spawn, EventKind::Poll, EventKind::Complete]));
+//! ```
+use crate::executor::TaskId;
+
+/// The kind of a scheduling event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// A task has been spawned into a slot.
+    Spawn,
+    /// A task is about to be polled.
+    Poll,
+    /// A poll of a task has returned `Poll::Pending`.
+    Pending,
+    /// A poll of a task has returned `Poll::Ready` and the task has been removed.
+    Complete,
+    /// A task has aborted itself during a poll and has been removed.
+    Abort,
+    /// A task has been cancelled by the executor and removed.
+    Cancel,
+}
+
+/// A scheduling event recorded by the executor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// What has happened.
+    pub kind: EventKind,
+    /// The task the event refers to.
+    pub id: TaskId,
+    /// The label of the task, attached with [`Task::instrument`](crate::task::Task::instrument).
+    pub label: Option<&'static str>,
+    /// The scheduling pass the event happened in, see
+    /// [`Executor::pass_count`](crate::executor::Executor::pass_count).
+    pub pass: u64,
+}
+
+/// What an [`EventLog`] does with a new event once its buffer is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The oldest event is overwritten, so the log holds the most recent events.
+    #[default]
+    OverwriteOldest,
+    /// The new event is dropped, so the log holds the first events.
+    Stop,
+}
+
+/// A ring buffer of scheduling events.
+pub struct EventLog<'a> {
+    /// The storage for the events.
+    buffer: &'a mut [Option<Event>],
+    /// The position of the oldest event in the buffer.
+    start: usize,
+    /// The number of events in the buffer.
+    len: usize,
+    /// What happens once the buffer is full.
+    policy: OverflowPolicy,
+}
+
+impl<'a> EventLog<'a> {
+    /// Creates an empty log storing its events in `buffer`.
+    ///
+    /// The previous content of `buffer` is discarded.
+    pub fn new(buffer: &'a mut [Option<Event>], policy: OverflowPolicy) -> Self {
+        buffer.fill(None);
+
+        Self {
+            buffer,
+            start: 0,
+            len: 0,
+            policy,
+        }
+    }
+
+    /// Records an event according to the overflow policy.
+    pub fn push(&mut self, event: Event) {
+        let capacity = self.buffer.len();
+
+        if capacity == 0 {
+            return;
+        }
+
+        if self.len < capacity {
+            self.buffer[(self.start + self.len) % capacity] = Some(event);
+            self.len += 1;
+        } else if self.policy == OverflowPolicy::OverwriteOldest {
+            self.buffer[self.start] = Some(event);
+            self.start = (self.start + 1) % capacity;
+        }
+    }
+
+    /// Returns the number of recorded events.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no events have been recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the recorded events, from the oldest to the most recent one.
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        let (newer, older) = self.buffer.split_at(self.start);
+
+        older.iter().chain(newer).take(self.len).flatten()
+    }
+}
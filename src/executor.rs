@@ -12,43 +12,341 @@
 //! ### Running the Executor
 //! ```no_run
 //! # use miniloop::executor::Executor;
-//! # use miniloop::task::Task;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
 //! const TASK_ARRAY_SIZE: usize = 4;
 //! let mut executor: Executor<TASK_ARRAY_SIZE> = Executor::new();
-//! let mut task = Task::new("task1", async { println!("Task executed"); });
-//! let mut handle = task.create_handle();
-//! executor.spawn(&mut task, &mut handle).expect("Failed to spawn task");
+//! let task = pin!(Task::new("task1", async { println!("Task executed"); }));
+//! let mut handle = Handle::new();
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
 //! executor.run();
 //! ```
 //!
 //! ## Usage Notes
-//! - The `Executor` is designed to work with a fixed task slot size. Trying to add more than 4 tasks will result in an error (`NoFreeSlots`).
+//! - The `Executor` is designed to work with a fixed task slot size. Trying to add more tasks than there are free slots will result in an error (`NoFreeSlots`) that names the rejected task. The slot of a task is freed once the task leaves the executor, so it can be reused by a later spawn.
 //! - Ensure that tasks added to the executor are correctly managed and polled to avoid resource leaks or incomplete executions.
+use crate::channel::DepthMonitor;
+use crate::completion_queue::{Completion, CompletionQueue};
+use crate::event_log::{Event, EventKind, EventLog, OverflowPolicy};
+use crate::reactor::{Interest, Reactor};
 use crate::sbox::{StackBox, StackBoxFuture};
-use crate::task::{Handle, Task};
+use crate::snapshot::{SchedulerSnapshot, SlotSnapshot, Stats};
+use crate::sync::AbortFlag;
+use crate::task::{ErasedTask, Handle, Task, TaskFuture};
+use crate::telemetry::{Metric, Telemetry};
+use crate::time::{TimeSource, is_reached};
+use crate::wake::{self, WakeFlag, WakeTable};
 
+use core::any::Any;
+use core::cell::Cell;
 use core::future::Future;
-use core::pin::pin;
+use core::pin::{Pin, pin};
 use core::ptr;
 use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
 /// An enumeration representing different types of errors that can occur.
+///
+/// # Type Parameters
+/// - `'a`: The lifetime of the task name carried by the error.
 #[derive(Debug, PartialEq)]
-pub enum Error {
+pub enum Error<'a> {
     /// Indicates that there are no free slots available.
-    NoFreeSlots,
+    NoFreeSlots {
+        /// The name of the task that was rejected, if it has one.
+        name: Option<&'a str>,
+    },
+}
+
+/// An identifier of a task spawned into an [`Executor`].
+///
+/// The identifier is returned by [`Executor::spawn`] and refers to the executor slot the task
+/// occupies. Slots are reused once their tasks leave the executor, so the identifier also carries
+/// the generation of the slot: a counter incremented every time a task is spawned into the slot.
+/// The executor ignores identifiers of an older generation, so an identifier kept after its task
+/// is gone never refers to the task that took over the slot.
+///
+/// The generation is a `u32` that wraps around. A stale identifier could only be mistaken for a
+/// live one if it were kept while exactly a multiple of 2<sup>32</sup> further tasks were spawned
+/// into the same slot, which is accepted as practically impossible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskId {
+    /// The index of the slot.
+    index: usize,
+    /// The generation of the slot the task was spawned in.
+    generation: u32,
+}
+
+impl TaskId {
+    pub(crate) const fn new(index: usize) -> Self {
+        Self {
+            index,
+            generation: 0,
+        }
+    }
+
+    /// Returns the index of the executor slot the task was spawned into.
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self.index
+    }
+
+    /// Returns the generation of the executor slot the task was spawned in.
+    #[must_use]
+    pub const fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+/// What the executor does with the poll a debugger hook is consulted about, see
+/// [`Executor::set_debugger`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// The task is polled.
+    Continue,
+    /// The task is not polled in this turn and the executor moves on to the next task.
+    Skip,
+    /// The task is not polled and the run returns, leaving all tasks in their slots.
+    Halt,
+}
+
+/// The progress made by a single scheduling pass, returned by [`Executor::run_once`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunStatus {
+    /// Tasks remain in the executor after the pass.
+    Pending {
+        /// The number of tasks left in the executor.
+        remaining: usize,
+        /// The number of tasks completed during the pass.
+        completed: usize,
+    },
+    /// No tasks remain in the executor after the pass.
+    AllComplete {
+        /// The number of tasks completed during the pass.
+        completed: usize,
+    },
+    /// The [abort flag](Executor::set_abort_flag) was set, so the pass cancelled all tasks.
+    Aborted {
+        /// The number of tasks cancelled by the pass.
+        cancelled: usize,
+    },
+}
+
+/// A task rejected for the lack of a free slot, returned by [`Executor::try_spawn`].
+///
+/// The task has not been linked with the handle, so both can be passed to another spawn.
+pub struct Rejected<'a, F: Future> {
+    /// The rejected task.
+    pub task: Pin<&'a mut Task<'a, F>>,
+    /// The handle the task was to be linked with.
+    pub handle: &'a mut Handle<F::Output>,
+}
+
+/// A guard that cancels its task when dropped, returned by [`Executor::spawn_guarded`].
+///
+/// The guard ties the lifetime of a task to a scope: once the guard goes out of scope, the
+/// executor removes the task before polling it again. The guard does not borrow the executor, so
+/// the executor can keep running while the guard is alive. It signals the cancellation through a
+/// flag shared with the executor instead, which is why cancelled tasks are removed on the next
+/// pass rather than right away. Dropping the guard of a completed task has no effect.
+#[must_use = "the task is cancelled right away if the guard is not kept"]
+pub struct SpawnGuard<'a> {
+    /// The guarded task.
+    id: TaskId,
+    /// The flag shared with the executor, set to cancel the task.
+    cancel: &'a Cell<bool>,
+}
+
+impl SpawnGuard<'_> {
+    /// Returns the identifier of the guarded task.
+    #[must_use]
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Releases the task from the guard, so it keeps running after the guard is gone.
+    pub fn detach(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl Drop for SpawnGuard<'_> {
+    fn drop(&mut self) {
+        self.cancel.set(true);
+    }
+}
+
+/// A future driving a child executor from a task of a parent executor, created by
+/// [`Executor::nested`].
+pub struct Nested<'e, 'a, const TASK_ARRAY_SIZE: usize> {
+    /// The child executor.
+    executor: &'e mut Executor<'a, TASK_ARRAY_SIZE>,
+}
+
+impl<const TASK_ARRAY_SIZE: usize> Future for Nested<'_, '_, TASK_ARRAY_SIZE> {
+    type Output = ();
+
+    /// Makes a single scheduling pass over the tasks of the child executor.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` once the child executor has no tasks left, or its debugger halted it.
+    /// * `Poll::Pending` otherwise.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let executor = &mut *self.get_mut().executor;
+
+        if executor.tasks.iter().all(Option::is_none) {
+            return Poll::Ready(());
+        }
+
+        let status = executor.run_once();
+
+        if executor.take_halted() || !matches!(status, RunStatus::Pending { .. }) {
+            return Poll::Ready(());
+        }
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
 }
 
 /// The `Executor` struct is responsible for managing and running tasks.
 pub struct Executor<'a, const TASK_ARRAY_SIZE: usize> {
-    /// An array of optional tasks that the executor can manage. The array size is fixed at 4 elements.
+    /// The slots holding the tasks of the executor, `None` for a free slot. A task is spawned into
+    /// the first free slot, and the slot is reused once the task completes or is cancelled.
     tasks: [Option<StackBoxFuture<'a>>; TASK_ARRAY_SIZE],
 
-    /// An index indicating the current position in the tasks array.
-    index: usize,
-
     /// An optional callback function that takes a `&str` argument and is pending execution.
     pending_callback: Option<fn(&str)>,
+
+    /// An optional hook invoked when no task can make progress, e.g. to wait for an interrupt.
+    idle_hook: Option<fn()>,
+
+    /// An optional callback invoked with the name of each task that completes.
+    completion_callback: Option<fn(&str)>,
+
+    /// An optional callback invoked at the start of each scheduling pass.
+    watchdog_kick: Option<fn()>,
+
+    /// An optional hook invoked right before each task poll.
+    before_poll: Option<fn(TaskId)>,
+
+    /// An optional hook invoked right after each task poll with its result.
+    after_poll: Option<fn(TaskId, Poll<()>)>,
+
+    /// The number of scheduling passes made over the tasks array.
+    pass_count: u64,
+
+    /// An optional limit on the number of tasks polled in a single pass.
+    concurrency_limit: Option<usize>,
+
+    /// An optional generator shuffling the polling order of each pass.
+    shuffle: Option<XorShift>,
+
+    /// The number of consecutive polls a task gets in its turn.
+    quantum: usize,
+
+    /// An optional limit on the number of polls of a single task in a single run.
+    max_polls_per_run: Option<u32>,
+
+    /// An optional callback invoked with the name of a task cancelled for exceeding the limit.
+    on_runaway: Option<fn(&str)>,
+
+    /// The number of passes between two calls of the statistics callback.
+    stats_every: u32,
+
+    /// An optional callback invoked with the executor statistics every few passes.
+    stats_callback: Option<fn(&Stats)>,
+
+    /// The number of task polls made.
+    poll_count: u64,
+
+    /// The number of tasks completed.
+    completion_count: u64,
+
+    /// Whether running the executor without any spawned task panics in debug builds.
+    panic_on_empty_run: bool,
+
+    /// An optional hook deciding about each task poll.
+    debugger: Option<fn(TaskId, &str) -> Control>,
+
+    /// Whether the debugger hook has halted the current run.
+    halted: bool,
+
+    /// The slot of the task being polled, which is taken out of the slot during the poll.
+    polling: Option<usize>,
+
+    /// An optional log of scheduling events.
+    event_log: Option<EventLog<'a>>,
+
+    /// An optional sink receiving the metrics of the executor.
+    telemetry: Option<&'a dyn Telemetry>,
+
+    /// An optional queue the completions of tasks are pushed into.
+    completion_queue: Option<&'a CompletionQueue<'a>>,
+
+    /// An optional source of time used to timestamp polls.
+    time_source: Option<&'a dyn TimeSource>,
+
+    /// An optional reactor consulted for the readiness of parked tasks.
+    reactor: Option<&'a dyn Reactor>,
+
+    /// An optional queue checked at the end of each pass.
+    depth_monitor: Option<&'a dyn DepthMonitor>,
+
+    /// An optional flag cancelling all tasks once set.
+    abort_flag: Option<&'a AbortFlag>,
+
+    /// The group of the task in each slot, if it was spawned into one.
+    groups: [Option<usize>; TASK_ARRAY_SIZE],
+
+    /// The task the task in each slot waits for, if it was spawned with a dependency.
+    blocked_on: [Option<TaskId>; TASK_ARRAY_SIZE],
+
+    /// The generation of each slot, incremented whenever a task is spawned into the slot.
+    generations: [u32; TASK_ARRAY_SIZE],
+
+    /// An optional table tracking the wakes of the tasks. Without it, every task is polled on
+    /// every pass.
+    wake_table: Option<&'static WakeTable<TASK_ARRAY_SIZE>>,
+
+    /// The cancellation flag of the task in each slot, if it was spawned with a guard.
+    cancel_flags: [Option<&'a Cell<bool>>; TASK_ARRAY_SIZE],
+
+    /// The name of the task spawned into each slot last.
+    names: [Option<&'a str>; TASK_ARRAY_SIZE],
+
+    /// The label of the task spawned into each slot last.
+    labels: [Option<&'static str>; TASK_ARRAY_SIZE],
+
+    /// The number of polls of the task in each slot since it was spawned.
+    polls: [u64; TASK_ARRAY_SIZE],
+
+    /// The tick at which the task in each slot was last polled.
+    last_polled: [Option<u64>; TASK_ARRAY_SIZE],
+
+    /// The tick since which the task in each slot has been waiting to be polled.
+    runnable_since: [Option<u64>; TASK_ARRAY_SIZE],
+
+    /// The longest wait of the task in each slot between becoming runnable and being polled.
+    max_latency: [Option<u64>; TASK_ARRAY_SIZE],
+
+    /// The number of polls of the task in each slot during the current run.
+    polls_this_run: [u32; TASK_ARRAY_SIZE],
+
+    /// The number of times the task in each slot has yielded since it was spawned.
+    yields: [u32; TASK_ARRAY_SIZE],
+
+    /// The interest the task in each slot is parked on, if any.
+    parked: [Option<Interest>; TASK_ARRAY_SIZE],
+
+    /// The tick the task in each slot sleeps until, if any.
+    deadlines: [Option<u64>; TASK_ARRAY_SIZE],
+
+    /// Total time spent polling the task in each slot.
+    #[cfg(feature = "std")]
+    busy_time: [Duration; TASK_ARRAY_SIZE],
 }
 
 impl<const TASK_ARRAY_SIZE: usize> Default for Executor<'_, TASK_ARRAY_SIZE> {
@@ -60,10 +358,9 @@ impl<const TASK_ARRAY_SIZE: usize> Default for Executor<'_, TASK_ARRAY_SIZE> {
 impl<'a, const TASK_ARRAY_SIZE: usize> Executor<'a, TASK_ARRAY_SIZE> {
     /// Creates a new instance of the `Executor` struct.
     ///
-    /// This function initializes the `Executor` with:
-    /// - an array of `None` tasks with a fixed size of 4,
-    /// - the index set to 0,
-    /// - and no pending callback function.
+    /// The executor starts with `TASK_ARRAY_SIZE` free slots and no callbacks or hooks installed.
+    /// Each spawned task takes the first free slot, which is freed again once the task completes
+    /// or is cancelled.
     ///
     /// # Returns
     ///
@@ -77,8 +374,50 @@ impl<'a, const TASK_ARRAY_SIZE: usize> Executor<'a, TASK_ARRAY_SIZE> {
     pub const fn new() -> Self {
         Self {
             tasks: [const { None }; TASK_ARRAY_SIZE],
-            index: 0,
             pending_callback: None,
+            idle_hook: None,
+            completion_callback: None,
+            watchdog_kick: None,
+            before_poll: None,
+            after_poll: None,
+            pass_count: 0,
+            concurrency_limit: None,
+            shuffle: None,
+            quantum: 1,
+            max_polls_per_run: None,
+            on_runaway: None,
+            stats_every: 1,
+            stats_callback: None,
+            poll_count: 0,
+            completion_count: 0,
+            panic_on_empty_run: false,
+            debugger: None,
+            halted: false,
+            polling: None,
+            event_log: None,
+            telemetry: None,
+            completion_queue: None,
+            time_source: None,
+            reactor: None,
+            depth_monitor: None,
+            abort_flag: None,
+            groups: [None; TASK_ARRAY_SIZE],
+            blocked_on: [None; TASK_ARRAY_SIZE],
+            generations: [0; TASK_ARRAY_SIZE],
+            wake_table: None,
+            cancel_flags: [None; TASK_ARRAY_SIZE],
+            names: [None; TASK_ARRAY_SIZE],
+            labels: [None; TASK_ARRAY_SIZE],
+            polls: [0; TASK_ARRAY_SIZE],
+            last_polled: [None; TASK_ARRAY_SIZE],
+            runnable_since: [None; TASK_ARRAY_SIZE],
+            max_latency: [None; TASK_ARRAY_SIZE],
+            polls_this_run: [0; TASK_ARRAY_SIZE],
+            yields: [0; TASK_ARRAY_SIZE],
+            parked: [None; TASK_ARRAY_SIZE],
+            deadlines: [None; TASK_ARRAY_SIZE],
+            #[cfg(feature = "std")]
+            busy_time: [Duration::ZERO; TASK_ARRAY_SIZE],
         }
     }
 
@@ -93,151 +432,2061 @@ impl<'a, const TASK_ARRAY_SIZE: usize> Executor<'a, TASK_ARRAY_SIZE> {
         self.pending_callback = Some(cb);
     }
 
-    /// # Errors
+    /// Sets the hook invoked when no task can make progress.
     ///
-    /// * `NoFreeSlots` - if there is no free slots in the executor
-    pub fn spawn<F>(
-        &mut self,
-        task: &'a mut Task<'a, F>,
-        handle: &'a mut Handle<F::Output>,
-    ) -> Result<(), Error>
-    where
-        F: Future + 'a,
-    {
-        if self.index >= self.tasks.len() {
-            return Err(Error::NoFreeSlots);
-        }
+    /// [`Executor::run`] invokes the hook after a scheduling pass that leaves no task woken, and
+    /// [`Executor::block_on`] after a poll that returns `Poll::Pending` without the future waking
+    /// itself. On an MCU the hook would typically execute `wfi` to sleep until the next interrupt,
+    /// which can then wake a task. The hook is never invoked while a task is ready to make
+    /// progress, so a wake from an interrupt handler is not slept through.
+    ///
+    /// # Parameters
+    ///
+    /// * `hook`:
+    ///   A function pointer to the hook idling the system.
+    pub fn set_idle_hook(&mut self, hook: fn()) {
+        self.idle_hook = Some(hook);
+    }
 
-        task.link_handle(handle);
-        let index = self.index;
-        self.index += 1;
-        self.tasks[index] = Some(StackBox::new(task));
+    /// Sets the callback function to be invoked when a task completes.
+    ///
+    /// The callback is invoked right after the poll the task completes in, so the order of the
+    /// invocations is the order the tasks actually finish in: tasks completing in an earlier
+    /// scheduling pass are reported first, and tasks completing in the same pass are reported in
+    /// the order of their slots. Tasks aborting themselves or cancelled by the executor are not
+    /// reported.
+    ///
+    /// # Parameters
+    ///
+    /// * `cb`:
+    ///   A function pointer to a callback that takes a `&str` argument.
+    ///   This callback will be called with the task's name when the task completes.
+    pub fn set_completion_callback(&mut self, cb: fn(&str)) {
+        self.completion_callback = Some(cb);
+    }
 
-        Ok(())
+    /// Sets the callback that kicks an external watchdog.
+    ///
+    /// The callback is invoked once at the start of every scheduling pass, e.g. to feed a hardware
+    /// watchdog timer. As long as the tasks keep yielding, the executor loop keeps the watchdog
+    /// satisfied, and it only expires if the executor stops making passes.
+    ///
+    /// A pass waits for each poll to return, so a task that blocks inside a single poll for
+    /// longer than the watchdog period starves the kick as well. That is the intended outcome:
+    /// blocking the executor is a bug the watchdog should catch.
+    ///
+    /// # Parameters
+    ///
+    /// * `cb`:
+    ///   A function pointer to the callback kicking the watchdog.
+    pub fn set_watchdog_kick(&mut self, cb: fn()) {
+        self.watchdog_kick = Some(cb);
     }
-    /// Blocks on the provided future until it is completed.
+
+    /// Sets a hook consulted before each task poll, acting as a programmable breakpoint.
     ///
-    /// This method will drive the given future to completion, blocking the
-    /// current thread during the process. It is useful for running a single
-    /// future to completion in a synchronous context.
+    /// The hook decides with a [`Control`] whether the task is polled, skipped for its turn or
+    /// whether the run halts right there. A halted [`Executor::run`] returns with all tasks still
+    /// in their slots, so a teaching harness, e.g. a REPL, can step through the scheduling
+    /// interactively and resume it with another call to `run`.
     ///
     /// # Parameters
     ///
-    /// * `future` - The future to be executed until completion. The future
-    ///   must implement [`Future`], and its output must match the type `T`.
+    /// * `hook`:
+    ///   A hook invoked with the id and the name of the task about to be polled.
+    pub fn set_debugger(&mut self, hook: fn(TaskId, &str) -> Control) {
+        self.debugger = Some(hook);
+    }
+
+    /// Sets the hooks bracketing each task poll.
     ///
-    /// # Returns
+    /// Unlike the pending callback, which only reports pending tasks, the hooks are invoked around
+    /// every poll, so they suit instrumentation that has to enter and exit a scope for each poll,
+    /// such as tracing spans.
     ///
-    /// This function will return the output of the provided future once it
-    /// is resolved.
+    /// # Parameters
     ///
-    /// # Example
+    /// * `before`:
+    ///   A hook invoked with the id of the task right before it is polled.
+    /// * `after`:
+    ///   A hook invoked with the id of the task and the result of the poll right after the poll
+    ///   and the pending callback return. A task aborting itself reports `Poll::Ready(())`, as it
+    ///   leaves the executor just like a completed one.
+    pub fn set_around_poll(&mut self, before: fn(TaskId), after: fn(TaskId, Poll<()>)) {
+        self.before_poll = Some(before);
+        self.after_poll = Some(after);
+    }
+
+    /// Returns the number of tasks in the executor, i.e. the tasks spawned and neither completed
+    /// nor removed yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tasks.iter().flatten().count()
+    }
+
+    /// Returns `true` if the executor holds no tasks.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.iter().all(Option::is_none)
+    }
+
+    /// Returns the number of task slots of the executor, `TASK_ARRAY_SIZE`.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        TASK_ARRAY_SIZE
+    }
+
+    /// Returns the number of scheduling passes the executor has made.
     ///
-    /// ```rust
-    /// # use miniloop::executor::Executor;
-    /// const TASK_ARRAY_SIZE: usize = 1;
-    /// let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
-    /// let result = executor.block_on(async { 42 });
-    /// assert_eq!(result, 42);
-    /// ```
-    pub fn block_on<F, T>(&mut self, future: F) -> T
-    where
-        F: Future<Output = T>,
-    {
-        let waker = create_waker();
-        let mut future = pin!(future);
-        let mut ctx = Context::from_waker(&waker);
+    /// A pass is a single iteration over the tasks array in [`Executor::run`]. The counter is
+    /// incremented at the start of each pass and keeps counting across calls to `run`, which
+    /// gives callbacks and diagnostics temporal context about the run, until it is restarted with
+    /// [`Executor::reset_pass_count`].
+    #[must_use]
+    pub fn pass_count(&self) -> u64 {
+        self.pass_count
+    }
 
-        loop {
-            if let Poll::Ready(val) = future.as_mut().poll(&mut ctx) {
-                return val;
-            }
+    /// Restarts the count of [`Executor::pass_count`] from zero, e.g. before reusing the executor
+    /// for the next batch of tasks.
+    pub fn reset_pass_count(&mut self) {
+        self.pass_count = 0;
+    }
+
+    /// Returns the counters of the executor summed up over all its tasks.
+    ///
+    /// Like [`Executor::pass_count`], the counters keep counting across calls to
+    /// [`Executor::run`]. Wakes are not counted, only the polls they lead to.
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        Stats {
+            passes: self.pass_count,
+            polls: self.poll_count,
+            completions: self.completion_count,
         }
     }
 
-    /// Executes tasks in the executor until all tasks are completed.
+    /// Sets the callback function to be invoked with the executor statistics periodically.
     ///
-    /// The method repeatedly polls each task in the tasks array. If a task completes, it is removed from the array.
-    /// The function keeps running until all tasks are either completed or removed from the tasks array.
+    /// The callback is invoked at the end of every `every_passes`-th scheduling pass, i.e. whenever
+    /// [`Executor::pass_count`] is a multiple of `every_passes`, with the same [`Stats`] that
+    /// [`Executor::stats`] returns. It allows to publish the progress of a long-running executor
+    /// without tracking the passes in the tasks. A value of `0` is treated as `1`.
     ///
-    /// <div class="warning">
-    /// That call does not return till all tasks are finished theirs execution.
-    /// </div>
+    /// # Parameters
     ///
-    /// # Behavior
+    /// * `every_passes`:
+    ///   The number of passes between two calls of the callback.
+    /// * `cb`:
+    ///   A function pointer to a callback that takes a `&Stats` argument.
+    pub fn set_stats_callback(&mut self, every_passes: u32, cb: fn(&Stats)) {
+        self.stats_every = every_passes.max(1);
+        self.stats_callback = Some(cb);
+    }
+
+    /// Limits the number of tasks that run concurrently.
     ///
-    /// - Iterates over all tasks and attempts to poll each one.
-    /// - If a task is completed, it is removed from the tasks array.
-    /// - If all tasks have been removed (i.e., all tasks are `None`), the function returns.
-    pub fn run(&mut self) {
-        loop {
-            for i in 0..self.tasks.len() {
-                let should_remove = match self.tasks[i].as_mut() {
-                    Some(task) => poll_task(task, self.pending_callback),
-                    None => false,
-                };
-
-                if should_remove {
-                    self.tasks[i].take();
-                }
-            }
+    /// A task is in flight from its first poll until it leaves the executor, whether it is being
+    /// polled or waiting. A task is polled for the first time only while fewer than `limit` tasks
+    /// are in flight, so the rest are held back until one of the running tasks completes. Tasks
+    /// are started in the order of their slots, lowest first, so the `limit` tasks spawned first
+    /// run until one of them completes and only then the next task gets polled for the first
+    /// time. This keeps the resources used by the running tasks bounded even if more tasks are
+    /// spawned, and even while the running tasks wait for events.
+    ///
+    /// # Parameters
+    ///
+    /// * `limit`:
+    ///   The maximum number of tasks in flight. A limit of `0` is treated as `1`, as otherwise no
+    ///   task could ever make progress.
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        self.concurrency_limit = Some(limit.max(1));
+    }
 
-            if self.tasks.iter().all(Option::is_none) {
-                return;
-            }
-        }
+    /// Shuffles the order the tasks are polled in within each scheduling pass.
+    ///
+    /// By default each pass polls the tasks in the order of their slots, which is the order they
+    /// were spawned in. Correct programs must not depend on that order, and shuffling it helps to
+    /// find the ones that do. The order is drawn from a pseudo-random generator seeded with
+    /// `seed`, so a failing order can be reproduced with the same seed. Every live task is still
+    /// polled exactly once per pass.
+    ///
+    /// With a concurrency limit set by [`Executor::set_concurrency_limit`], the tasks coming first
+    /// in the shuffled order are started instead of the ones in the lowest slots.
+    ///
+    /// # Parameters
+    ///
+    /// * `seed`:
+    ///   The seed of the generator.
+    pub fn set_shuffle(&mut self, seed: u64) {
+        self.shuffle = Some(XorShift::new(seed));
     }
-}
 
-/// Polls a given task and optionally calls a callback function if the task is pending.
-///
-/// # Parameters
-///
-/// * `task`:
-///   A mutable reference to the task being polled.
-/// * `cb`:
-///   An optional callback function that takes a `&str` argument. This callback is invoked with the task's name if the task is pending.
-///
-/// # Returns
-///
-/// * `true` if the task has completed.
-/// * `false` if the task is still pending.
-fn poll_task(task: &mut StackBoxFuture, cb: Option<fn(&str)>) -> bool {
-    if let Some(future) = task.value.get_mut() {
-        let waker = create_waker();
-        let context = &mut Context::from_waker(&waker);
+    /// Sets the number of consecutive polls each task gets in its turn.
+    ///
+    /// By default a scheduling pass polls each task once and moves on to the next one. With a
+    /// quantum of `n`, a task that is still pending after a poll is polled again right away, up to
+    /// `n` times in a row, before the next task gets its turn. A task that has not completed
+    /// within its quantum continues in the next pass. This is round-robin time-slicing: the
+    /// quantum trades the latency of the other tasks for fewer switches between tasks.
+    ///
+    /// A task is only polled again within its quantum if it has woken itself during the previous
+    /// poll, e.g. with [`yield_me`](crate::helpers::yield_me). A task waiting for another task or
+    /// an event gives up the rest of its quantum.
+    ///
+    /// # Parameters
+    ///
+    /// * `quantum`:
+    ///   The number of consecutive polls. A quantum of `0` is treated as `1`, as otherwise no task
+    ///   could ever make progress.
+    pub fn set_quantum(&mut self, quantum: usize) {
+        self.quantum = quantum.max(1);
+    }
 
-        if matches!(future.as_mut().poll(context), Poll::Pending) {
-            if let Some(cb) = cb {
-                cb(future.name().unwrap_or(""));
-            }
-        } else {
-            return true;
-        }
+    /// Limits the number of times a single task may be polled during a single run.
+    ///
+    /// Unlike [`Executor::set_concurrency_limit`], which bounds the work of every pass, this
+    /// protects the run against a single pathological task that never completes: once a task has
+    /// been polled `limit` times since the start of [`Executor::run`] or
+    /// [`Executor::run_until_predicate`], the executor cancels it as a runaway at its next turn
+    /// and frees its slot. The handle of a cancelled task stays empty.
+    ///
+    /// # Parameters
+    ///
+    /// * `limit`:
+    ///   The maximum number of polls of each task in a single run.
+    /// * `on_runaway`:
+    ///   A callback invoked with the name of each cancelled task.
+    pub fn set_max_poll_per_task_per_run(&mut self, limit: u32, on_runaway: fn(&str)) {
+        self.max_polls_per_run = Some(limit);
+        self.on_runaway = Some(on_runaway);
     }
 
-    false
-}
+    /// Makes running the executor without any spawned task panic in debug builds.
+    ///
+    /// Running an empty executor is valid and returns right away, so by default it goes unnoticed.
+    /// Usually, though, it means that spawning the tasks was forgotten. With the check enabled,
+    /// [`Executor::run`] and [`Executor::run_until_predicate`] panic if no task is spawned. The
+    /// check is compiled out of release builds.
+    ///
+    /// # Parameters
+    ///
+    /// * `enabled`:
+    ///   Whether an empty run panics.
+    pub fn set_panic_on_empty_run(&mut self, enabled: bool) {
+        self.panic_on_empty_run = enabled;
+    }
 
-fn create_raw_waker() -> RawWaker {
-    unsafe fn clone(_: *const ()) -> RawWaker {
-        create_raw_waker()
+    /// Sets the buffer the executor records scheduling events into.
+    ///
+    /// Each spawn, poll and poll result is recorded as an [`Event`], building a trace of the run
+    /// that can be inspected with [`Executor::events`] afterwards.
+    ///
+    /// # Parameters
+    ///
+    /// * `buffer`:
+    ///   The storage for the events. Its previous content is discarded.
+    /// * `policy`:
+    ///   What to do with new events once the buffer is full.
+    pub fn set_event_log(&mut self, buffer: &'a mut [Option<Event>], policy: OverflowPolicy) {
+        self.event_log = Some(EventLog::new(buffer, policy));
     }
 
-    unsafe fn wake(_: *const ()) {}
+    /// Returns an iterator over the recorded scheduling events, from the oldest to the most recent
+    /// one.
+    ///
+    /// The iterator is empty if no event log is set with [`Executor::set_event_log`].
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.event_log.iter().flat_map(EventLog::iter)
+    }
 
-    unsafe fn wake_by_ref(_: *const ()) {}
+    /// Sets the queue the executor pushes task completions into.
+    ///
+    /// Each time a task completes, its [`TaskId`] and name are pushed into the queue as a
+    /// [`Completion`], in the order the tasks complete. Unlike the completion callback, the queue
+    /// lets the completions be handled outside of the pass they happen in, either by the caller
+    /// after a run or by another task holding a reference to the queue. Aborted and cancelled tasks
+    /// are not pushed. Once the queue is full, completions are dropped or overwrite the oldest ones
+    /// according to the policy of the queue.
+    ///
+    /// # Parameters
+    ///
+    /// * `queue`:
+    ///   A reference to the [`CompletionQueue`] to push the completions into.
+    pub fn set_completion_queue(&mut self, queue: &'a CompletionQueue<'a>) {
+        self.completion_queue = Some(queue);
+    }
 
-    unsafe fn drop(_: *const ()) {}
+    /// Sets the sink all metrics of the executor are pushed into.
+    ///
+    /// The sink receives a [`Metric`] for every spawn, poll, wake, completion, abort and
+    /// cancellation, timestamped with the time source of the executor if one is set. It reports
+    /// the same events as the individual callbacks and the event log through a single channel,
+    /// which the sink can route wherever it needs to.
+    ///
+    /// # Parameters
+    ///
+    /// * `telemetry`:
+    ///   A reference to the [`Telemetry`] sink.
+    pub fn set_telemetry(&mut self, telemetry: &'a dyn Telemetry) {
+        self.telemetry = Some(telemetry);
+    }
 
-    RawWaker::new(
-        ptr::null(),
-        &RawWakerVTable::new(clone, wake, wake_by_ref, drop),
-    )
-}
+    /// Sets the time source the executor uses to timestamp task polls.
+    ///
+    /// # Parameters
+    ///
+    /// * `source`:
+    ///   A reference to the [`TimeSource`] read right before each task poll.
+    pub fn set_time_source(&mut self, source: &'a dyn TimeSource) {
+        self.time_source = Some(source);
+    }
 
-fn create_waker() -> Waker {
-    let raw_waker = create_raw_waker();
+    /// Sets the reactor the executor consults for the readiness of I/O sources.
+    ///
+    /// A task waiting with [`wait_ready`](crate::reactor::wait_ready) is parked: its interest is
+    /// registered with the reactor and the task is skipped by the following passes until
+    /// [`Reactor::poll_ready`] reports the interest as satisfied. Without a reactor, parked tasks
+    /// are polled again on the next pass like any other task that has woken itself.
+    ///
+    /// # Parameters
+    ///
+    /// * `reactor`:
+    ///   A reference to the [`Reactor`] watching the I/O sources.
+    pub fn set_reactor(&mut self, reactor: &'a dyn Reactor) {
+        self.reactor = Some(reactor);
+    }
 
-    unsafe { Waker::from_raw(raw_waker) }
+    /// Sets the table tracking the wakes of the tasks, so only woken tasks are polled.
+    ///
+    /// Without a table, the executor cannot tell which tasks have been woken: every task is
+    /// polled on every pass, the hook set by [`Executor::set_idle_hook`] only runs while all tasks
+    /// wait for a reactor interest, a deadline or a dependency, and [`Executor::block_on`] polls
+    /// its future in a loop. The table lives in a `static`, as the wakers of the tasks may outlive
+    /// the executor, and must not be used by another executor at the same time.
+    ///
+    /// # Parameters
+    ///
+    /// * `table`:
+    ///   A reference to the [`WakeTable`] with a flag for every slot of the executor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    /// use miniloop::wake::WakeTable;
+    ///
+    /// static WAKES: WakeTable<1> = WakeTable::new();
+    ///
+    /// let mut executor = Executor::<1>::new();
+    /// executor.set_wake_table(&WAKES);
+    ///
+    /// let task = pin!(Task::new("task", async { 42 }));
+    /// let mut handle = Handle::new();
+    /// executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    /// executor.run();
+    /// assert_eq!(handle.value, Some(42));
+    /// ```
+    pub fn set_wake_table(&mut self, table: &'static WakeTable<TASK_ARRAY_SIZE>) {
+        self.wake_table = Some(table);
+
+        // the tasks spawned before are polled at least once to register their wakers
+        for index in 0..TASK_ARRAY_SIZE {
+            if self.tasks[index].is_some() {
+                table.slot(index).claim();
+            }
+        }
+    }
+
+    /// Sets a queue checked at the end of every scheduling pass, e.g. a
+    /// [`Channel`](crate::channel::Channel) warning about a consumer that stays behind, see
+    /// [`Channel::set_stuck_warning`](crate::channel::Channel::set_stuck_warning).
+    ///
+    /// # Parameters
+    ///
+    /// * `monitor`:
+    ///   A reference to the [`DepthMonitor`] to check.
+    pub fn set_depth_monitor(&mut self, monitor: &'a dyn DepthMonitor) {
+        self.depth_monitor = Some(monitor);
+    }
+
+    /// Installs a kill switch that cancels all tasks of the executor once set.
+    ///
+    /// The flag is checked at the start of each scheduling pass. Once it is set, all live tasks
+    /// are cancelled, leaving their handles empty, and [`Executor::run`] returns
+    /// [`RunStatus::Aborted`]. The tasks can hold a reference to the same flag to set it, or to
+    /// check it and stop cooperatively.
+    ///
+    /// The executor borrows the flag instead of owning it, as the flag is shared through a plain
+    /// reference without heap allocation: whoever decides about the shutdown, a task or the code
+    /// around the executor, only needs the flag to outlive the executor.
+    ///
+    /// # Parameters
+    ///
+    /// * `flag`:
+    ///   A reference to the [`AbortFlag`] to check.
+    pub fn set_abort_flag(&mut self, flag: &'a AbortFlag) {
+        self.abort_flag = Some(flag);
+    }
+
+    /// Returns the kill switch installed with [`Executor::set_abort_flag`], if any.
+    ///
+    /// The returned reference does not borrow the executor, so it can be handed to the code that
+    /// decides about the shutdown while the executor keeps running.
+    #[must_use]
+    pub fn abort_flag(&self) -> Option<&'a AbortFlag> {
+        self.abort_flag
+    }
+
+    /// Returns the tick the task with the given `id` sleeps until, if it has yielded with
+    /// [`yield_until_tick`](crate::time::yield_until_tick) and the tick has not been reached yet.
+    #[must_use]
+    pub fn deadline(&self, id: TaskId) -> Option<u64> {
+        self.deadlines
+            .get(id.index)
+            .copied()
+            .flatten()
+            .filter(|_| self.is_current(id))
+    }
+
+    /// Returns the tick at which the task with the given `id` was last polled.
+    ///
+    /// Comparing the tick with the current time shows how long a task has gone without being
+    /// polled, which helps to diagnose tasks starved by their siblings. The tick is kept after the
+    /// task completes.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(tick)` - the tick of the time source when the task was last polled.
+    /// * `None` - if the task has not been polled yet, no time source is set with
+    ///   [`Executor::set_time_source`], or another task has been spawned into the slot of `id`
+    ///   since.
+    #[must_use]
+    pub fn last_polled(&self, id: TaskId) -> Option<u64> {
+        self.last_polled
+            .get(id.index)
+            .copied()
+            .flatten()
+            .filter(|_| self.is_current(id))
+    }
+
+    /// Writes the number of polls of the task in each slot during the current or last run into
+    /// `out`.
+    ///
+    /// The counts start from zero on every call to [`Executor::run`] or
+    /// [`Executor::run_until_predicate`], so they show how the scheduler has divided the polls of
+    /// a run among the tasks. Comparing them makes fairness measurable: a run stopped midway
+    /// leaves the counts balanced under round-robin scheduling and skewed towards the tasks
+    /// spawned first under a [concurrency limit](Executor::set_concurrency_limit) of `1`, which
+    /// runs the tasks one after another.
+    ///
+    /// # Parameters
+    ///
+    /// * `out`:
+    ///   The buffer receiving the counts, indexed by slot. Only the first `out.len()` slots are
+    ///   written if the buffer is shorter than the tasks array, and the rest of a longer buffer is
+    ///   left untouched.
+    pub fn poll_histogram(&self, out: &mut [u32]) {
+        for (count, polls) in out.iter_mut().zip(self.polls_this_run) {
+            *count = polls;
+        }
+    }
+
+    /// Returns the number of times the task with the given `id` has yielded.
+    ///
+    /// A yield is a poll returning `Poll::Pending` after the task has woken itself, e.g. with
+    /// [`yield_me`](crate::helpers::yield_me): the task could go on right away but lets the other
+    /// tasks have their turn first. A poll returning `Poll::Pending` while the task waits for
+    /// another task, an event, a reactor interest or a deadline is not a yield. The count is kept
+    /// after the task completes. Without a table set by
+    /// [`Executor::set_wake_table`], the executor cannot tell the yields apart and counts none.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(count)` - the number of yields so far.
+    /// * `None` - if another task has been spawned into the slot of `id` since.
+    #[must_use]
+    pub fn yield_count(&self, id: TaskId) -> Option<u32> {
+        self.yields
+            .get(id.index)
+            .copied()
+            .filter(|_| self.is_current(id))
+    }
+
+    /// Returns the longest scheduling latency of the task with the given `id`, in ticks.
+    ///
+    /// The latency is the gap between the task becoming runnable and the executor actually
+    /// polling it. A task becomes runnable when it is spawned and when its waker is woken. The
+    /// executor notices a wake right after the poll of the task that woke it, or at the start of
+    /// the next pass for a wake from outside the executor, e.g. from an interrupt handler, and
+    /// takes the tick at that moment. A task parked on a [`Reactor`] becomes runnable once the
+    /// reactor reports its interest as satisfied, and a sleeping task at its deadline. The latency
+    /// thus measures how long the task waits for the other tasks to be polled, which quantifies
+    /// the responsiveness of the executor. The value is kept after the task completes.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(ticks)` - the longest latency measured so far.
+    /// * `None` - if the task has not been polled yet, no time source is set with
+    ///   [`Executor::set_time_source`], or another task has been spawned into the slot of `id`
+    ///   since.
+    #[must_use]
+    pub fn max_latency(&self, id: TaskId) -> Option<u64> {
+        self.max_latency
+            .get(id.index)
+            .copied()
+            .flatten()
+            .filter(|_| self.is_current(id))
+    }
+
+    /// Returns the label of the task with the given `id`.
+    ///
+    /// The label is attached with [`Task::instrument`] and lets the poll hooks set by
+    /// [`Executor::set_around_poll`], which only receive the [`TaskId`], categorize the task. The
+    /// label is kept after the task completes.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(label)` - the label of the task spawned into the slot last.
+    /// * `None` - if the task has no label, or another task has been spawned into the slot of
+    ///   `id` since.
+    #[must_use]
+    pub fn label(&self, id: TaskId) -> Option<&'static str> {
+        self.labels
+            .get(id.index)
+            .copied()
+            .flatten()
+            .filter(|_| self.is_current(id))
+    }
+
+    /// Returns the total time the executor spent polling the task with the given `id`.
+    ///
+    /// The time is accumulated around every poll of the task and is kept after the task completes,
+    /// which makes it easy to spot tasks that block the executor instead of yielding.
+    ///
+    /// Only available with the `std` feature.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(duration)` - the accumulated busy time of the task.
+    /// * `None` - if another task has been spawned into the slot of `id` since.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn busy_time(&self, id: TaskId) -> Option<Duration> {
+        self.busy_time
+            .get(id.index)
+            .copied()
+            .filter(|_| self.is_current(id))
+    }
+
+    /// Takes a snapshot of the observable scheduling state of the executor.
+    ///
+    /// The snapshot captures the metadata of the slots, not the futures, and does not borrow the
+    /// executor, so it can be compared with snapshots taken later in the run.
+    #[must_use]
+    pub fn snapshot(&self) -> SchedulerSnapshot<'a, TASK_ARRAY_SIZE> {
+        SchedulerSnapshot {
+            slots: core::array::from_fn(|index| SlotSnapshot {
+                occupied: self.tasks[index].is_some(),
+                name: self.names[index],
+                polls: self.polls[index],
+                last_polled: self.last_polled[index],
+            }),
+            next_slot: self.free_slot().unwrap_or(TASK_ARRAY_SIZE),
+            pass_count: self.pass_count,
+        }
+    }
+
+    /// Spawns a task into the first free executor slot and links it with the `handle` that
+    /// receives the task output.
+    ///
+    /// The task is passed pinned, e.g. with [`pin!`], which guarantees by construction that it
+    /// stays in place until it is dropped, even after the executor is gone. Futures that point
+    /// into themselves, like most `async` blocks, are therefore never moved between two polls.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slots in the executor. The error carries the name of
+    ///   the rejected task. Use [`Executor::try_spawn`] to get the task back instead.
+    pub fn spawn<F>(
+        &mut self,
+        task: Pin<&'a mut Task<'a, F>>,
+        handle: &'a mut Handle<F::Output>,
+    ) -> Result<TaskId, Error<'a>>
+    where
+        F: Future + 'a,
+    {
+        self.try_spawn(task, handle)
+            .map_err(|rejected| Error::NoFreeSlots {
+                name: rejected.task.name,
+            })
+    }
+
+    /// Spawns a task like [`Executor::spawn`], handing the task and its handle back if there is
+    /// no free slot.
+    ///
+    /// The task and the handle are borrowed for the lifetime of the executor, so a task rejected
+    /// by [`Executor::spawn`] cannot be used anymore. The rejected task is returned untouched
+    /// instead, not linked with the handle, so it can be spawned into another executor or spawned
+    /// again once a slot is free.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * [`Rejected`] - if there is no free slot in the executor, with the task and the handle.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    ///
+    /// let mut primary = Executor::<0>::new();
+    /// let mut fallback = Executor::<1>::new();
+    /// let task = pin!(Task::new("task", async { 42 }));
+    /// let mut handle = Handle::new();
+    ///
+    /// if let Err(rejected) = primary.try_spawn(task, &mut handle) {
+    ///     fallback
+    ///         .spawn(rejected.task, rejected.handle)
+    ///         .expect("Failed to spawn task");
+    /// }
+    ///
+    /// fallback.run();
+    /// assert_eq!(handle.value, Some(42));
+    /// ```
+    pub fn try_spawn<F>(
+        &mut self,
+        mut task: Pin<&'a mut Task<'a, F>>,
+        handle: &'a mut Handle<F::Output>,
+    ) -> Result<TaskId, Rejected<'a, F>>
+    where
+        F: Future + 'a,
+    {
+        if self.free_slot().is_none() {
+            return Err(Rejected { task, handle });
+        }
+
+        self.debug_assert_not_spawned(ptr::from_ref(&*task).cast());
+        task.as_mut().link_handle(handle);
+
+        Ok(self.insert(task.name, StackBox::new(task)))
+    }
+
+    /// Spawns a task into the first free executor slot as a member of the given `group`.
+    ///
+    /// The task runs like any other spawned task, and in addition it can be cancelled together
+    /// with the rest of its group by [`Executor::cancel_group`]. Groups are identified by numbers
+    /// chosen by the caller.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slots in the executor. The error carries the name of
+    ///   the rejected task.
+    pub fn spawn_in_group<F>(
+        &mut self,
+        group: usize,
+        task: Pin<&'a mut Task<'a, F>>,
+        handle: &'a mut Handle<F::Output>,
+    ) -> Result<TaskId, Error<'a>>
+    where
+        F: Future + 'a,
+    {
+        let id = self.spawn(task, handle)?;
+        self.groups[id.index] = Some(group);
+
+        Ok(id)
+    }
+
+    /// Spawns a task into the first free executor slot that is not polled until the task `dep`
+    /// has left the executor.
+    ///
+    /// The executor skips the new task on every pass while `dep` occupies its slot, so the stages
+    /// of a pipeline can run in order without awaiting each other. If `dep` has already completed,
+    /// the new task runs right away. A dependency leaves the executor by being cancelled or
+    /// aborting itself as well, and the new task is unblocked then too: check the handle of the
+    /// dependency if the outcome matters.
+    ///
+    /// # Parameters
+    ///
+    /// * `dep`:
+    ///   The task the new task waits for.
+    /// * `task`:
+    ///   The task to spawn.
+    /// * `handle`:
+    ///   The handle receiving the output of the task.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slots in the executor. The error carries the name of
+    ///   the rejected task.
+    pub fn spawn_after<F>(
+        &mut self,
+        dep: TaskId,
+        task: Pin<&'a mut Task<'a, F>>,
+        handle: &'a mut Handle<F::Output>,
+    ) -> Result<TaskId, Error<'a>>
+    where
+        F: Future + 'a,
+    {
+        let id = self.spawn(task, handle)?;
+
+        if self.is_live(dep) {
+            self.blocked_on[id.index] = Some(dep);
+        }
+
+        Ok(id)
+    }
+
+    /// Spawns a task into the first free executor slot, returning a guard that cancels the task
+    /// when dropped.
+    ///
+    /// The guard and the executor share the `cancel` flag, so the guard does not borrow the
+    /// executor. Dropping the guard sets the flag, and the executor removes the task before it
+    /// would be polled next, leaving its handle empty. A task whose guard is dropped before
+    /// [`Executor::run`] is therefore never polled at all.
+    ///
+    /// # Parameters
+    ///
+    /// * `task`:
+    ///   The task to spawn.
+    /// * `handle`:
+    ///   The handle receiving the output of the task.
+    /// * `cancel`:
+    ///   The flag shared with the returned guard. It is cleared on spawn.
+    ///
+    /// # Returns
+    ///
+    /// A [`SpawnGuard`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slots in the executor. The error carries the name of
+    ///   the rejected task.
+    pub fn spawn_guarded<F>(
+        &mut self,
+        task: Pin<&'a mut Task<'a, F>>,
+        handle: &'a mut Handle<F::Output>,
+        cancel: &'a Cell<bool>,
+    ) -> Result<SpawnGuard<'a>, Error<'a>>
+    where
+        F: Future + 'a,
+    {
+        let id = self.spawn(task, handle)?;
+        cancel.set(false);
+        self.cancel_flags[id.index] = Some(cancel);
+
+        Ok(SpawnGuard { id, cancel })
+    }
+
+    /// Cancels the task with the given `id` before it completes.
+    ///
+    /// The task is removed from its slot and never polled again, so its handle stays empty. Its
+    /// future is dropped right away, which releases the resources it holds, e.g. a
+    /// [`SemaphorePermit`](crate::sync::SemaphorePermit) held across an `.await`. The task itself
+    /// stays borrowed by the executor, so the cancelled task cannot be spawned again.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the task has been cancelled.
+    /// * `false` if the task has already left the executor.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    /// use miniloop::helpers::never;
+    ///
+    /// let task = pin!(Task::new("stuck", never::<()>()));
+    /// let mut handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    /// let id = executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    ///
+    /// assert!(executor.cancel(id));
+    /// assert!(!executor.cancel(id));
+    /// executor.run();
+    /// assert_eq!(handle.value, None);
+    /// ```
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        self.is_live(id) && self.cancel_slot(id.index)
+    }
+
+    /// Cancels all live tasks of the given `group`.
+    ///
+    /// The tasks are removed from their slots and never polled again, so their handles stay
+    /// empty, and their futures are dropped like by [`Executor::cancel`]. Tasks of other groups
+    /// and tasks spawned without a group keep running.
+    ///
+    /// # Returns
+    ///
+    /// The number of cancelled tasks.
+    pub fn cancel_group(&mut self, group: usize) -> usize {
+        let mut cancelled = 0;
+
+        for index in 0..self.tasks.len() {
+            if self.groups[index] == Some(group) && self.cancel_slot(index) {
+                cancelled += 1;
+            }
+        }
+
+        cancelled
+    }
+
+    /// Spawns a task with its future type erased into the first free executor slot.
+    ///
+    /// The task is already linked with its handle by [`Task::erase`].
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slots in the executor. The error carries the name of
+    ///   the rejected task.
+    pub fn spawn_erased(&mut self, task: ErasedTask<'a>) -> Result<TaskId, Error<'a>> {
+        if self.free_slot().is_none() {
+            return Err(Error::NoFreeSlots { name: task.name() });
+        }
+
+        Ok(self.insert_erased(task))
+    }
+
+    /// Spawns a group of tasks with their future types erased, either all of them or none.
+    ///
+    /// Spawning interdependent tasks one by one may fail halfway, leaving part of the group
+    /// spawned. This method checks that there are enough free slots for the whole group first and
+    /// only then spawns the tasks, in order, so a failure leaves the executor unchanged.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`]s of the spawned tasks, in the order of `tasks`.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there are not enough free slots for all the tasks. The error carries
+    ///   the name of the first task that would not fit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    /// let producer = pin!(Task::new("producer", async { 1 }));
+    /// let mut producer_handle = Handle::new();
+    /// let consumer = pin!(Task::new("consumer", async {}));
+    /// let mut consumer_handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    ///
+    /// let result = executor.spawn_atomic([
+    ///     producer.erase(&mut producer_handle),
+    ///     consumer.erase(&mut consumer_handle),
+    /// ]);
+    /// assert!(result.is_err());
+    /// assert_eq!(executor.snapshot().next_slot, 0);
+    /// ```
+    pub fn spawn_atomic<const GROUP_SIZE: usize>(
+        &mut self,
+        tasks: [ErasedTask<'a>; GROUP_SIZE],
+    ) -> Result<[TaskId; GROUP_SIZE], Error<'a>> {
+        let free = TASK_ARRAY_SIZE - self.len();
+
+        if let Some(rejected) = tasks.get(free) {
+            return Err(Error::NoFreeSlots {
+                name: rejected.name(),
+            });
+        }
+
+        Ok(tasks.map(|task| self.insert_erased(task)))
+    }
+
+    /// Stores the erased `task` into the first free slot.
+    fn insert_erased(&mut self, task: ErasedTask<'a>) -> TaskId {
+        let name = task.name();
+        let task = task.into_inner();
+
+        if let Some(future) = task.value.get() {
+            self.debug_assert_not_spawned(ptr::from_ref::<dyn TaskFuture>(&**future).cast());
+        }
+
+        self.insert(name, task)
+    }
+
+    /// Returns the identifier of the task spawned into the slot with the given `index` last.
+    fn id(&self, index: usize) -> TaskId {
+        TaskId {
+            index,
+            generation: self.generations[index],
+        }
+    }
+
+    /// Checks whether `id` refers to the task spawned into its slot last, even if the task has
+    /// left the executor since.
+    fn is_current(&self, id: TaskId) -> bool {
+        self.generations.get(id.index) == Some(&id.generation)
+    }
+
+    /// Checks whether the task with the given `id` is still in the executor.
+    fn is_live(&self, id: TaskId) -> bool {
+        self.is_current(id) && self.is_occupied(id.index)
+    }
+
+    /// Checks whether the slot with the given `index` holds a task, including the task taken out
+    /// of its slot while it is polled.
+    fn is_occupied(&self, index: usize) -> bool {
+        self.tasks[index].is_some() || self.polling == Some(index)
+    }
+
+    /// Returns the index of the first free slot, if any.
+    ///
+    /// Slots are freed as soon as their tasks leave the executor, so they are reused by later
+    /// spawns.
+    fn free_slot(&self) -> Option<usize> {
+        self.tasks.iter().position(Option::is_none)
+    }
+
+    /// Stores the `task` into the first free slot and resets the statistics of the slot.
+    fn insert(&mut self, name: Option<&'a str>, task: StackBoxFuture<'a>) -> TaskId {
+        let index = self
+            .free_slot()
+            .expect("a free slot is checked for before inserting");
+
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.tasks[index] = Some(task);
+        if let Some(flag) = self.wake_flag(index) {
+            flag.claim();
+        }
+        self.names[index] = name;
+        self.labels[index] = self.tasks[index]
+            .as_ref()
+            .and_then(|task| task.value.get())
+            .and_then(|future| future.label());
+        self.groups[index] = None;
+        self.blocked_on[index] = None;
+        self.cancel_flags[index] = None;
+        self.polls[index] = 0;
+        self.last_polled[index] = None;
+        self.runnable_since[index] = self.time_source.map(TimeSource::now);
+        self.max_latency[index] = None;
+        self.polls_this_run[index] = 0;
+        self.yields[index] = 0;
+        self.parked[index] = None;
+        self.deadlines[index] = None;
+        self.record(EventKind::Spawn, index);
+        #[cfg(feature = "std")]
+        {
+            self.busy_time[index] = Duration::ZERO;
+        }
+
+        self.id(index)
+    }
+    /// Blocks on the provided future until it is completed.
+    ///
+    /// This method will drive the given future to completion, blocking the
+    /// current thread during the process. It is useful for running a single
+    /// future to completion in a synchronous context.
+    ///
+    /// # Parameters
+    ///
+    /// * `future` - The future to be executed until completion. The future
+    ///   must implement [`Future`], and its output must match the type `T`.
+    ///
+    /// # Returns
+    ///
+    /// This function will return the output of the provided future once it
+    /// is resolved.
+    ///
+    /// With a hook set by [`Executor::set_idle_hook`], the hook is invoked after each poll that
+    /// returns `Poll::Pending` without the future having been woken. Otherwise, or without a table
+    /// set by [`Executor::set_wake_table`] to track the wake, the future is polled again right
+    /// away.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// const TASK_ARRAY_SIZE: usize = 1;
+    /// let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
+    /// let result = executor.block_on(async { 42 });
+    /// assert_eq!(result, 42);
+    /// ```
+    pub fn block_on<F, T>(&mut self, future: F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        let Some(idle) = self.idle_hook else {
+            return block_on(future);
+        };
+        // the flag belongs to this executor alone, so a call on another executor nested in the
+        // future cannot clear a wake meant for this one
+        let Some(table) = self.wake_table else {
+            return block_on(future);
+        };
+        let flag = table.block_on();
+        flag.claim();
+
+        // SAFETY: the data of a flag waker is the waker data of a wake flag.
+        let waker = unsafe { Waker::from_raw(RawWaker::new(flag.waker_data(), &FLAG_VTABLE)) };
+        let mut future = pin!(future);
+        let mut ctx = Context::from_waker(&waker);
+
+        loop {
+            flag.clear();
+
+            if let Poll::Ready(val) = future.as_mut().poll(&mut ctx) {
+                return val;
+            }
+
+            if !flag.is_woken() {
+                idle();
+            }
+        }
+    }
+
+    /// Executes tasks in the executor until all tasks are completed.
+    ///
+    /// The method repeatedly polls each task in the tasks array. If a task completes, it is removed from the array.
+    /// The function keeps running until all tasks are either completed or removed from the tasks array.
+    ///
+    /// <div class="warning">
+    /// That call does not return till all tasks are finished theirs execution.
+    /// </div>
+    ///
+    /// # Behavior
+    ///
+    /// - Iterates over all tasks and attempts to poll each one. With a concurrency limit set by
+    ///   [`Executor::set_concurrency_limit`] the tasks not started yet are held back while the
+    ///   limit of tasks is in flight.
+    /// - Only polls the tasks woken since their last poll. A task returning `Poll::Pending`
+    ///   without waking itself, e.g. with [`yield_me`](crate::helpers::yield_me), is left alone
+    ///   until the waker of its [`Context`] is woken, by another task or an interrupt handler.
+    ///   The wakes are tracked in the table set by [`Executor::set_wake_table`]. Without one,
+    ///   every task is polled on every pass.
+    /// - If no task is left woken after a pass, the hook set by [`Executor::set_idle_hook`] is
+    ///   invoked before the next pass.
+    /// - If a task is completed, it is removed from the tasks array.
+    /// - If all tasks have been removed (i.e., all tasks are `None`), the function returns.
+    /// - If the hook set by [`Executor::set_debugger`] halts the run, the function returns right
+    ///   away, leaving the remaining tasks in their slots.
+    /// - If the [abort flag](Executor::set_abort_flag) is set, the function cancels all tasks and
+    ///   returns.
+    ///
+    /// # Returns
+    ///
+    /// The [`RunStatus`] of the last pass:
+    ///
+    /// * `RunStatus::AllComplete` - if all tasks have completed.
+    /// * `RunStatus::Pending` - if the debugger hook has halted the run.
+    /// * `RunStatus::Aborted` - if the abort flag has been set, with the number of tasks it
+    ///   cancelled.
+    pub fn run(&mut self) -> RunStatus {
+        self.polls_this_run.fill(0);
+
+        loop {
+            let status = self.run_once();
+
+            if self.take_halted() || !matches!(status, RunStatus::Pending { .. }) {
+                return status;
+            }
+
+            if let Some(idle) = self.idle_hook
+                && !self.has_runnable()
+            {
+                idle();
+            }
+        }
+    }
+
+    /// Makes a single scheduling pass over the tasks and returns right after.
+    ///
+    /// Each woken task is polled once, or up to its [quantum](Executor::set_quantum), and the
+    /// completed tasks are removed. This lets the executor be interleaved with other work in a
+    /// super-loop instead of blocking in [`Executor::run`]. Unlike `run`, the method does not
+    /// restart the counts of [`Executor::poll_histogram`], so they add up over consecutive calls.
+    ///
+    /// # Returns
+    ///
+    /// * `RunStatus::Pending` - if tasks remain after the pass, with their number and the number
+    ///   of tasks completed during the pass.
+    /// * `RunStatus::AllComplete` - if no tasks remain after the pass.
+    /// * `RunStatus::Aborted` - if the [abort flag](Executor::set_abort_flag) is set, with the
+    ///   number of tasks the pass cancelled instead of polling them.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use miniloop::executor::{Executor, RunStatus};
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    /// use miniloop::helpers::yield_me;
+    ///
+    /// let task = pin!(Task::new("task", async { yield_me().await }));
+    /// let mut handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    /// executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    ///
+    /// loop {
+    ///     if let RunStatus::AllComplete { .. } = executor.run_once() {
+    ///         break;
+    ///     }
+    ///     // other bare-metal work
+    /// }
+    /// assert_eq!(handle.value, Some(()));
+    /// ```
+    pub fn run_once(&mut self) -> RunStatus {
+        self.debug_assert_not_empty();
+        // a halt left over by a previous call does not concern this pass
+        self.halted = false;
+
+        let before = self.completion_count;
+        let live = self.len();
+
+        if self.run_pass() {
+            return RunStatus::Aborted { cancelled: live };
+        }
+
+        // at most one task completes per slot and pass, so the difference fits into `usize`
+        #[allow(clippy::cast_possible_truncation)]
+        let completed = (self.completion_count - before) as usize;
+        let remaining = self.len();
+
+        if remaining == 0 {
+            RunStatus::AllComplete { completed }
+        } else {
+            RunStatus::Pending {
+                remaining,
+                completed,
+            }
+        }
+    }
+
+    /// Executes tasks in the executor until `pred` holds for `state` or all tasks are completed.
+    ///
+    /// A spawned task borrows its [`Handle`] mutably until the executor is dropped, so the
+    /// progress of the tasks cannot be observed through their handles while they run. Instead,
+    /// the tasks publish their progress into a shared `state`, e.g. a [`Cell`](core::cell::Cell),
+    /// and `pred` decides whether the executor may stop.
+    ///
+    /// `pred` is checked before each scheduling pass, so the remaining tasks are left in their
+    /// slots and a later call to [`Executor::run`] continues them.
+    ///
+    /// # Parameters
+    ///
+    /// * `state`:
+    ///   The state shared with the tasks.
+    /// * `pred`:
+    ///   The condition to stop at.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if `pred` holds for `state`.
+    /// * `false` if all tasks have completed, or the run has been halted by the
+    ///   [debugger hook](Executor::set_debugger), and `pred` still does not hold.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::cell::Cell;
+    /// use core::pin::pin;
+    /// use miniloop::helpers::yield_me;
+    ///
+    /// let progress = Cell::new(0);
+    /// let task = pin!(Task::new("counter", async {
+    ///     loop {
+    ///         progress.set(progress.get() + 1);
+    ///         yield_me().await;
+    ///     }
+    /// }));
+    /// let mut handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    /// executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    ///
+    /// assert!(executor.run_until_predicate(&progress, |progress| progress.get() >= 3));
+    /// assert_eq!(progress.get(), 3);
+    /// ```
+    pub fn run_until_predicate<T: ?Sized>(&mut self, state: &T, pred: fn(&T) -> bool) -> bool {
+        self.debug_assert_not_empty();
+        self.polls_this_run.fill(0);
+
+        loop {
+            if pred(state) {
+                return true;
+            }
+
+            if self.tasks.iter().all(Option::is_none) {
+                return false;
+            }
+
+            self.run_pass();
+
+            if self.take_halted() {
+                return pred(state);
+            }
+        }
+    }
+
+    /// Executes tasks in the executor until all tasks are completed, letting `sleep` idle the
+    /// system whenever no task can make progress.
+    ///
+    /// After each scheduling pass, the executor checks whether any task can be polled right away.
+    /// Tasks sleeping until a tick with [`yield_until_tick`](crate::time::yield_until_tick) wait
+    /// for the [time source](Executor::set_time_source), tasks parked on the
+    /// [reactor](Executor::set_reactor) wait for their interest, and tasks spawned with
+    /// [`Executor::spawn_after`] wait for their dependency. If all live tasks wait, `sleep` is
+    /// called with the number of ticks until the earliest deadline, or `None` if no task waits for
+    /// a deadline and only an external event can wake the tasks. The caller can put the MCU to
+    /// sleep for exactly that long, e.g. with a timer interrupt, and the next pass is made once
+    /// `sleep` returns. Waking up early is fine, as the waiting tasks are simply skipped again.
+    ///
+    /// # Parameters
+    ///
+    /// * `sleep`:
+    ///   The function idling the system, receiving the number of ticks until the next deadline.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::cell::Cell;
+    /// use core::pin::pin;
+    /// use miniloop::time::yield_until_tick;
+    ///
+    /// let clock = Cell::new(0);
+    /// let now = || clock.get();
+    /// let task = pin!(Task::new("blink", async { yield_until_tick(10).await }));
+    /// let mut handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    /// executor.set_time_source(&now);
+    /// executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    ///
+    /// executor.run_parked(|ticks| {
+    ///     assert_eq!(ticks, Some(10));
+    ///     // sleep until the timer fires
+    ///     clock.set(clock.get() + 10);
+    /// });
+    /// assert_eq!(handle.value, Some(()));
+    /// ```
+    pub fn run_parked(&mut self, mut sleep: impl FnMut(Option<u64>)) {
+        self.debug_assert_not_empty();
+        self.polls_this_run.fill(0);
+
+        loop {
+            self.run_pass();
+
+            if self.take_halted() || self.tasks.iter().all(Option::is_none) {
+                return;
+            }
+
+            if !self.has_runnable() {
+                sleep(self.ticks_to_next_deadline());
+            }
+        }
+    }
+
+    /// Turns the executor into a future that can be spawned as a task of a parent executor.
+    ///
+    /// Each poll of the future makes a single scheduling pass over the tasks of this executor, so
+    /// its tasks share the time of one parent task and are scheduled among themselves by the
+    /// settings of this executor. The future completes like [`Executor::run`] returns: once all
+    /// tasks are completed or the debugger halts the run.
+    ///
+    /// The future borrows this executor, so its tasks and their handles have to outlive the
+    /// parent executor. Declaring the child executor before the parent one is enough for that.
+    ///
+    /// # Returns
+    ///
+    /// A future driving this executor.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    ///
+    /// let sensor = pin!(Task::new("sensor", async { 21 }));
+    /// let mut sensor_handle = Handle::new();
+    /// let mut child = Executor::<2>::new();
+    /// child.spawn(sensor, &mut sensor_handle).expect("Failed to spawn task");
+    ///
+    /// let group = pin!(Task::new("sensors", child.nested()));
+    /// let mut group_handle = Handle::new();
+    /// let mut parent = Executor::<1>::new();
+    /// parent.spawn(group, &mut group_handle).expect("Failed to spawn task");
+    /// parent.run();
+    ///
+    /// assert_eq!(sensor_handle.value, Some(21));
+    /// ```
+    pub fn nested(&mut self) -> Nested<'_, 'a, TASK_ARRAY_SIZE> {
+        Nested { executor: self }
+    }
+
+    /// Runs the task with the given `id` to its next yield point while the other tasks stay
+    /// frozen.
+    ///
+    /// A single poll already runs a task until it either completes or reaches an `.await` that
+    /// cannot make progress, so the task is polled exactly once. This keeps the step bounded even
+    /// for tasks that wake themselves and would be ready to be polled again right away.
+    ///
+    /// The step does not count as a scheduling pass, see [`Executor::pass_count`].
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Pending` if the task has reached a yield point.
+    /// * `Poll::Ready(())` if the task has completed and its slot is freed, or if `id` does not
+    ///   refer to a spawned task of this executor anymore.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::task::{Handle, Task};
+    /// use core::pin::pin;
+    /// use core::task::Poll;
+    /// use miniloop::helpers::yield_me;
+    ///
+    /// let task = pin!(Task::new("task", yield_me()));
+    /// let mut handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    /// let id = executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    ///
+    /// assert_eq!(executor.step_task_until_pending(id), Poll::Pending);
+    /// assert_eq!(executor.step_task_until_pending(id), Poll::Ready(()));
+    /// ```
+    pub fn step_task_until_pending(&mut self, id: TaskId) -> Poll<()> {
+        if !self.is_live(id) {
+            return Poll::Ready(());
+        }
+
+        if self.poll_slot(id.index) {
+            self.remove(id.index);
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Makes a single scheduling pass over the tasks array, removing the completed tasks.
+    ///
+    /// If the abort flag is set, all tasks are cancelled instead.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the pass has cancelled the tasks for the abort flag.
+    /// * `false` otherwise.
+    fn run_pass(&mut self) -> bool {
+        self.pass_count += 1;
+
+        if self.abort_flag.is_some_and(AbortFlag::is_aborted) {
+            for index in 0..TASK_ARRAY_SIZE {
+                self.cancel_slot(index);
+            }
+
+            return true;
+        }
+
+        if let Some(kick) = self.watchdog_kick {
+            kick();
+        }
+
+        // tasks woken from outside the executor since the last poll
+        self.stamp_wakes();
+
+        let mut order: [usize; TASK_ARRAY_SIZE] = core::array::from_fn(|i| i);
+
+        if let Some(rng) = self.shuffle.as_mut() {
+            // Fisher-Yates shuffle
+            for i in (1..order.len()).rev() {
+                order.swap(i, rng.below(i + 1));
+            }
+        }
+
+        for i in order {
+            if self.tasks[i].is_none() {
+                continue;
+            }
+
+            if self.cancel_flags[i].is_some_and(Cell::get) {
+                self.cancel_slot(i);
+                continue;
+            }
+
+            if self.polls[i] == 0
+                && self
+                    .concurrency_limit
+                    .is_some_and(|limit| self.started() >= limit)
+            {
+                continue;
+            }
+
+            if let Some(dep) = self.blocked_on[i] {
+                if self.is_live(dep) {
+                    continue;
+                }
+
+                self.blocked_on[i] = None;
+            }
+
+            if let Some(interest) = self.parked[i] {
+                if self
+                    .reactor
+                    .is_some_and(|reactor| !reactor.poll_ready(interest))
+                {
+                    continue;
+                }
+
+                self.parked[i] = None;
+                self.runnable_since[i] = self.time_source.map(TimeSource::now);
+                self.report(Metric::Woken {
+                    id: self.id(i),
+                    tick: self.runnable_since[i],
+                });
+            }
+
+            if let Some(deadline) = self.deadlines[i]
+                && let Some(source) = self.time_source
+            {
+                if !is_reached(source.now(), deadline) {
+                    continue;
+                }
+
+                self.deadlines[i] = None;
+                self.runnable_since[i] = Some(deadline);
+                self.report(Metric::Woken {
+                    id: self.id(i),
+                    tick: Some(source.now()),
+                });
+            }
+
+            if !self.is_woken(i) {
+                continue;
+            }
+
+            if self
+                .max_polls_per_run
+                .is_some_and(|limit| self.polls_this_run[i] >= limit)
+            {
+                self.cancel_runaway(i);
+                continue;
+            }
+
+            self.run_quantum(i);
+
+            if self.halted {
+                break;
+            }
+        }
+
+        if let Some(monitor) = self.depth_monitor {
+            monitor.end_pass();
+        }
+
+        if let Some(cb) = self.stats_callback
+            && self.pass_count.is_multiple_of(u64::from(self.stats_every))
+        {
+            cb(&self.stats());
+        }
+
+        false
+    }
+}
+
+impl<'a, const TASK_ARRAY_SIZE: usize> Executor<'a, TASK_ARRAY_SIZE> {
+    /// Checks in debug builds that the task at the `task` address is not spawned into a live slot.
+    ///
+    /// The borrow checker rejects spawning the same `&mut Task` twice, but the check catches
+    /// aliasing introduced through raw pointers in `unsafe` code: two slots polling the same
+    /// future would alias it mutably.
+    pub(crate) fn debug_assert_not_spawned(&self, task: *const ()) {
+        debug_assert!(
+            !self
+                .tasks
+                .iter()
+                .flatten()
+                .filter_map(|spawned| spawned.value.get())
+                .any(|spawned| ptr::addr_eq(ptr::from_ref::<dyn TaskFuture>(&**spawned), task)),
+            "the task is already spawned into the executor"
+        );
+    }
+
+    /// Sets the generation of the slot with the given `index`, so tests can reach the wrap-around
+    /// of the counter without spawning 2<sup>32</sup> tasks.
+    #[cfg(test)]
+    pub(crate) fn set_generation(&mut self, index: usize, generation: u32) {
+        self.generations[index] = generation;
+    }
+
+    /// Clears the flag set by a debugger hook halting the run.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the run has been halted.
+    /// * `false` otherwise.
+    fn take_halted(&mut self) -> bool {
+        core::mem::take(&mut self.halted)
+    }
+
+    /// Polls the task in the slot with the given `index` for up to `quantum` consecutive polls,
+    /// as long as the task completes no poll and wakes itself after each one.
+    fn run_quantum(&mut self, index: usize) {
+        for _ in 0..self.quantum {
+            if !self.is_woken(index) {
+                break;
+            }
+
+            match self
+                .debugger
+                .map(|hook| hook(self.id(index), self.names[index].unwrap_or("")))
+            {
+                Some(Control::Skip) => break,
+                Some(Control::Halt) => {
+                    self.halted = true;
+                    break;
+                }
+                Some(Control::Continue) | None => {}
+            }
+
+            if self.poll_slot(index) {
+                self.remove(index);
+                break;
+            }
+        }
+    }
+
+    /// Removes the task from the slot with the given `index`. The wakers the task leaves behind
+    /// are ignored once the next task spawned into the slot claims its wake flag.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(task)` with the removed task.
+    /// * `None` if the slot is free.
+    fn remove(&mut self, index: usize) -> Option<StackBoxFuture<'a>> {
+        self.tasks[index].take()
+    }
+
+    /// Returns the wake flag of the slot with the given `index`, `None` without a wake table.
+    fn wake_flag(&self, index: usize) -> Option<&'static WakeFlag> {
+        self.wake_table.map(|table| table.slot(index))
+    }
+
+    /// Checks whether the task in the slot with the given `index` has been woken since its last
+    /// poll. Free slots are never woken, and without a wake table every task always is.
+    fn is_woken(&self, index: usize) -> bool {
+        self.is_occupied(index) && self.wake_flag(index).is_none_or(WakeFlag::is_woken)
+    }
+
+    /// Records the current tick as the moment each task woken since its last poll has become
+    /// runnable, unless the task already waits to be polled or waits for a reactor interest or a
+    /// deadline, which make it runnable on their own.
+    fn stamp_wakes(&mut self) {
+        let Some(source) = self.time_source else {
+            return;
+        };
+        let mut now = None;
+
+        for i in 0..TASK_ARRAY_SIZE {
+            if self.runnable_since[i].is_none()
+                && self.parked[i].is_none()
+                && self.deadlines[i].is_none()
+                && self.is_woken(i)
+            {
+                self.runnable_since[i] = Some(*now.get_or_insert_with(|| source.now()));
+            }
+        }
+    }
+
+    /// Checks whether a live task can be polled right away rather than waiting for a wake, a
+    /// dependency, a reactor interest or a deadline.
+    fn has_runnable(&self) -> bool {
+        (0..TASK_ARRAY_SIZE).any(|i| {
+            if self.tasks[i].is_none() {
+                return false;
+            }
+
+            if self.cancel_flags[i].is_some_and(Cell::get) {
+                return true;
+            }
+
+            if self.blocked_on[i].is_some_and(|dep| self.is_live(dep)) {
+                return false;
+            }
+
+            if let Some(interest) = self.parked[i]
+                && let Some(reactor) = self.reactor
+            {
+                return reactor.poll_ready(interest);
+            }
+
+            self.is_ready(i)
+        })
+    }
+
+    /// Returns the number of tasks in flight, i.e. the live tasks polled at least once.
+    fn started(&self) -> usize {
+        (0..TASK_ARRAY_SIZE)
+            .filter(|&i| self.is_occupied(i) && self.polls[i] > 0)
+            .count()
+    }
+
+    /// Checks whether the task in the slot with the given `index` would be polled on its turn
+    /// without consulting the reactor: it has been woken, is not held back by the concurrency
+    /// limit and waits neither for a live dependency, a reactor interest nor a deadline that has
+    /// not been reached yet.
+    fn is_ready(&self, index: usize) -> bool {
+        self.is_woken(index)
+            && (self.polls[index] > 0
+                || self
+                    .concurrency_limit
+                    .is_none_or(|limit| self.started() < limit))
+            && !self.blocked_on[index].is_some_and(|dep| self.is_live(dep))
+            && self.parked[index].is_none()
+            && self.deadlines[index].is_none_or(|deadline| {
+                self.time_source
+                    .is_none_or(|source| is_reached(source.now(), deadline))
+            })
+    }
+
+    /// Returns the number of ticks until the earliest deadline of the live tasks, if any task
+    /// sleeps until a tick.
+    fn ticks_to_next_deadline(&self) -> Option<u64> {
+        let now = self.time_source?.now();
+
+        self.tasks
+            .iter()
+            .zip(self.deadlines)
+            .filter(|(task, _)| task.is_some())
+            .filter_map(|(_, deadline)| deadline)
+            .map(|deadline| deadline.wrapping_sub(now))
+            .min()
+    }
+
+    /// Checks in debug builds that a run has tasks to run if empty runs are reported.
+    fn debug_assert_not_empty(&self) {
+        debug_assert!(
+            !self.panic_on_empty_run || self.tasks.iter().any(Option::is_some),
+            "the executor is run without any spawned task"
+        );
+    }
+
+    /// Records a scheduling event for the slot with the given `index` into the event log and the
+    /// telemetry sink, if they are set.
+    fn record(&mut self, kind: EventKind, index: usize) {
+        let id = self.id(index);
+
+        if let Some(log) = self.event_log.as_mut() {
+            log.push(Event {
+                kind,
+                id,
+                label: self.labels[index],
+                pass: self.pass_count,
+            });
+        }
+
+        let tick = self.time_source.map(TimeSource::now);
+
+        match kind {
+            EventKind::Spawn => self.report(Metric::Spawned { id, tick }),
+            EventKind::Poll => self.report(Metric::PollStarted { id, tick }),
+            EventKind::Pending => self.report(Metric::PollEnded { id, tick }),
+            EventKind::Complete => {
+                self.report(Metric::PollEnded { id, tick });
+                self.report(Metric::Completed { id, tick });
+            }
+            EventKind::Abort => {
+                self.report(Metric::PollEnded { id, tick });
+                self.report(Metric::Aborted { id, tick });
+            }
+            EventKind::Cancel => self.report(Metric::Cancelled { id, tick }),
+        }
+    }
+
+    /// Pushes `metric` into the telemetry sink if one is set.
+    fn report(&self, metric: Metric) {
+        if let Some(telemetry) = self.telemetry {
+            telemetry.record(metric);
+        }
+    }
+
+    /// Removes the task in the slot with the given `index` without completing it.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if a task has been removed.
+    /// * `false` if the slot is free.
+    fn cancel_slot(&mut self, index: usize) -> bool {
+        let Some(mut task) = self.remove(index) else {
+            return false;
+        };
+
+        if let Some(future) = task.value.get_mut() {
+            future.as_mut().drop_future();
+        }
+
+        self.record(EventKind::Cancel, index);
+        true
+    }
+
+    /// Removes the task in the slot with the given `index` for exceeding the poll limit and
+    /// reports it to the runaway callback.
+    fn cancel_runaway(&mut self, index: usize) {
+        if self.cancel_slot(index)
+            && let Some(cb) = self.on_runaway
+        {
+            cb(self.names[index].unwrap_or(""));
+        }
+    }
+
+    /// Polls the task in the slot with the given `index` and records the poll statistics.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the task has completed or aborted itself.
+    /// * `false` if the task is still pending or the slot is free.
+    fn poll_slot(&mut self, index: usize) -> bool {
+        if self.tasks[index].is_none() {
+            return false;
+        }
+
+        self.record(EventKind::Poll, index);
+        self.polls[index] += 1;
+        self.poll_count += 1;
+        self.polls_this_run[index] = self.polls_this_run[index].saturating_add(1);
+
+        if let Some(source) = self.time_source {
+            let now = source.now();
+            self.last_polled[index] = Some(now);
+
+            if let Some(since) = self.runnable_since[index].take() {
+                let latency = now.saturating_sub(since);
+                self.max_latency[index] = self.max_latency[index].max(Some(latency));
+            }
+        }
+
+        if let Some(before) = self.before_poll {
+            before(self.id(index));
+        }
+
+        let context = self.tasks[index]
+            .as_ref()
+            .and_then(|task| task.value.get())
+            .and_then(|future| future.context());
+        // the task is taken out of its slot during the poll, so the contention is only computed
+        // from the other slots if the task asks for it, see `checkpoint`
+        let mut task = self.tasks[index].take();
+        self.polling = Some(index);
+        let this = &*self;
+        let contended = || (0..TASK_ARRAY_SIZE).any(|i| i != index && this.is_ready(i));
+        let mut data = PollData::new(context, &contended);
+
+        if let Some(flag) = self.wake_flag(index) {
+            flag.clear();
+            data.wake_flag = Some(flag.waker_data());
+        }
+
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        let kind = task.as_mut().map_or(EventKind::Pending, |task| {
+            poll_task(task, self.pending_callback, &data)
+        });
+        let interest = data.interest.get();
+        let deadline = data.deadline.get();
+        self.tasks[index] = task;
+        self.polling = None;
+        #[cfg(feature = "std")]
+        {
+            self.busy_time[index] += start.elapsed();
+        }
+
+        if let Some(after) = self.after_poll {
+            let result = if kind == EventKind::Pending {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            };
+            after(self.id(index), result);
+        }
+
+        self.record(kind, index);
+
+        // an aborted task never resumes, so its future is dropped like the one of a cancelled task
+        if kind == EventKind::Abort
+            && let Some(future) = self.tasks[index]
+                .as_mut()
+                .and_then(|task| task.value.get_mut())
+        {
+            future.as_mut().drop_future();
+        }
+
+        if kind == EventKind::Pending
+            && interest.is_none()
+            && deadline.is_none()
+            && self.wake_flag(index).is_some_and(WakeFlag::is_woken)
+        {
+            self.yields[index] = self.yields[index].saturating_add(1);
+        }
+
+        if kind == EventKind::Pending {
+            if let Some(interest) = interest
+                && let Some(reactor) = self.reactor
+            {
+                self.parked[index] = Some(interest);
+                reactor.register(self.id(index), interest);
+            } else if let Some(deadline) = deadline
+                && self.time_source.is_some()
+            {
+                self.deadlines[index] = Some(deadline);
+            }
+        }
+
+        self.stamp_wakes();
+
+        if kind == EventKind::Complete {
+            self.completion_count += 1;
+
+            if let Some(queue) = self.completion_queue {
+                queue.push(Completion {
+                    id: self.id(index),
+                    name: self.names[index],
+                });
+            }
+
+            if let Some(cb) = self.completion_callback {
+                cb(self.names[index].unwrap_or(""));
+            }
+        }
+
+        kind != EventKind::Pending
+    }
+}
+
+/// A xorshift64* pseudo-random number generator.
+struct XorShift(u64);
+
+impl XorShift {
+    /// Creates a generator from `seed`. A zero seed would only ever produce zeros, so it is
+    /// replaced with a fixed non-zero one.
+    const fn new(seed: u64) -> Self {
+        Self(if seed == 0 {
+            0x9e37_79b9_7f4a_7c15
+        } else {
+            seed
+        })
+    }
+
+    /// Returns the next number of the sequence.
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number in `0..bound`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn below(&mut self, bound: usize) -> usize {
+        // the remainder is below `bound`, so it fits into `usize`
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Blocks on the provided future until it is completed, without an [`Executor`] instance.
+///
+/// The future is busy-polled with the waker of the executor, the same way
+/// [`Executor::block_on`] does. Nothing sleeps between the polls, so the function works in
+/// `no_std` builds as is.
+///
+/// The function is re-exported at the crate root as `miniloop::block_on`.
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(miniloop::block_on(async { 1 + 1 }), 2);
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = create_waker(ptr::null());
+    let mut future = pin!(future);
+    let mut ctx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(val) = future.as_mut().poll(&mut ctx) {
+            return val;
+        }
+    }
+}
+
+/// Polls a given task and optionally calls a callback function if the task is pending.
+///
+/// # Parameters
+///
+/// * `task`:
+///   A mutable reference to the task being polled.
+/// * `cb`:
+///   An optional callback function that takes a `&str` argument. This callback is invoked with the task's name if the task is pending.
+/// * `data`:
+///   The data passed to the task through the waker. The task may leave requests to the executor
+///   in it.
+///
+/// # Returns
+///
+/// * `EventKind::Complete` if the task has completed.
+/// * `EventKind::Abort` if the task has aborted itself with [`abort_self`](crate::helpers::abort_self).
+/// * `EventKind::Pending` if the task is still pending.
+fn poll_task(task: &mut StackBoxFuture, cb: Option<fn(&str)>, data: &PollData) -> EventKind {
+    let Some(future) = task.value.get_mut() else {
+        return EventKind::Pending;
+    };
+    let waker = create_waker(ptr::from_ref(data).cast());
+    let context = &mut Context::from_waker(&waker);
+
+    if future.as_mut().poll(context).is_ready() {
+        return EventKind::Complete;
+    }
+
+    if data.abort.get() {
+        return EventKind::Abort;
+    }
+
+    if let Some(cb) = cb {
+        cb(future.name().unwrap_or(""));
+    }
+
+    EventKind::Pending
+}
+
+/// The data the executor passes to the task it polls through the waker.
+pub(crate) struct PollData<'p> {
+    /// The context of the task being polled.
+    pub(crate) context: Option<&'static dyn Any>,
+    /// Checks whether other tasks are ready to be polled.
+    contended: &'p dyn Fn() -> bool,
+    /// Set by the task to ask the executor to drop it once the poll returns.
+    pub(crate) abort: Cell<bool>,
+    /// Set by the task to park until the interest is satisfied.
+    pub(crate) interest: Cell<Option<Interest>>,
+    /// Set by the task to sleep until the time source reaches the tick.
+    pub(crate) deadline: Cell<Option<u64>>,
+    /// The waker data of the wake flag of the task being polled, `None` if the task is polled
+    /// regardless of wakes.
+    pub(crate) wake_flag: Option<*const ()>,
+}
+
+impl<'p> PollData<'p> {
+    pub(crate) const fn new(
+        context: Option<&'static dyn Any>,
+        contended: &'p dyn Fn() -> bool,
+    ) -> Self {
+        Self {
+            context,
+            contended,
+            abort: Cell::new(false),
+            interest: Cell::new(None),
+            deadline: Cell::new(None),
+            wake_flag: None,
+        }
+    }
+
+    /// Checks whether other tasks are ready to be polled. The executor only looks at its slots
+    /// when asked, as most tasks never do.
+    pub(crate) fn is_contended(&self) -> bool {
+        (self.contended)()
+    }
+}
+
+/// Clones the waker passed to a poll into a waker that outlives the poll.
+///
+/// The [`PollData`] is only alive during the poll, so the clone carries the waker data of the
+/// wake flag of the task instead. Without a wake flag, the clone does nothing when woken.
+unsafe fn clone(data: *const ()) -> RawWaker {
+    // SAFETY: a non-null data pointer of a poll waker points to the data of the task being
+    // polled, and the waker is only borrowed by the poll.
+    match unsafe { data.cast::<PollData<'_>>().as_ref() }.and_then(|data| data.wake_flag) {
+        Some(flag) => RawWaker::new(flag, &FLAG_VTABLE),
+        None => create_raw_waker(ptr::null()),
+    }
+}
+
+unsafe fn wake(data: *const ()) {
+    // SAFETY: see `wake_by_ref`.
+    unsafe { wake_by_ref(data) }
+}
+
+unsafe fn wake_by_ref(data: *const ()) {
+    // SAFETY: a non-null data pointer of a poll waker points to the data of the task being
+    // polled, and the waker is only borrowed by the poll.
+    if let Some(flag) =
+        unsafe { data.cast::<PollData<'_>>().as_ref() }.and_then(|data| data.wake_flag)
+    {
+        // SAFETY: the poll data only carries the waker data of a wake flag.
+        unsafe { wake::wake(flag) };
+    }
+}
+
+unsafe fn drop(_: *const ()) {}
+
+/// The virtual function table of the wakers the executor passes to the polls.
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+unsafe fn clone_flag(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &FLAG_VTABLE)
+}
+
+/// Sets the wake flag the waker data refers to. The flag lives in a `static` [`WakeTable`], so the
+/// waker stays harmless after its task and executor are gone.
+unsafe fn wake_flag(data: *const ()) {
+    // SAFETY: the data of a flag waker is the waker data of a wake flag.
+    unsafe { wake::wake(data) };
+}
+
+/// The virtual function table of the wakers cloned from the poll wakers, which set the wake flag
+/// of their task, and of the wakers passed by [`Executor::block_on`] with an idle hook.
+static FLAG_VTABLE: RawWakerVTable = RawWakerVTable::new(clone_flag, wake_flag, wake_flag, drop);
+
+fn create_raw_waker(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+/// Creates a waker carrying `data`.
+///
+/// The data is either null or points to the [`PollData`] of the task being polled with the waker.
+pub(crate) fn create_waker(data: *const ()) -> Waker {
+    let raw_waker = create_raw_waker(data);
+
+    unsafe { Waker::from_raw(raw_waker) }
+}
+
+/// Returns the data of the task that is being polled with the given `waker`.
+///
+/// Must only be called with the waker of the [`Context`] passed to the current poll.
+///
+/// # Returns
+///
+/// * `Some(data)` - if `waker` was created by the executor for a task.
+/// * `None` - if `waker` was not created by [`poll_task`], e.g. in [`Executor::block_on`].
+pub(crate) fn poll_data(waker: &Waker) -> Option<&PollData<'_>> {
+    if !ptr::eq(waker.vtable(), &raw const VTABLE) || waker.data().is_null() {
+        return None;
+    }
+
+    // SAFETY: a non-null data pointer of an executor waker points to the data of the task that
+    // `poll_task` is polling with this waker, which stays alive for the whole poll.
+    unsafe { Some(&*waker.data().cast::<PollData<'_>>()) }
 }
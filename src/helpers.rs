@@ -2,39 +2,77 @@
 //!
 //! Contains a set of helper functions/structs that helps with executor control:
 //!   - `yield_me` - yield current task execution and let the executor switches to another task
+//!   - `yield_if` - yield only when a condition holds
+//!   - `checkpoint` - yield only when other tasks are waiting to run
+//!   - `poll_immediate` - check whether a future can complete right now without waiting for it
+//!   - `select_with_default` - take the first future that can complete right now, or a default
+//!   - `lazy` - create a future only when it is polled for the first time
+//!   - `never` - a future that never completes and never asks to be woken
+//!   - `current_context` - read the context attached to the task being polled
+//!   - `abort_self` - remove the current task from the executor without completing it
+//!   - `catch` - turn a panic inside a future into an error (`std` only)
+//!   - `zip` - pair up the items of two streams
+//!   - `throttle` - let through at most one item of a stream per time window
+//!   - `race_ok` - wait for the first of several fallible futures to succeed
+//!   - `scope` - run child futures that all complete before the scope does
+//!   - `buffer_unordered` - run a batch of futures with a bounded number of them in flight
+//!   - `try_join_all` - run fallible futures concurrently, failing on the first error
+//!   - `counted` - count the polls of a future, e.g. to test combinators
+//!   - `ordered` - stream the outputs of a batch of futures in the order of the batch
+//!   - `select_loop` - repeatedly wait for the first of two re-armed futures in an event loop
+//!   - `shared` - await the output of a single future from several places (`alloc` only)
+//!   - `pin_mut!` - pin local futures on the stack in place
+//!   - `pin_each` - split a pinned array of tasks into the pinned tasks
 //!
 //! # Example
 //!
 //! ```no_run
 //! # use miniloop::executor::Executor;
-//! # use miniloop::task::Task;
+//! # use miniloop::task::{Handle, Task};
 //! # use core::future::Future;
+//! use core::pin::pin;
 //! use miniloop::helpers::yield_me;
 //! const TASK_ARRAY_SIZE: usize = 4;
 //! // Assume `some_future` is a mutable future reference
 //! let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
-//! let mut task1 = Task::new("task1", async {
+//! let task1 = pin!(Task::new("task1", async {
 //!     loop {
 //!         // computation
 //!         yield_me().await; // let to switch to another task
 //!     }
-//! });
-//! let mut handle1 = task1.create_handle();
-//! let mut task2 = Task::new("task2", async {
+//! }));
+//! let mut handle1 = Handle::new();
+//! let task2 = pin!(Task::new("task2", async {
 //!     loop {
 //!         // computation
 //!         yield_me().await; // let to switch to another task
 //!     }
-//! });
-//! let mut handle2 = task2.create_handle();
-//! executor.spawn(&mut task1, &mut handle1).expect("Failed to spawn task");
-//! executor.spawn(&mut task2, &mut handle2).expect("Failed to spawn task");
+//! }));
+//! let mut handle2 = Handle::new();
+//! executor.spawn(task1, &mut handle1).expect("Failed to spawn task");
+//! executor.spawn(task2, &mut handle2).expect("Failed to spawn task");
 //! executor.run();
 //! ```
+use crate::executor::{PollData, poll_data};
+use crate::stream::Stream;
+use crate::time::TimeSource;
+
+#[cfg(feature = "alloc")]
+use alloc::rc::Rc;
+use core::any::Any;
+use core::cell::Cell;
+#[cfg(feature = "alloc")]
+use core::cell::RefCell;
 use core::default::Default;
 use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
 use core::pin::Pin;
 use core::task::{Context, Poll};
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::panic::{AssertUnwindSafe, catch_unwind};
 
 /// A struct that implements the `Future` trait to create a single-yield future.
 #[derive(Default)]
@@ -85,3 +123,1516 @@ impl Future for Yield {
 pub async fn yield_me() {
     Yield::default().await;
 }
+
+/// Yields execution back to the executor once if `cond` is `true`.
+///
+/// If `cond` is `false`, the returned future resolves immediately without letting the executor
+/// switch to another task. This helps to yield adaptively, e.g. every few iterations of a loop.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::yield_if;
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     for counter in 0..32 {
+///         // some work here
+///         yield_if(counter % 8 == 7).await; // yield once every 8 iterations
+///     }
+/// });
+/// ```
+pub fn yield_if(cond: bool) -> impl Future<Output = ()> {
+    Yield { flag: !cond }
+}
+
+/// A future that never resolves, created by [`never`].
+struct Never<T> {
+    /// The type the future would resolve to.
+    output_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Future for Never<T> {
+    type Output = T;
+
+    /// Does nothing.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Pending` always, without waking the task.
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+        Poll::Pending
+    }
+}
+
+/// Creates a future that never resolves and never wakes the task awaiting it.
+///
+/// Unlike a future that is merely pending for now, such as [`yield_me`], it never asks to be
+/// polled again, so it stands for a task waiting on an event that will never happen. This makes
+/// it the canonical input for exercising the paths where a future loses: timeouts firing,
+/// selects picking the other branch or tasks that are stuck for good.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::{never, poll_immediate};
+/// let mut executor = Executor::<1>::new();
+/// let output = executor.block_on(poll_immediate(never::<u32>()));
+/// assert_eq!(output, None);
+/// ```
+pub fn never<T>() -> impl Future<Output = T> {
+    Never {
+        output_type: PhantomData,
+    }
+}
+
+/// A future that yields once if other tasks are waiting, created by [`checkpoint`].
+struct Checkpoint {
+    /// A flag indicating whether the future has checked for other tasks already.
+    checked: bool,
+}
+
+impl Future for Checkpoint {
+    type Output = ();
+
+    /// Yields on the first poll if the executor reports other tasks waiting to be polled.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` if no other task is waiting or the future has already yielded.
+    /// * `Poll::Pending` if the future needs to yield.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.checked || !poll_data(cx.waker()).is_some_and(PollData::is_contended) {
+            return Poll::Ready(());
+        }
+
+        this.checked = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Yields execution back to the executor once if other tasks are waiting to run.
+///
+/// Unlike [`yield_me`], which always lets the executor switch to another task, a checkpoint
+/// only yields under contention: if no other task is ready to be polled, i.e. woken and not
+/// waiting for a dependency, a reactor interest or a deadline, switching would just come back to
+/// the current task, so the returned future resolves immediately. Tasks can call it at natural
+/// boundaries of their work without paying for needless switches.
+///
+/// The executor passes the contention to the task with the waker it polls the task with, so only
+/// tasks driven by [`Executor::run`](crate::executor::Executor::run) ever yield at a checkpoint.
+///
+/// # Example
+/// ```no_run
+/// # use miniloop::helpers::checkpoint;
+/// async fn task() {
+///     for chunk in 0..16 {
+///         // process the chunk
+///         checkpoint().await; // let others run, if there are any
+///     }
+/// }
+/// ```
+pub fn checkpoint() -> impl Future<Output = ()> {
+    Checkpoint { checked: false }
+}
+
+/// A future running child futures until all of them complete, created by [`scope`].
+pub struct Scope<'a, const N: usize> {
+    /// The children that have not completed yet.
+    children: [Option<Pin<&'a mut dyn Future<Output = ()>>>; N],
+}
+
+impl<const N: usize> Future for Scope<'_, N> {
+    type Output = ();
+
+    /// Polls every child that has not completed yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` once all children have completed.
+    /// * `Poll::Pending` while any child is pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        for slot in &mut this.children {
+            if let Some(child) = slot
+                && child.as_mut().poll(cx).is_ready()
+            {
+                *slot = None;
+            }
+        }
+
+        if this.children.iter().all(Option::is_none) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Runs child futures concurrently within the current task, completing once all of them have.
+///
+/// The children are borrowed for the lifetime of the returned future, so none of them can outlive
+/// the scope: when `scope(...).await` returns, every child has run to completion, and the task
+/// continues past the scope only then. This is the core guarantee of structured concurrency.
+/// The children are polled in turn by the task awaiting the scope, not spawned into the executor,
+/// so they take no executor slots. They are pinned in place, e.g. with [`pin_mut!`], and report
+/// their results by writing to variables captured by reference.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::cell::Cell;
+/// use miniloop::helpers::{pin_mut, scope, yield_me};
+///
+/// let sum = Cell::new(0);
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     let first = async {
+///         yield_me().await;
+///         sum.set(sum.get() + 1);
+///     };
+///     let second = async { sum.set(sum.get() + 2) };
+///     pin_mut!(first, second);
+///
+///     scope([first, second]).await;
+///     assert_eq!(sum.get(), 3);
+/// });
+/// ```
+pub fn scope<const N: usize>(children: [Pin<&mut dyn Future<Output = ()>>; N]) -> Scope<'_, N> {
+    Scope {
+        children: children.map(Some),
+    }
+}
+
+/// A future driving a batch of futures with bounded concurrency, created by [`buffer_unordered`].
+pub struct BufferUnordered<'a, F: Future> {
+    /// The futures of the batch.
+    futures: &'a mut [F],
+    /// The outputs of the futures, `None` until the future at the same index completes.
+    out: &'a mut [Option<F::Output>],
+    /// The maximum number of futures in flight.
+    limit: usize,
+    /// The index of the next future to start.
+    next: usize,
+}
+
+impl<F: Future + Unpin> Future for BufferUnordered<'_, F> {
+    type Output = ();
+
+    /// Polls the futures in flight and starts new ones in their place as they complete.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` once all futures have completed.
+    /// * `Poll::Pending` while any future is pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut active = this.out[..this.next]
+            .iter()
+            .filter(|output| output.is_none())
+            .count();
+
+        for (index, (future, output)) in
+            this.futures.iter_mut().zip(this.out.iter_mut()).enumerate()
+        {
+            if output.is_some() {
+                continue;
+            }
+
+            if index >= this.next {
+                if active == this.limit {
+                    break;
+                }
+
+                this.next += 1;
+                active += 1;
+            }
+
+            if let Poll::Ready(value) = Pin::new(future).poll(cx) {
+                *output = Some(value);
+                active -= 1;
+            }
+        }
+
+        if this.next == this.futures.len() && active == 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Runs a batch of futures, keeping at most `limit` of them in flight at a time.
+///
+/// The futures are started in order: the first `limit` of them right away, and each following one
+/// as soon as a future in flight completes, so futures that have not started yet are not polled
+/// at all. The output of each future is written into `out` at the index of the future, and the
+/// returned future resolves once all of them have completed. The previous content of `out` is
+/// discarded. A `limit` of `0` is treated as `1`.
+///
+/// The futures have to be [`Unpin`] as they are polled in place. Futures that are not, like
+/// `async` blocks, can be pinned first, e.g. with [`pin_mut!`], and passed as `Pin<&mut F>`.
+///
+/// # Panics
+///
+/// Panics if `out` and `futures` have different lengths.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::future::{Ready, ready};
+/// use miniloop::helpers::buffer_unordered;
+///
+/// let mut futures: [Ready<u32>; 4] = core::array::from_fn(|i| ready(i as u32 * 10));
+/// let mut out = [None; 4];
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(buffer_unordered(&mut futures, 2, &mut out));
+/// assert_eq!(out, [Some(0), Some(10), Some(20), Some(30)]);
+/// ```
+pub fn buffer_unordered<'a, F>(
+    futures: &'a mut [F],
+    limit: usize,
+    out: &'a mut [Option<F::Output>],
+) -> BufferUnordered<'a, F>
+where
+    F: Future + Unpin,
+{
+    assert_eq!(
+        futures.len(),
+        out.len(),
+        "every future needs a place for its output"
+    );
+    out.fill_with(|| None);
+
+    BufferUnordered {
+        futures,
+        out,
+        limit: limit.max(1),
+        next: 0,
+    }
+}
+
+/// A future driving fallible futures until all succeed or one fails, created by
+/// [`try_join_all`].
+pub struct TryJoinAll<'a, F, T> {
+    /// The futures to join.
+    futures: &'a mut [F],
+    /// The outputs of the futures, `None` until the future at the same index succeeds.
+    out: &'a mut [Option<T>],
+}
+
+impl<F, T, E> Future for TryJoinAll<'_, F, T>
+where
+    F: Future<Output = Result<T, E>> + Unpin,
+{
+    type Output = Result<(), E>;
+
+    /// Polls each future that has not succeeded yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Ok(()))` once all futures have succeeded.
+    /// * `Poll::Ready(Err(error))` as soon as a future fails.
+    /// * `Poll::Pending` while any future is pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        for (future, output) in this.futures.iter_mut().zip(this.out.iter_mut()) {
+            if output.is_some() {
+                continue;
+            }
+
+            match Pin::new(future).poll(cx) {
+                Poll::Ready(Ok(value)) => *output = Some(value),
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => {}
+            }
+        }
+
+        if this.out.iter().all(Option::is_some) {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Runs fallible futures concurrently, failing as soon as one of them fails.
+///
+/// This is `try_join!` over a slice. The output of each future that succeeds is written into
+/// `out` at the index of the future. The returned future resolves to `Ok(())` once all futures
+/// have succeeded, or to the error of the first future that fails. The remaining futures are not
+/// polled anymore then, and are dropped by the caller along with the slice. The previous content
+/// of `out` is discarded.
+///
+/// The futures have to be [`Unpin`] as they are polled in place, like in [`buffer_unordered`].
+///
+/// # Panics
+///
+/// Panics if `out` and `futures` have different lengths.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::future::{Ready, ready};
+/// use miniloop::helpers::try_join_all;
+///
+/// let mut executor = Executor::<1>::new();
+/// let mut out = [None; 3];
+///
+/// let mut futures: [Ready<Result<u32, &str>>; 3] = [ready(Ok(1)), ready(Ok(2)), ready(Ok(3))];
+/// assert_eq!(executor.block_on(try_join_all(&mut futures, &mut out)), Ok(()));
+/// assert_eq!(out, [Some(1), Some(2), Some(3)]);
+///
+/// let mut futures = [ready(Ok(1)), ready(Err("sensor offline")), ready(Ok(3))];
+/// assert_eq!(executor.block_on(try_join_all(&mut futures, &mut out)), Err("sensor offline"));
+/// ```
+pub fn try_join_all<'a, F, T, E>(
+    futures: &'a mut [F],
+    out: &'a mut [Option<T>],
+) -> impl Future<Output = Result<(), E>> + 'a
+where
+    F: Future<Output = Result<T, E>> + Unpin,
+{
+    assert_eq!(
+        futures.len(),
+        out.len(),
+        "every future needs a place for its output"
+    );
+    out.fill_with(|| None);
+
+    TryJoinAll { futures, out }
+}
+
+/// A counter of the polls of a future wrapped with [`counted`].
+#[derive(Debug, Default)]
+pub struct PollCounter {
+    /// The number of polls counted so far.
+    polls: Cell<u32>,
+}
+
+impl PollCounter {
+    /// Creates a new `PollCounter` with no polls counted.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            polls: Cell::new(0),
+        }
+    }
+
+    /// Returns the number of times the wrapped future has been polled.
+    #[must_use]
+    pub fn get(&self) -> u32 {
+        self.polls.get()
+    }
+}
+
+/// A future counting its polls, created by [`counted`].
+pub struct Counted<'a, F> {
+    /// The wrapped future.
+    future: F,
+    /// The counter incremented on each poll.
+    counter: &'a PollCounter,
+}
+
+impl<F: Future> Future for Counted<'_, F> {
+    type Output = F::Output;
+
+    /// Counts the poll and polls the wrapped future.
+    ///
+    /// # Returns
+    ///
+    /// The result of polling the wrapped future.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `Counted`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        this.counter.polls.set(this.counter.polls.get() + 1);
+        future.poll(cx)
+    }
+}
+
+/// Wraps `future` to count how many times it is polled into `counter`.
+///
+/// This makes it straightforward to check that a combinator polls its inner futures the expected
+/// number of times, e.g. that it does not poll a future again once it has completed. The counter
+/// is borrowed rather than returned alongside the future, so no allocation is needed to share it.
+/// The wrapped future is [`Unpin`] if `future` is.
+///
+/// # Parameters
+///
+/// * `future`:
+///   The future to count the polls of.
+/// * `counter`:
+///   The counter incremented on each poll.
+///
+/// # Returns
+///
+/// A future resolving to the output of `future`.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::{PollCounter, counted, yield_me};
+///
+/// let counter = PollCounter::new();
+/// let mut executor = Executor::<1>::new();
+///
+/// executor.block_on(counted(yield_me(), &counter));
+/// assert_eq!(counter.get(), 2);
+/// ```
+pub fn counted<F: Future>(future: F, counter: &PollCounter) -> Counted<'_, F> {
+    Counted { future, counter }
+}
+
+/// A stream of the outputs of a batch of futures in the order of the batch, created by
+/// [`ordered`].
+pub struct Ordered<'a, F: Future> {
+    /// The futures of the batch.
+    futures: &'a mut [F],
+    /// The outputs of the completed futures that have not been produced yet.
+    buffer: &'a mut [Option<F::Output>],
+    /// The index of the future whose output is produced next.
+    next: usize,
+}
+
+impl<F: Future + Unpin> Stream for Ordered<'_, F> {
+    type Item = F::Output;
+
+    /// Polls every future that has not completed yet and produces the next output in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Some(output))` once the future next in order has completed.
+    /// * `Poll::Ready(None)` once the outputs of all futures have been produced.
+    /// * `Poll::Pending` while the future next in order is pending.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let pending = this.futures.iter_mut().zip(this.buffer.iter_mut());
+
+        for (future, output) in pending.skip(this.next) {
+            if output.is_none()
+                && let Poll::Ready(value) = Pin::new(future).poll(cx)
+            {
+                *output = Some(value);
+            }
+        }
+
+        let Some(output) = this.buffer.get_mut(this.next) else {
+            return Poll::Ready(None);
+        };
+
+        match output.take() {
+            Some(value) => {
+                this.next += 1;
+                Poll::Ready(Some(value))
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Drives a batch of futures concurrently, producing their outputs in the order of the batch.
+///
+/// All futures are polled on every poll of the returned stream, but the outputs are produced
+/// strictly in the order of `futures`: the output of a future that completes early is kept in
+/// `buffer` until the outputs of all futures before it have been produced. Unlike with
+/// [`buffer_unordered`], the position of an output in the stream thus tells which future it came
+/// from. The previous content of `buffer` is discarded.
+///
+/// The futures have to be [`Unpin`] as they are polled in place. Futures that are not, like
+/// `async` blocks, can be pinned first, e.g. with [`pin_mut!`], and passed as `Pin<&mut F>`.
+///
+/// # Panics
+///
+/// Panics if `buffer` and `futures` have different lengths.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::future::{Ready, ready};
+/// use miniloop::helpers::ordered;
+/// use miniloop::stream::next;
+///
+/// let mut futures = [ready(1), ready(2)];
+/// let mut buffer = [None; 2];
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     let mut outputs = ordered(&mut futures, &mut buffer);
+///     assert_eq!(next(&mut outputs).await, Some(1));
+///     assert_eq!(next(&mut outputs).await, Some(2));
+///     assert_eq!(next(&mut outputs).await, None);
+/// });
+/// ```
+pub fn ordered<'a, F>(futures: &'a mut [F], buffer: &'a mut [Option<F::Output>]) -> Ordered<'a, F>
+where
+    F: Future + Unpin,
+{
+    assert_eq!(
+        futures.len(),
+        buffer.len(),
+        "every future needs a place for its output"
+    );
+    buffer.fill_with(|| None);
+
+    Ordered {
+        futures,
+        buffer,
+        next: 0,
+    }
+}
+
+/// The branch of [`select_loop`] that has completed, with its output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selected<A, B> {
+    /// The future of the first branch has completed.
+    First(A),
+    /// The future of the second branch has completed.
+    Second(B),
+}
+
+/// An event loop waiting for the first of two futures on each iteration, created by
+/// [`select_loop`].
+pub struct SelectLoop<MA, FA, MB, FB, H> {
+    /// Creates the future of the first branch.
+    make_a: MA,
+    /// The armed future of the first branch, `None` after it has completed.
+    a: Option<FA>,
+    /// Creates the future of the second branch.
+    make_b: MB,
+    /// The armed future of the second branch, `None` after it has completed.
+    b: Option<FB>,
+    /// Handles the output of the completed branch.
+    handler: H,
+    /// Whether the second branch is polled first on the next iteration.
+    b_first: bool,
+}
+
+impl<MA, FA, MB, FB, H, T> Future for SelectLoop<MA, FA, MB, FB, H>
+where
+    MA: FnMut() -> FA,
+    FA: Future,
+    MB: FnMut() -> FB,
+    FB: Future,
+    H: FnMut(Selected<FA::Output, FB::Output>) -> ControlFlow<T>,
+{
+    type Output = T;
+
+    /// Re-arms the branches that have completed and runs iterations until the handler breaks.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(value)` once the handler returns `ControlFlow::Break(value)`.
+    /// * `Poll::Pending` while both branches are pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the armed futures are never moved out of `SelectLoop`. They are only dropped in
+        // place once they complete, and new futures are written into the emptied places.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        loop {
+            let a = unsafe { Pin::new_unchecked(this.a.get_or_insert_with(&mut this.make_a)) };
+            let b = unsafe { Pin::new_unchecked(this.b.get_or_insert_with(&mut this.make_b)) };
+
+            let selected = if this.b_first {
+                match b.poll(cx) {
+                    Poll::Ready(output) => Poll::Ready(Selected::Second(output)),
+                    Poll::Pending => a.poll(cx).map(Selected::First),
+                }
+            } else {
+                match a.poll(cx) {
+                    Poll::Ready(output) => Poll::Ready(Selected::First(output)),
+                    Poll::Pending => b.poll(cx).map(Selected::Second),
+                }
+            };
+            let Poll::Ready(selected) = selected else {
+                return Poll::Pending;
+            };
+
+            this.b_first = !this.b_first;
+
+            match selected {
+                Selected::First(_) => this.a = None,
+                Selected::Second(_) => this.b = None,
+            }
+
+            if let ControlFlow::Break(value) = (this.handler)(selected) {
+                return Poll::Ready(value);
+            }
+        }
+    }
+}
+
+/// Runs an event loop that repeatedly waits for the first of two futures to complete.
+///
+/// Each iteration waits for either branch, passes the output of the branch that completes first
+/// to `handler` and starts over until `handler` returns `ControlFlow::Break(value)`, which the
+/// returned future resolves to. This is the `loop { select! { ... } }` idiom without the
+/// bookkeeping.
+///
+/// A future cannot be polled again once it has completed, so the branches are given as closures
+/// creating their futures instead. The future of the completed branch is re-armed by calling its
+/// closure again at the start of the next iteration, while the future of the other branch keeps
+/// its progress. The branch polled first alternates between iterations, so a branch that is always
+/// ready cannot starve the other one.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::future::ready;
+/// use core::ops::ControlFlow;
+/// use miniloop::helpers::{Selected, select_loop};
+///
+/// let mut executor = Executor::<1>::new();
+/// let mut counts = (0, 0);
+/// let counts = executor.block_on(select_loop(
+///     || ready('a'),
+///     || ready(1),
+///     |selected| {
+///         match selected {
+///             Selected::First(_) => counts.0 += 1,
+///             Selected::Second(_) => counts.1 += 1,
+///         }
+///
+///         if counts.0 + counts.1 == 4 {
+///             ControlFlow::Break(counts)
+///         } else {
+///             ControlFlow::Continue(())
+///         }
+///     },
+/// ));
+/// // the always ready branches take turns
+/// assert_eq!(counts, (2, 2));
+/// ```
+pub fn select_loop<MA, FA, MB, FB, H, T>(
+    make_a: MA,
+    make_b: MB,
+    handler: H,
+) -> SelectLoop<MA, FA, MB, FB, H>
+where
+    MA: FnMut() -> FA,
+    FA: Future,
+    MB: FnMut() -> FB,
+    FB: Future,
+    H: FnMut(Selected<FA::Output, FB::Output>) -> ControlFlow<T>,
+{
+    SelectLoop {
+        make_a,
+        a: None,
+        make_b,
+        b: None,
+        handler,
+        b_first: false,
+    }
+}
+
+/// A future that polls the inner future exactly once.
+struct PollImmediate<F> {
+    /// The future polled on behalf of the caller.
+    future: F,
+}
+
+impl<F: Future> Future for PollImmediate<F> {
+    type Output = Option<F::Output>;
+
+    /// Polls the inner future once and resolves regardless of its result.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Some(output))` if the inner future completed.
+    /// * `Poll::Ready(None)` if the inner future is still pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `PollImmediate`.
+        let future = unsafe { self.map_unchecked_mut(|this| &mut this.future) };
+
+        match future.poll(cx) {
+            Poll::Ready(output) => Poll::Ready(Some(output)),
+            Poll::Pending => Poll::Ready(None),
+        }
+    }
+}
+
+/// Polls a future exactly once without waiting for it.
+///
+/// This is the async counterpart of a non-blocking `try_recv`: the returned future resolves
+/// immediately, either with the output of `future` if it could complete on its first poll, or
+/// with `None` if it could not. In the latter case `future` is dropped.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::{poll_immediate, yield_me};
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     assert_eq!(poll_immediate(async { 42 }).await, Some(42));
+///     assert_eq!(poll_immediate(yield_me()).await, None);
+/// });
+/// ```
+pub fn poll_immediate<F: Future>(future: F) -> impl Future<Output = Option<F::Output>> {
+    PollImmediate { future }
+}
+
+/// A future polling several futures once each, created by [`select_with_default`].
+struct SelectWithDefault<F, const N: usize> {
+    /// The futures to select from.
+    futures: [F; N],
+}
+
+impl<F, T, const N: usize> Future for SelectWithDefault<F, N>
+where
+    F: Future<Output = T>,
+    T: Default,
+{
+    type Output = T;
+
+    /// Polls each future once in order and resolves regardless of their results.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(output)` with the output of the first future that completed.
+    /// * `Poll::Ready(T::default())` if all futures are still pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the futures are never moved out of `SelectWithDefault`.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        for future in &mut this.futures {
+            if let Poll::Ready(output) = unsafe { Pin::new_unchecked(future) }.poll(cx) {
+                return Poll::Ready(output);
+            }
+        }
+
+        Poll::Ready(T::default())
+    }
+}
+
+/// Selects the first of several futures that can complete right now, falling back to a default.
+///
+/// This is the `default:` arm of a `select!` as a standalone combinator. A blocking select waits
+/// until one of its futures completes, while the returned future never waits: it polls each
+/// future of `futures` once, in order, and resolves immediately, either with the output of the
+/// first future that completed or with `T::default()` if none of them could. The futures that
+/// did not complete are dropped.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::{select_with_default, yield_me};
+///
+/// async fn receive(ready: bool) -> Option<u8> {
+///     if !ready {
+///         yield_me().await;
+///     }
+///     Some(42)
+/// }
+///
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     assert_eq!(select_with_default([receive(false), receive(true)]).await, Some(42));
+///     assert_eq!(select_with_default([receive(false), receive(false)]).await, None);
+/// });
+/// ```
+pub fn select_with_default<F, T, const N: usize>(futures: [F; N]) -> impl Future<Output = T>
+where
+    F: Future<Output = T>,
+    T: Default,
+{
+    SelectWithDefault { futures }
+}
+
+/// The state of a [`Lazy`] future.
+enum LazyState<F, Fut> {
+    /// The future has not been polled yet, the closure creating the inner future is waiting.
+    Init(Option<F>),
+    /// The inner future is running.
+    Running(Fut),
+}
+
+/// A future that creates the inner future on its first poll.
+struct Lazy<F, Fut> {
+    state: LazyState<F, Fut>,
+}
+
+impl<F, Fut> Future for Lazy<F, Fut>
+where
+    F: FnOnce(&mut Context<'_>) -> Fut,
+    Fut: Future,
+{
+    type Output = Fut::Output;
+
+    /// Creates the inner future if it does not exist yet and polls it.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(output)` if the inner future completed.
+    /// * `Poll::Pending` if the inner future is still pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the inner future is never moved out of the state. The closure is not pinned and
+        // is moved out before the inner future is created in place.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if let LazyState::Init(f) = &mut this.state {
+            let f = f.take().expect("`Lazy` polled after its closure panicked");
+            this.state = LazyState::Running(f(cx));
+        }
+
+        match &mut this.state {
+            LazyState::Running(future) => unsafe { Pin::new_unchecked(future) }.poll(cx),
+            LazyState::Init(_) => unreachable!("the inner future is created above"),
+        }
+    }
+}
+
+/// Defers the creation of a future until it is polled for the first time.
+///
+/// `f` is called with the context of the first poll to create the inner future, which is then
+/// polled right away. This postpones the cost of building an expensive future, and lets the
+/// future capture state sampled when it starts to run instead of when it is created.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::cell::Cell;
+/// use miniloop::helpers::lazy;
+///
+/// let counter = Cell::new(1);
+/// let future = lazy(|_| {
+///     let sampled = counter.get();
+///     async move { sampled * 10 }
+/// });
+/// counter.set(2);
+/// assert_eq!(Executor::<1>::new().block_on(future), 20);
+/// ```
+pub fn lazy<F, Fut>(f: F) -> impl Future<Output = Fut::Output>
+where
+    F: FnOnce(&mut Context<'_>) -> Fut,
+    Fut: Future,
+{
+    Lazy {
+        state: LazyState::Init(Some(f)),
+    }
+}
+
+/// A future that reads the context of the task polling it.
+struct CurrentContext<T> {
+    /// The type the context is expected to have.
+    context_type: PhantomData<fn() -> T>,
+}
+
+impl<T: Any> Future for CurrentContext<T> {
+    type Output = Option<&'static T>;
+
+    /// Looks the context up through the waker the executor polls the task with.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Some(context))` if the task has a context of type `T`.
+    /// * `Poll::Ready(None)` otherwise.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let context = poll_data(cx.waker()).and_then(|data| data.context);
+
+        Poll::Ready(context.and_then(<dyn Any>::downcast_ref))
+    }
+}
+
+/// Returns the context attached to the current task with
+/// [`Task::with_context`](crate::task::Task::with_context).
+///
+/// The context travels with the waker the executor polls a task with, so it is only available
+/// to tasks driven by [`Executor::run`](crate::executor::Executor::run). The returned future
+/// resolves immediately.
+///
+/// # Returns
+///
+/// * `Some(context)` - if the current task has a context of type `T`.
+/// * `None` - if the task has no context, its context has a different type, or the future is not
+///   polled by the executor as part of a task.
+///
+/// # Example
+/// ```no_run
+/// use miniloop::helpers::current_context;
+///
+/// struct Config {
+///     retries: u8,
+/// }
+///
+/// async fn task() {
+///     let retries = current_context::<Config>().await.map_or(1, |config| config.retries);
+///     // some work here
+/// }
+/// ```
+pub fn current_context<T: Any>() -> impl Future<Output = Option<&'static T>> {
+    CurrentContext {
+        context_type: PhantomData,
+    }
+}
+
+/// A future that asks the executor to drop the task polling it.
+struct AbortSelf;
+
+impl Future for AbortSelf {
+    type Output = ();
+
+    /// Raises the abort flag of the task through the waker the executor polls the task with.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Pending` always, the task is dropped before it could be polled again.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(data) = poll_data(cx.waker()) {
+            data.abort.set(true);
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Aborts the current task.
+///
+/// Unlike completing, aborting produces no output: once the poll that awaits the returned future
+/// returns, the executor drops the task and frees its slot, leaving the task's
+/// [`Handle`](crate::task::Handle) empty. The code after the `.await` never runs, and the future
+/// of the task is dropped in place, releasing what it holds, e.g. a
+/// [`MutexGuard`](crate::sync::MutexGuard).
+///
+/// The request travels with the waker the executor polls a task with, so only tasks driven by
+/// [`Executor::run`](crate::executor::Executor::run) can abort themselves. Elsewhere, e.g. in
+/// [`Executor::block_on`](crate::executor::Executor::block_on), the future never resolves.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// # use miniloop::task::{Handle, Task};
+/// use core::pin::pin;
+/// use miniloop::helpers::abort_self;
+///
+/// let task = pin!(Task::new("task", async {
+///     abort_self().await;
+///     42
+/// }));
+/// let mut handle = Handle::new();
+/// let mut executor = Executor::<1>::new();
+/// executor.spawn(task, &mut handle).expect("Failed to spawn task");
+/// executor.run();
+/// assert!(handle.value.is_none());
+/// ```
+pub fn abort_self() -> impl Future<Output = ()> {
+    AbortSelf
+}
+
+/// A stream pairing the items of two streams, created by [`zip`].
+pub struct Zip<A: Stream, B: Stream> {
+    /// The stream providing the first item of each pair.
+    a: A,
+    /// The stream providing the second item of each pair.
+    b: B,
+    /// An item of `a` waiting for its counterpart from `b`.
+    a_item: Option<A::Item>,
+    /// An item of `b` waiting for its counterpart from `a`.
+    b_item: Option<B::Item>,
+}
+
+impl<A: Stream, B: Stream> Stream for Zip<A, B> {
+    type Item = (A::Item, B::Item);
+
+    /// Polls both streams for an item, buffering the one that arrives first.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Some((a, b)))` once both streams have produced an item.
+    /// * `Poll::Ready(None)` as soon as either stream ends. A buffered item of the other stream is
+    ///   dropped.
+    /// * `Poll::Pending` while either stream is pending.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: `a` and `b` are never moved out of `Zip`.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if this.a_item.is_none() {
+            match unsafe { Pin::new_unchecked(&mut this.a) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => this.a_item = Some(item),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {}
+            }
+        }
+
+        if this.b_item.is_none() {
+            match unsafe { Pin::new_unchecked(&mut this.b) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => this.b_item = Some(item),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {}
+            }
+        }
+
+        match (this.a_item.take(), this.b_item.take()) {
+            (Some(a), Some(b)) => Poll::Ready(Some((a, b))),
+            (a_item, b_item) => {
+                this.a_item = a_item;
+                this.b_item = b_item;
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Pairs up the items of two streams.
+///
+/// The returned stream produces `(a, b)` pairs, where `a` and `b` are the next items of streams
+/// `a` and `b` respectively. It ends as soon as either of the streams ends, so the pairs are
+/// produced as long as both streams have items left.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::zip;
+/// use miniloop::stream::{iter, next};
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     let mut pairs = zip(iter([1, 2]), iter(['a', 'b', 'c']));
+///     assert_eq!(next(&mut pairs).await, Some((1, 'a')));
+///     assert_eq!(next(&mut pairs).await, Some((2, 'b')));
+///     assert_eq!(next(&mut pairs).await, None);
+/// });
+/// ```
+pub fn zip<A: Stream, B: Stream>(a: A, b: B) -> Zip<A, B> {
+    Zip {
+        a,
+        b,
+        a_item: None,
+        b_item: None,
+    }
+}
+
+/// A stream letting through at most one item per time window, created by [`throttle`].
+pub struct Throttle<'a, S, T: ?Sized> {
+    /// The throttled stream.
+    stream: S,
+    /// The time source measuring the windows.
+    source: &'a T,
+    /// The length of a window in ticks.
+    min_ticks: u64,
+    /// The tick before which items are dropped, `None` until the first item passes.
+    next: Option<u64>,
+}
+
+impl<S: Stream, T: TimeSource + ?Sized> Stream for Throttle<'_, S, T> {
+    type Item = S::Item;
+
+    /// Polls the inner stream, dropping the items that arrive before the current window ends.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Some(item))` if an item has arrived after the previous window has ended.
+    /// * `Poll::Ready(None)` once the inner stream has ended.
+    /// * `Poll::Pending` while the inner stream is pending.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: `stream` is never moved out of `Throttle`.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        loop {
+            let item = match unsafe { Pin::new_unchecked(&mut this.stream) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => item,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            let now = this.source.now();
+
+            if this.next.is_none_or(|next| now >= next) {
+                this.next = Some(now.saturating_add(this.min_ticks));
+                return Poll::Ready(Some(item));
+            }
+        }
+    }
+}
+
+/// Lets through at most one item of a stream per window of `min_ticks` ticks.
+///
+/// The first item passes right away and opens a window of `min_ticks` ticks measured by `source`.
+/// Items arriving before the window ends are dropped, and the first item arriving after it passes
+/// and opens the next window. Items are never delayed or buffered, so if the stream ends during a
+/// window, the items dropped in that window are lost and the throttled stream ends as well.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use core::cell::Cell;
+/// use miniloop::helpers::throttle;
+/// use miniloop::stream::{iter, next};
+///
+/// let now = Cell::new(0);
+/// let clock = || now.get();
+/// // each item arrives at the tick equal to its value
+/// let items = iter((0..6).inspect(|&tick| now.set(tick)));
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     let mut throttled = throttle(items, &clock, 3);
+///     assert_eq!(next(&mut throttled).await, Some(0));
+///     assert_eq!(next(&mut throttled).await, Some(3));
+///     assert_eq!(next(&mut throttled).await, None);
+/// });
+/// ```
+pub fn throttle<S, T>(stream: S, source: &T, min_ticks: u64) -> Throttle<'_, S, T>
+where
+    S: Stream,
+    T: TimeSource + ?Sized,
+{
+    Throttle {
+        stream,
+        source,
+        min_ticks,
+        next: None,
+    }
+}
+
+/// The state shared by the clones of a [`Shared`] future.
+#[cfg(feature = "alloc")]
+enum SharedState<F: Future> {
+    /// The inner future has not completed yet.
+    Pending(F),
+    /// The inner future has completed with the output.
+    Ready(F::Output),
+}
+
+/// A cloneable future resolving to the output of the inner future, created by [`shared`].
+///
+/// Only available with the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub struct Shared<F: Future> {
+    /// The state shared by all clones.
+    state: Rc<RefCell<SharedState<F>>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Future> Clone for Shared<F> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Rc::clone(&self.state),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Future> Future for Shared<F>
+where
+    F::Output: Clone,
+{
+    type Output = F::Output;
+
+    /// Polls the inner future unless one of the clones has already seen it complete.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(output)` with a clone of the cached output once the inner future completed.
+    /// * `Poll::Pending` if the inner future is still pending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inner future polls a clone of the `Shared` future it is wrapped into.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+
+        let output = match &mut *state {
+            SharedState::Ready(output) => return Poll::Ready(output.clone()),
+            // SAFETY: the future lives in a heap allocation that is never moved, and it is dropped
+            // in place once it completes.
+            SharedState::Pending(future) => match unsafe { Pin::new_unchecked(future) }.poll(cx) {
+                Poll::Ready(output) => output,
+                Poll::Pending => return Poll::Pending,
+            },
+        };
+
+        *state = SharedState::Ready(output.clone());
+        Poll::Ready(output)
+    }
+}
+
+/// Shares the output of `future` between several awaiting places.
+///
+/// The returned future can be cloned, and all clones resolve to a clone of the same output. The
+/// inner future is polled by whichever clone is polled, and it is driven to completion only once:
+/// its output is cached and the clones polled afterwards resolve immediately.
+///
+/// The clones share their state through a reference counted heap allocation, so they are meant
+/// to be awaited from within the same task. Only available with the `alloc` feature.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::{shared, yield_me};
+/// let mut executor = Executor::<1>::new();
+/// executor.block_on(async {
+///     let config = shared(async {
+///         yield_me().await;
+///         42
+///     });
+///     let other = config.clone();
+///     assert_eq!(config.await, 42);
+///     assert_eq!(other.await, 42);
+/// });
+/// ```
+#[cfg(feature = "alloc")]
+pub fn shared<F: Future>(future: F) -> Shared<F>
+where
+    F::Output: Clone,
+{
+    Shared {
+        state: Rc::new(RefCell::new(SharedState::Pending(future))),
+    }
+}
+
+/// A future racing fallible futures, created by [`race_ok`].
+struct RaceOk<F, E, const N: usize> {
+    /// The futures still racing, `None` once a future has failed.
+    futures: [Option<F>; N],
+    /// The index of the future polled first on the next poll.
+    start: usize,
+    /// The error of the future that failed last.
+    last_error: Option<E>,
+}
+
+impl<F, T, E, const N: usize> Future for RaceOk<F, E, N>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<(usize, T), E>;
+
+    /// Polls each racing future once, starting with a different future on every poll.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Ok((index, output)))` as soon as a future succeeds.
+    /// * `Poll::Ready(Err(error))` with the error of the future that failed last once all have
+    ///   failed.
+    /// * `Poll::Pending` while no future has succeeded and some are still racing.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the futures are never moved out of `RaceOk`. They are only dropped in place
+        // once they fail.
+        let this = unsafe { self.get_unchecked_mut() };
+        let start = this.start;
+        this.start = (start + 1) % N;
+
+        for index in (start..N).chain(0..start) {
+            let Some(future) = this.futures[index].as_mut() else {
+                continue;
+            };
+
+            match unsafe { Pin::new_unchecked(future) }.poll(cx) {
+                Poll::Ready(Ok(output)) => return Poll::Ready(Ok((index, output))),
+                Poll::Ready(Err(error)) => {
+                    this.futures[index] = None;
+                    this.last_error = Some(error);
+                }
+                Poll::Pending => {}
+            }
+        }
+
+        if this.futures.iter().all(Option::is_none)
+            && let Some(error) = this.last_error.take()
+        {
+            return Poll::Ready(Err(error));
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Races fallible futures, resolving with the first one that succeeds.
+///
+/// The returned future resolves to `Ok((index, output))`, where `index` is the position of the
+/// first future to succeed in `futures`. A future that fails is dropped and not polled anymore.
+/// If all futures fail, the returned future resolves to the error of the future that failed last.
+///
+/// Each poll starts with a different future, so no future gets an advantage just because of its
+/// position in `futures`.
+///
+/// `N` must not be zero, otherwise the call fails to compile: with no futures there is neither a
+/// success nor an error to resolve to.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::race_ok;
+///
+/// async fn connect(endpoint: u8) -> Result<u8, &'static str> {
+///     if endpoint == 2 { Ok(endpoint) } else { Err("unreachable") }
+/// }
+///
+/// let mut executor = Executor::<1>::new();
+/// let result = executor.block_on(race_ok([connect(1), connect(2), connect(3)]));
+/// assert_eq!(result, Ok((1, 2)));
+/// ```
+pub fn race_ok<F, T, E, const N: usize>(
+    futures: [F; N],
+) -> impl Future<Output = Result<(usize, T), E>>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    const { assert!(N > 0, "`race_ok` needs at least one future") };
+
+    RaceOk {
+        futures: futures.map(Some),
+        start: 0,
+        last_error: None,
+    }
+}
+
+/// A panic caught by [`catch`].
+#[cfg(feature = "std")]
+pub struct Panic {
+    /// The value the future panicked with.
+    payload: Box<dyn Any + Send>,
+}
+
+#[cfg(feature = "std")]
+impl Panic {
+    /// Returns the value the future panicked with.
+    ///
+    /// For `panic!` invocations the payload is either a `&'static str` or a `String`.
+    #[must_use]
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    /// Consumes the `Panic` returning the value the future panicked with, e.g. to resume the
+    /// panic with [`std::panic::resume_unwind`].
+    #[must_use]
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Debug for Panic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Panic").finish_non_exhaustive()
+    }
+}
+
+/// A future that catches panics raised while polling the inner future.
+#[cfg(feature = "std")]
+struct CatchUnwind<F> {
+    /// The future polled on behalf of the caller.
+    future: F,
+}
+
+#[cfg(feature = "std")]
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, Panic>;
+
+    /// Polls the inner future inside [`catch_unwind`].
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Ok(output))` if the inner future completed.
+    /// * `Poll::Ready(Err(panic))` if the inner future panicked.
+    /// * `Poll::Pending` if the inner future is still pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `CatchUnwind`.
+        let future = unsafe { self.map_unchecked_mut(|this| &mut this.future) };
+
+        match catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(Panic { payload })),
+        }
+    }
+}
+
+/// Converts a panic inside `future` into an error.
+///
+/// Each poll of `future` runs inside [`catch_unwind`], so a panic no longer unwinds through the
+/// executor and the rest of the tasks. The returned future resolves to `Ok(output)` if `future`
+/// completes and to `Err(Panic)` as soon as it panics.
+///
+/// `future` is wrapped into [`AssertUnwindSafe`]: a future that panicked may be left in an
+/// inconsistent state, so it is never polled again after the panic and should only be dropped.
+/// Make sure the state it shares with other tasks is still valid after a caught panic.
+///
+/// Only available with the `std` feature.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// use miniloop::helpers::catch;
+/// let mut executor = Executor::<1>::new();
+/// let result = executor.block_on(catch(async { panic!("boom") }));
+/// assert!(result.is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn catch<F: Future>(future: F) -> impl Future<Output = Result<F::Output, Panic>> {
+    CatchUnwind { future }
+}
+
+/// Pins local values on the stack, shadowing each binding with a `Pin<&mut T>` to the value.
+///
+/// Futures built or combined by hand have to be pinned before they can be polled. The macro does
+/// that in place, so the pinned future keeps the name of the original binding and the unpinned
+/// value cannot be reached anymore. It mirrors the `pin_mut!` macro of the `futures` and `tokio`
+/// crates, for readers following along with their examples.
+///
+/// Since Rust 1.68 the same can be written with [`core::pin::pin!`], which the macro expands to:
+/// `pin_mut!(fut)` is a shorthand for `let mut fut = core::pin::pin!(fut);`. Unlike the stack
+/// boxes the executor keeps its type-erased tasks in, the pinned value lives in the current stack
+/// frame and cannot outlive it.
+///
+/// # Example
+/// ```
+/// use core::task::{Context, Poll, Waker};
+/// use miniloop::helpers::pin_mut;
+///
+/// let fut = async { 42 };
+/// pin_mut!(fut);
+///
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(42));
+/// ```
+#[macro_export]
+macro_rules! pin_mut {
+    ($($x:ident),* $(,)?) => {
+        $(
+            #[allow(unused_mut)]
+            let mut $x = ::core::pin::pin!($x);
+        )*
+    };
+}
+
+pub use crate::pin_mut;
+
+/// Splits a pinned array into pinned references to its elements.
+///
+/// Tasks are spawned pinned, so an array of tasks has to be pinned as a whole, e.g. with
+/// [`core::pin::pin!`], and then split into the pinned tasks to spawn one by one. The elements of
+/// a pinned array can never move either, which makes the split safe.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::executor::Executor;
+/// # use miniloop::task::{Handle, Task};
+/// use core::iter::zip;
+/// use core::pin::pin;
+/// use miniloop::helpers::pin_each;
+///
+/// async fn square(x: u32) -> u32 {
+///     x * x
+/// }
+///
+/// let tasks = pin!([Task::new("first", square(1)), Task::new("second", square(2))]);
+/// let mut handles = [const { Handle::new() }; 2];
+/// let mut executor = Executor::<2>::new();
+///
+/// for (task, handle) in zip(pin_each(tasks), &mut handles) {
+///     executor.spawn(task, handle).expect("Failed to spawn task");
+/// }
+///
+/// executor.run();
+/// assert_eq!(handles.map(|handle| handle.value), [Some(1), Some(4)]);
+/// ```
+#[must_use]
+pub fn pin_each<T, const N: usize>(array: Pin<&mut [T; N]>) -> [Pin<&mut T>; N] {
+    // SAFETY: the elements are pinned for as long as the array is, as it is never moved and its
+    // elements are never moved out of it.
+    unsafe { array.get_unchecked_mut() }
+        .each_mut()
+        .map(|element| unsafe { Pin::new_unchecked(element) })
+}
@@ -0,0 +1,225 @@
+//! # Homogeneous executor implementation
+//!
+//! This module provides [`HomogeneousExecutor`], an executor for tasks that all produce the same
+//! output type. Because every task has the same output type, the executor can hand the outputs
+//! back to the caller itself, so tasks are spawned without a [`Handle`](crate::task::Handle).
+//! This suits pools of identical workers, where managing one handle per task is awkward.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! # use miniloop::homogeneous::HomogeneousExecutor;
+//! # use miniloop::task::Task;
+//! use core::pin::pin;
+//! const TASK_ARRAY_SIZE: usize = 2;
+//! let mut executor = HomogeneousExecutor::<u32, TASK_ARRAY_SIZE>::new();
+//! let task1 = pin!(Task::new("task1", async { 1 }));
+//! let task2 = pin!(Task::new("task2", async { 2 }));
+//! executor.spawn(task1).expect("Failed to spawn task");
+//! executor.spawn(task2).expect("Failed to spawn task");
+//!
+//! let mut values = [None; TASK_ARRAY_SIZE];
+//! executor.drain_completed_values(&mut values);
+//! assert_eq!(values, [Some(1), Some(2)]);
+//! ```
+//!
+//! The executor can also keep the outputs itself, so they are read by indexing the executor with
+//! the [`TaskId`] of a task:
+//!
+//! ```rust
+//! # use miniloop::homogeneous::HomogeneousExecutor;
+//! # use miniloop::task::Task;
+//! use core::pin::pin;
+//! let mut executor = HomogeneousExecutor::<u32, 2>::new();
+//! let task1 = pin!(Task::new("task1", async { 1 }));
+//! let task2 = pin!(Task::new("task2", async { 2 }));
+//! let id1 = executor.spawn(task1).expect("Failed to spawn task");
+//! let id2 = executor.spawn(task2).expect("Failed to spawn task");
+//! executor.run();
+//!
+//! assert_eq!(executor[id1], Some(1));
+//! assert_eq!(executor[id2], Some(2));
+//! ```
+use crate::executor::{Error, PollData, TaskId, create_waker};
+use crate::sbox::{StackBox, StackBoxOutput};
+use crate::task::Task;
+
+use core::future::Future;
+use core::ops::Index;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll};
+
+/// An executor for tasks sharing the output type `T`.
+pub struct HomogeneousExecutor<'a, T, const TASK_ARRAY_SIZE: usize> {
+    /// An array of optional tasks that the executor can manage.
+    tasks: [Option<StackBoxOutput<'a, T>>; TASK_ARRAY_SIZE],
+
+    /// An index indicating the current position in the tasks array.
+    index: usize,
+
+    /// An optional callback function that takes a `&str` argument and is pending execution.
+    pending_callback: Option<fn(&str)>,
+
+    /// The outputs of the completed tasks, indexed by slot.
+    values: [Option<T>; TASK_ARRAY_SIZE],
+}
+
+impl<T, const TASK_ARRAY_SIZE: usize> Default for HomogeneousExecutor<'_, T, TASK_ARRAY_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T, const TASK_ARRAY_SIZE: usize> HomogeneousExecutor<'a, T, TASK_ARRAY_SIZE> {
+    /// Creates a new instance of the `HomogeneousExecutor` struct with all task slots free.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            tasks: [const { None }; TASK_ARRAY_SIZE],
+            index: 0,
+            pending_callback: None,
+            values: [const { None }; TASK_ARRAY_SIZE],
+        }
+    }
+
+    /// Sets the callback function to be invoked when a task is pending.
+    ///
+    /// # Parameters
+    ///
+    /// * `cb`:
+    ///   A function pointer to a callback that takes a `&str` argument.
+    ///   This callback will be called with the task's name when the task is pending.
+    pub fn set_pending_callback(&mut self, cb: fn(&str)) {
+        self.pending_callback = Some(cb);
+    }
+
+    /// Spawns a task into the first free executor slot.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task. Its index is the position the task output is stored at
+    /// by [`HomogeneousExecutor::drain_completed_values`].
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slots in the executor. The error carries the name of
+    ///   the rejected task.
+    pub fn spawn<F>(&mut self, task: Pin<&'a mut Task<'a, F>>) -> Result<TaskId, Error<'a>>
+    where
+        F: Future<Output = T> + 'a,
+    {
+        if self.index >= self.tasks.len() {
+            return Err(Error::NoFreeSlots { name: task.name });
+        }
+
+        let index = self.index;
+        self.index += 1;
+        self.tasks[index] = Some(StackBox::new(task));
+        self.values[index] = None;
+
+        Ok(TaskId::new(index))
+    }
+
+    /// Executes tasks until all of them are completed, keeping their outputs in the executor.
+    ///
+    /// The output of a task is read by indexing the executor with the [`TaskId`] returned by
+    /// [`HomogeneousExecutor::spawn`].
+    ///
+    /// <div class="warning">
+    /// That call does not return till all tasks are finished theirs execution.
+    /// </div>
+    pub fn run(&mut self) {
+        loop {
+            let mut live = self.tasks.iter().flatten().count();
+
+            for (task, value) in self.tasks.iter_mut().zip(self.values.iter_mut()) {
+                let output = task.as_mut().map_or(Poll::Pending, |task| {
+                    poll_output(task, self.pending_callback, live > 1)
+                });
+
+                if let Poll::Ready(output) = output {
+                    *value = output;
+                    task.take();
+                    live -= 1;
+                }
+            }
+
+            if self.tasks.iter().all(Option::is_none) {
+                return;
+            }
+        }
+    }
+
+    /// Executes tasks until all of them are completed, collecting their outputs.
+    ///
+    /// The output of each task is moved into `values` at the index of the slot the task was
+    /// spawned into, including the outputs kept by an earlier [`HomogeneousExecutor::run`].
+    /// Entries for slots without an output are left untouched.
+    ///
+    /// <div class="warning">
+    /// That call does not return till all tasks are finished theirs execution.
+    /// </div>
+    pub fn drain_completed_values(&mut self, values: &mut [Option<T>; TASK_ARRAY_SIZE]) {
+        self.run();
+
+        for (kept, value) in self.values.iter_mut().zip(values.iter_mut()) {
+            if kept.is_some() {
+                *value = kept.take();
+            }
+        }
+    }
+}
+
+impl<T, const TASK_ARRAY_SIZE: usize> Index<TaskId>
+    for HomogeneousExecutor<'_, T, TASK_ARRAY_SIZE>
+{
+    type Output = Option<T>;
+
+    /// Returns the output of the task with the given `id`, `None` until the task completes, if the
+    /// task has aborted itself or after the output is drained with
+    /// [`HomogeneousExecutor::drain_completed_values`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not refer to a slot of this executor.
+    fn index(&self, id: TaskId) -> &Self::Output {
+        &self.values[id.index()]
+    }
+}
+
+/// Polls a given task and optionally calls a callback function if the task is pending.
+///
+/// `contended` tells the task whether other tasks are waiting to be polled.
+///
+/// # Returns
+///
+/// * `Poll::Ready(Some(output))` if the task has completed.
+/// * `Poll::Ready(None)` if the task has aborted itself with
+///   [`abort_self`](crate::helpers::abort_self).
+/// * `Poll::Pending` if the task is still pending.
+fn poll_output<T>(
+    task: &mut StackBoxOutput<T>,
+    cb: Option<fn(&str)>,
+    contended: bool,
+) -> Poll<Option<T>> {
+    let Some(future) = task.value.get_mut() else {
+        return Poll::Pending;
+    };
+    let contended = || contended;
+    let data = PollData::new(future.context(), &contended);
+    let waker = create_waker(ptr::from_ref(&data).cast());
+    let context = &mut Context::from_waker(&waker);
+
+    match future.as_mut().poll_output(context) {
+        Poll::Ready(output) => Poll::Ready(Some(output)),
+        Poll::Pending if data.abort.get() => Poll::Ready(None),
+        Poll::Pending => {
+            if let Some(cb) = cb {
+                cb(future.name().unwrap_or(""));
+            }
+
+            Poll::Pending
+        }
+    }
+}
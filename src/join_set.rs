@@ -0,0 +1,127 @@
+//! # `JoinSet` implementation
+//!
+//! This module provides [`JoinSet`], a collection of futures that is driven as a whole and yields
+//! the output of each future in the order they complete. Unlike tasks spawned into an
+//! [`Executor`](crate::executor::Executor), the futures are allocated on the heap, so the number
+//! of futures tracked by a set is not fixed at compile time.
+//!
+//! Only available with the `alloc` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! use miniloop::join_set::JoinSet;
+//!
+//! let mut executor = Executor::<1>::new();
+//! let sum = executor.block_on(async {
+//!     let mut set = JoinSet::new();
+//!
+//!     for i in 1..=3u32 {
+//!         set.spawn(async move { i * 10 });
+//!     }
+//!
+//!     let mut sum = 0;
+//!
+//!     while let Some(value) = set.join_next().await {
+//!         sum += value;
+//!     }
+//!
+//!     sum
+//! });
+//! assert_eq!(sum, 60);
+//! ```
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A collection of futures producing values of the same type `T`.
+///
+/// Futures are added with [`JoinSet::spawn`] and polled only while the future returned by
+/// [`JoinSet::join_next`] is being awaited. Each `join_next` resolves to the output of the next
+/// future to complete and removes that future from the set.
+///
+/// # Type Parameters
+/// - `'a`: The lifetime of the data borrowed by the stored futures.
+/// - `T`: The output type of the stored futures.
+pub struct JoinSet<'a, T> {
+    /// Futures that have not completed yet.
+    futures: Vec<Pin<Box<dyn Future<Output = T> + 'a>>>,
+}
+
+impl<T> Default for JoinSet<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> JoinSet<'a, T> {
+    /// Creates an empty `JoinSet`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            futures: Vec::new(),
+        }
+    }
+
+    /// Adds a future to the set.
+    ///
+    /// The future does not make progress until the set is joined with [`JoinSet::join_next`].
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = T> + 'a,
+    {
+        self.futures.push(Box::pin(future));
+    }
+
+    /// Returns the number of futures that have not completed yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+
+    /// Returns `true` if the set holds no futures.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// Waits for the next future in the set to complete.
+    ///
+    /// # Returns
+    ///
+    /// A future that resolves to:
+    /// * `Some(output)` - the output of the next future to complete.
+    /// * `None` - if the set is empty.
+    pub fn join_next(&mut self) -> JoinNext<'_, 'a, T> {
+        JoinNext { set: self }
+    }
+}
+
+/// A future returned by [`JoinSet::join_next`].
+pub struct JoinNext<'s, 'a, T> {
+    set: &'s mut JoinSet<'a, T>,
+}
+
+impl<T> Future for JoinNext<'_, '_, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let futures = &mut self.get_mut().set.futures;
+
+        if futures.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        for i in 0..futures.len() {
+            if let Poll::Ready(value) = futures[i].as_mut().poll(cx) {
+                drop(futures.swap_remove(i));
+                return Poll::Ready(Some(value));
+            }
+        }
+
+        Poll::Pending
+    }
+}
@@ -7,61 +7,90 @@
 //!
 //! - **No Standard Library**: This crate is `#![no_std]`, making it suitable for embedded and
 //!   other constrained environments.
+//! - **Optional `alloc` support**: The `alloc` feature enables helpers built on heap-allocated
+//!   futures, such as `JoinSet` or shared futures.
+//! - **Optional `std` support**: The `std` feature enables instrumentation that needs the standard
+//!   library, such as measuring how long each task keeps the executor busy. It implies `alloc`.
 //! - **Simple API**: Easy to use API to spawn and run tasks.
 //! - **Educational Purpose**: Designed with learning in mind, this crate breaks down the concepts
 //!   of executors to their simplest form.
 //!
 //! ## Modules
 //!
+//! - [`channel`]: A bounded channel passing values between two tasks.
+//! - [`completion_queue`]: A queue of task completions pushed by the executor.
+//! - [`coroutine`]: Tasks yielding intermediate values to their owner.
+//! - [`event_log`]: A buffer of scheduling events recorded by the executor.
 //! - [`executor`]: Contains the core executor implementation.
 //! - [`helpers`]: Utility functions and types to assist with task management.
+//! - [`homogeneous`]: An executor for tasks sharing the same output type.
+//! - `join_set`: A growable collection of futures joined in completion order (`alloc` only).
+//! - [`pool`]: Reusable slots for many short-lived tasks of the same type.
+//! - [`reactor`]: Parking tasks until an I/O source is ready.
+//! - [`snapshot`]: Copies of the observable scheduling state of an executor.
+//! - [`stream`]: Asynchronous sequences of values.
+//! - [`sync`]: Primitives for coordinating tasks with each other.
 //! - [`task`]: Definitions and management of tasks.
+//! - [`telemetry`]: A single sink for the metrics of an executor.
+//! - [`time`]: Cooperative waiting on a user-provided time source.
+//! - [`wake`]: Tracking the wakes of the tasks of an executor.
 //!
 //! ## Examples
 //!
 //! ### Spawning and Running a Single Task
 //!
 //! ```rust,no_run
+//! use core::pin::pin;
 //! use miniloop::executor::Executor;
-//! use miniloop::task::Task;
+//! use miniloop::task::{Handle, Task};
 //!
 //! const TASK_ARRAY_SIZE: usize = 1;
 //! let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
 //!
-//! let mut task = Task::new("task", async {
+//! let task = pin!(Task::new("task", async {
 //!     println!("Hello, world!");
-//! });
-//! let mut handle = task.create_handle();
+//! }));
+//! let mut handle = Handle::new();
 //!
-//! executor.spawn(&mut task, &mut handle).expect("Failed to spawn task");
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
 //! executor.run();
 //! ```
 //!
 //! ### Handling Multiple Tasks
 //!
 //! ```rust,no_run
+//! use core::pin::pin;
 //! use miniloop::executor::Executor;
-//! use miniloop::task::Task;
+//! use miniloop::task::{Handle, Task};
 //!
 //! const TASK_ARRAY_SIZE: usize = 2;
 //! let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
 //!
-//! let mut task1 = Task::new("task1", async {
+//! let task1 = pin!(Task::new("task1", async {
 //!     println!("Task 1 executed");
-//! });
-//! let mut handle1 = task1.create_handle();
+//! }));
+//! let mut handle1 = Handle::new();
 //!
-//! let mut task2 = Task::new("task2", async {
+//! let task2 = pin!(Task::new("task2", async {
 //!     println!("Task 2 executed");
-//! });
-//! let mut handle2 = task2.create_handle();
+//! }));
+//! let mut handle2 = Handle::new();
 //!
-//! executor.spawn(&mut task1, &mut handle1).expect("Failed to spawn task 1");
-//! executor.spawn(&mut task2, &mut handle2).expect("Failed to spawn task 2");
+//! executor.spawn(task1, &mut handle1).expect("Failed to spawn task 1");
+//! executor.spawn(task2, &mut handle2).expect("Failed to spawn task 2");
 //!
 //! executor.run();
 //! ```
 //!
+//! ### Running a Single Future
+//!
+//! [`block_on`] runs a single future to completion without creating an executor:
+//!
+//! ```rust
+//! let answer = miniloop::block_on(async { 40 + 2 });
+//! assert_eq!(answer, 42);
+//! ```
+//!
 //! ## Testing
 //!
 //! This crate includes several tests demonstrating its usage and ensuring its correctness. The tests cover:
@@ -78,23 +107,69 @@
 //! Happy learning!
 //!
 #![no_std]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod channel;
+pub mod completion_queue;
+pub mod coroutine;
+pub mod event_log;
 pub mod executor;
 pub mod helpers;
+pub mod homogeneous;
+#[cfg(feature = "alloc")]
+pub mod join_set;
+pub mod pool;
+pub mod reactor;
+pub mod snapshot;
+pub mod stream;
+pub mod sync;
 pub mod task;
+pub mod telemetry;
+pub mod time;
+pub mod wake;
 
 pub(crate) mod sbox;
 
+pub use executor::block_on;
+
 #[cfg(test)]
 mod test {
-    use super::executor::Executor;
-    use super::task::Task;
+    use super::executor::{Error, Executor};
+    use super::helpers::{current_context, pin_each, poll_immediate, yield_me};
+    use super::homogeneous::HomogeneousExecutor;
+    use super::sync::Notify;
+    use super::task::{Handle, Task};
+    use super::time::{TimeSource, sleep_until};
+    use super::wake::WakeTable;
 
+    use core::cell::{Cell, RefCell};
     use core::future::Future;
     use core::iter::zip;
-    use core::pin::Pin;
+    use core::pin::{Pin, pin};
     use core::task::{Context, Poll};
     const TASK_ARRAY_SIZE: usize = 256;
 
+    /// A time source that only moves when a test advances it.
+    #[derive(Default)]
+    struct MockClock {
+        now: Cell<u64>,
+    }
+
+    impl MockClock {
+        fn advance(&self, ticks: u64) {
+            self.now.set(self.now.get() + ticks);
+        }
+    }
+
+    impl TimeSource for MockClock {
+        fn now(&self) -> u64 {
+            self.now.get()
+        }
+    }
+
     struct MyTestFuture(bool);
 
     impl MyTestFuture {
@@ -115,22 +190,23 @@ mod test {
     #[test]
     fn test_one_future() {
         let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
-        let mut task = Task::new("my_test_task", MyTestFuture::default());
-        let mut handle = task.create_handle();
-        let result = executor.spawn(&mut task, &mut handle);
+        let task = pin!(Task::new("my_test_task", MyTestFuture::default()));
+        let mut handle = Handle::new();
+        let result = executor.spawn(task, &mut handle);
         assert!(result.is_ok());
         executor.run();
         assert!(handle.value.is_some_and(|v| v == 42u8));
     }
 
     #[test]
+    #[allow(clippy::large_stack_arrays)] // a full executor worth of tasks
     fn test_multiple_futures() {
-        let mut task_array =
-            [const { Task::new_nameless(MyTestFuture::default()) }; TASK_ARRAY_SIZE];
-        let mut handles = [(); TASK_ARRAY_SIZE].map(|()| task_array[0].create_handle());
+        let task_array =
+            pin!([const { Task::new_nameless(MyTestFuture::default()) }; TASK_ARRAY_SIZE]);
+        let mut handles = [const { Handle::new() }; TASK_ARRAY_SIZE];
         let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
 
-        for (task, handle) in zip(&mut task_array, &mut handles) {
+        for (task, handle) in zip(pin_each(task_array), &mut handles) {
             let result = executor.spawn(task, handle);
             assert!(result.is_ok(), "Failed to spawn task");
         }
@@ -148,13 +224,14 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::large_stack_arrays)] // a full executor worth of tasks
     fn test_schedule_too_many_tasks() {
-        let mut task_array =
-            [const { Task::new_nameless(MyTestFuture::default()) }; TASK_ARRAY_SIZE + 1];
-        let mut handles = [(); TASK_ARRAY_SIZE].map(|()| task_array[0].create_handle());
+        let task_array =
+            pin!([const { Task::new_nameless(MyTestFuture::default()) }; TASK_ARRAY_SIZE + 1]);
+        let mut handles = [const { Handle::new() }; TASK_ARRAY_SIZE];
         let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
 
-        for (i, (task, handle)) in zip(&mut task_array, &mut handles).enumerate() {
+        for (i, (task, handle)) in zip(pin_each(task_array), &mut handles).enumerate() {
             let result = executor.spawn(task, handle);
 
             if i < TASK_ARRAY_SIZE {
@@ -167,25 +244,3812 @@ mod test {
 
     #[test]
     fn test_different_return_type_tasks() {
-        let mut task1 = Task::new("task1", async { 1u32 });
-        let mut handle1 = task1.create_handle();
-        let mut task2 = Task::new("task1", async {
+        let task1 = pin!(Task::new("task1", async { 1u32 }));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task1", async {
             if false {
                 return Err(());
             }
 
             Ok(2u32)
-        });
-        let mut handle2 = task2.create_handle();
+        }));
+        let mut handle2 = Handle::new();
         let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
 
-        let result = executor.spawn(&mut task1, &mut handle1);
+        let result = executor.spawn(task1, &mut handle1);
         assert!(result.is_ok());
-        let result = executor.spawn(&mut task2, &mut handle2);
+        let result = executor.spawn(task2, &mut handle2);
         assert!(result.is_ok());
         executor.run();
 
         assert_eq!(handle1.value, Some(1u32));
         assert_eq!(handle2.value, Some(Ok(2u32)));
     }
+
+    #[test]
+    fn test_notify_before_wait() {
+        let notify = Notify::new();
+        notify.notify_one();
+
+        let task = pin!(Task::new("waiter", async {
+            notify.notified().await;
+            true
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(handle.value, Some(true));
+    }
+
+    #[test]
+    fn test_notify_wait_before_notify() {
+        let notify = Notify::new();
+        let step = Cell::new(0u8);
+
+        let waiter = pin!(Task::new("waiter", async {
+            step.set(1);
+            notify.notified().await;
+            // the notifier must have run before the waiter got through
+            step.get()
+        }));
+        let mut waiter_handle = Handle::new();
+        let notifier = pin!(Task::new("notifier", async {
+            yield_me().await;
+            assert_eq!(step.get(), 1);
+            step.set(2);
+            notify.notify_one();
+        }));
+        let mut notifier_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(waiter, &mut waiter_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(notifier, &mut notifier_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(waiter_handle.value, Some(2));
+        assert!(notifier_handle.value.is_some());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_busy_time() {
+        use std::time::Duration;
+
+        const WORK: Duration = Duration::from_millis(5);
+        let busy = pin!(Task::new("busy", async {
+            std::thread::sleep(WORK);
+            yield_me().await;
+            std::thread::sleep(WORK);
+        }));
+        let mut busy_handle = Handle::new();
+        let idle = pin!(Task::new("idle", async {
+            yield_me().await;
+        }));
+        let mut idle_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let busy_id = executor.spawn(busy, &mut busy_handle).unwrap();
+        let idle_id = executor.spawn(idle, &mut idle_handle).unwrap();
+        assert_eq!(executor.busy_time(busy_id), Some(Duration::ZERO));
+        executor.run();
+
+        let busy_time = executor.busy_time(busy_id).unwrap();
+        let idle_time = executor.busy_time(idle_id).unwrap();
+        assert!(busy_time >= 2 * WORK);
+        assert!(idle_time < busy_time);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_join_set() {
+        use super::join_set::JoinSet;
+        use alloc::vec::Vec;
+
+        let mut executor = Executor::<1>::new();
+        let outputs = executor.block_on(async {
+            let mut set = JoinSet::new();
+
+            // the first future needs the most polls, so it completes last
+            for (output, yields) in [(0u32, 2), (1, 1), (2, 0)] {
+                set.spawn(async move {
+                    for _ in 0..yields {
+                        yield_me().await;
+                    }
+
+                    output
+                });
+            }
+
+            assert_eq!(set.len(), 3);
+            let mut outputs = Vec::new();
+
+            while let Some(output) = set.join_next().await {
+                outputs.push(output);
+            }
+
+            assert!(set.is_empty());
+            outputs
+        });
+
+        assert_eq!(outputs, [2, 1, 0]);
+    }
+
+    #[test]
+    fn test_poll_immediate() {
+        let mut executor = Executor::<1>::new();
+
+        assert_eq!(
+            executor.block_on(poll_immediate(MyTestFuture::default())),
+            Some(42)
+        );
+        assert_eq!(executor.block_on(poll_immediate(yield_me())), None);
+    }
+
+    #[test]
+    fn test_task_context() {
+        struct Config {
+            retries: u8,
+        }
+
+        static CONFIG: Config = Config { retries: 3 };
+
+        let task1 = pin!(
+            Task::new("task1", async {
+                yield_me().await;
+                current_context::<Config>()
+                    .await
+                    .map(|config| config.retries)
+            })
+            .with_context(&CONFIG)
+        );
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", async {
+            current_context::<u32>().await.is_none() && current_context::<Config>().await.is_none()
+        }));
+        let mut handle2 = Handle::new();
+        let task3 = pin!(
+            Task::new("task3", async { current_context::<u32>().await.is_none() })
+                .with_context(&CONFIG)
+        );
+        let mut handle3 = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        let result = executor.spawn(task3, &mut handle3);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(handle1.value, Some(Some(3)));
+        assert_eq!(handle2.value, Some(true));
+        assert_eq!(handle3.value, Some(true));
+        assert!(
+            Executor::<1>::new()
+                .block_on(current_context::<Config>())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_homogeneous_executor() {
+        const WORKERS: usize = 4;
+        let tasks = pin!(core::array::from_fn::<_, WORKERS, _>(|i| {
+            Task::new_nameless(async move {
+                for _ in 0..i {
+                    yield_me().await;
+                }
+
+                i * 10
+            })
+        }));
+        let mut executor = HomogeneousExecutor::<usize, WORKERS>::new();
+
+        for (i, task) in pin_each(tasks).into_iter().enumerate() {
+            let result = executor.spawn(task);
+            assert!(result.is_ok_and(|id| id.index() == i));
+        }
+
+        let mut values = [None; WORKERS];
+        executor.drain_completed_values(&mut values);
+
+        assert_eq!(values, [Some(0), Some(10), Some(20), Some(30)]);
+    }
+
+    #[test]
+    fn test_task_chain() {
+        let task = pin!(
+            Task::new("chain", async {
+                yield_me().await;
+                21u32
+            })
+            .chain(|v| async move {
+                yield_me().await;
+                u64::from(v) * 2
+            })
+        );
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(handle.value, Some(42u64));
+    }
+
+    #[test]
+    fn test_spawn_error_names_rejected_task() {
+        let task1 = pin!(Task::new("task1", MyTestFuture::default()));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", MyTestFuture::default()));
+        let mut handle2 = Handle::new();
+        let task3 = pin!(Task::new_nameless(MyTestFuture::default()));
+        let mut handle3 = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert_eq!(
+            result,
+            Err(Error::NoFreeSlots {
+                name: Some("task2")
+            })
+        );
+        let result = executor.spawn(task3, &mut handle3);
+        assert_eq!(result, Err(Error::NoFreeSlots { name: None }));
+    }
+
+    #[test]
+    fn test_sleep_until() {
+        let clock = MockClock::default();
+        let wakeups = RefCell::new([("", 0u64); 2]);
+        let woken = Cell::new(0usize);
+        let record = |name| {
+            wakeups.borrow_mut()[woken.get()] = (name, clock.now());
+            woken.set(woken.get() + 1);
+        };
+
+        let late = pin!(Task::new("late", async {
+            sleep_until(&clock, 5).await;
+            record("late");
+        }));
+        let mut late_handle = Handle::new();
+        let early = pin!(Task::new("early", async {
+            sleep_until(&clock, 3).await;
+            record("early");
+        }));
+        let mut early_handle = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            while woken.get() < 2 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let result = executor.spawn(late, &mut late_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(early, &mut early_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(ticker, &mut ticker_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(*wakeups.borrow(), [("early", 3), ("late", 5)]);
+    }
+
+    #[test]
+    fn test_last_polled() {
+        let clock = MockClock::default();
+        let task1 = pin!(Task::new("task1", async {
+            for _ in 0..3 {
+                yield_me().await;
+            }
+        }));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", async {
+            yield_me().await;
+        }));
+        let mut handle2 = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            for _ in 0..5 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_time_source(&clock);
+
+        let id1 = executor.spawn(task1, &mut handle1).unwrap();
+        let id2 = executor.spawn(task2, &mut handle2).unwrap();
+        let ticker_id = executor.spawn(ticker, &mut ticker_handle).unwrap();
+        assert_eq!(executor.last_polled(id1), None);
+        executor.run();
+
+        // the ticker advances the clock after the other tasks are polled in each pass
+        assert_eq!(executor.last_polled(id1), Some(3));
+        assert_eq!(executor.last_polled(id2), Some(1));
+        assert_eq!(executor.last_polled(ticker_id), Some(5));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_catch() {
+        use super::helpers::catch;
+
+        let polls = Cell::new(0u32);
+        let task1 = pin!(Task::new("panicking", async {
+            catch(async {
+                polls.set(polls.get() + 1);
+                yield_me().await;
+                polls.set(polls.get() + 1);
+                panic!("second poll");
+            })
+            .await
+        }));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("healthy", catch(async { 42 })));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        executor.run();
+
+        let panic = handle1.value.unwrap().unwrap_err();
+        assert_eq!(panic.payload().downcast_ref::<&str>(), Some(&"second poll"));
+        assert_eq!(polls.get(), 2);
+        assert_eq!(handle2.value.unwrap().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        const TASKS: usize = 4;
+        let active = Cell::new(0usize);
+        let max_active = Cell::new(0usize);
+        let completed = Cell::new(0usize);
+        let completed_before_start = [const { Cell::new(usize::MAX) }; TASKS];
+        let worker = |i: usize| {
+            let active = &active;
+            let max_active = &max_active;
+            let completed = &completed;
+            let completed_before_start = &completed_before_start;
+
+            async move {
+                completed_before_start[i].set(completed.get());
+                active.set(active.get() + 1);
+                max_active.set(max_active.get().max(active.get()));
+
+                for _ in 0..3 {
+                    yield_me().await;
+                }
+
+                active.set(active.get() - 1);
+                completed.set(completed.get() + 1);
+            }
+        };
+        let tasks = pin!(core::array::from_fn::<_, TASKS, _>(|i| Task::new_nameless(
+            worker(i)
+        )));
+        let mut handles = [const { Handle::new() }; TASKS];
+        let mut executor = Executor::<TASKS>::new();
+        executor.set_concurrency_limit(2);
+
+        for (task, handle) in zip(pin_each(tasks), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        executor.run();
+
+        assert!(handles.iter().all(|handle| handle.value.is_some()));
+        assert_eq!(max_active.get(), 2);
+        assert_eq!(completed_before_start[0].get(), 0);
+        assert_eq!(completed_before_start[1].get(), 0);
+        assert!(completed_before_start[2].get() >= 1);
+        assert!(completed_before_start[3].get() >= 1);
+    }
+
+    #[test]
+    fn test_task_with_timeout() {
+        use super::time::TimedOut;
+
+        let clock = MockClock::default();
+        let long = pin!(
+            Task::new("long", async {
+                for _ in 0..10 {
+                    yield_me().await;
+                }
+
+                1u32
+            })
+            .with_timeout(&clock, 3)
+        );
+        let mut long_handle = Handle::new();
+        let short = pin!(
+            Task::new("short", async {
+                yield_me().await;
+                2u32
+            })
+            .with_timeout(&clock, 3)
+        );
+        let mut short_handle = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            for _ in 0..10 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let result = executor.spawn(long, &mut long_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(short, &mut short_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(ticker, &mut ticker_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(long_handle.value, Some(Err(TimedOut)));
+        assert_eq!(short_handle.value, Some(Ok(2)));
+    }
+
+    #[test]
+    fn test_pass_count() {
+        let yielding = |yields: usize| async move {
+            for _ in 0..yields {
+                yield_me().await;
+            }
+        };
+        let task1 = pin!(Task::new("task1", yielding(1)));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", yielding(3)));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<2>::new();
+        assert_eq!(executor.pass_count(), 0);
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the task yielding 3 times completes on its 4th poll
+        assert_eq!(executor.pass_count(), 4);
+
+        executor.reset_pass_count();
+        assert_eq!(executor.pass_count(), 0);
+    }
+
+    #[test]
+    fn test_zip_streams() {
+        use super::helpers::zip;
+        use super::stream::{Stream, iter, next};
+
+        /// Counts up to a limit, being pending before every item.
+        struct SlowCounter {
+            next: u32,
+            limit: u32,
+            ready: bool,
+        }
+
+        impl Stream for SlowCounter {
+            type Item = u32;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u32>> {
+                if !self.ready {
+                    self.ready = true;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+
+                self.ready = false;
+
+                if self.next == self.limit {
+                    return Poll::Ready(None);
+                }
+
+                self.next += 1;
+                Poll::Ready(Some(self.next))
+            }
+        }
+
+        let task = pin!(Task::new("zip", async {
+            let slow = SlowCounter {
+                next: 0,
+                limit: 3,
+                ready: false,
+            };
+            let mut pairs = zip(slow, iter(['a', 'b', 'c', 'd', 'e']));
+            let mut collected = [(0, ' '); 4];
+            let mut count = 0;
+
+            while let Some(pair) = next(&mut pairs).await {
+                collected[count] = pair;
+                count += 1;
+            }
+
+            (collected, count)
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        let (collected, count) = handle.value.unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(collected[..count], [(1, 'a'), (2, 'b'), (3, 'c')]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "the task is already spawned into the executor")]
+    fn test_spawn_duplicate_task_detected() {
+        let task_array = pin!([const { Task::new_nameless(MyTestFuture::default()) }; 2]);
+        let mut handles = [const { Handle::new() }; 2];
+        let first = core::ptr::from_ref(&task_array[0]).cast();
+        let mut executor = Executor::<2>::new();
+
+        for (task, handle) in zip(pin_each(task_array), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        // spawning the first task again through an aliasing pointer would be caught
+        executor.debug_assert_not_spawned(first);
+    }
+
+    #[test]
+    fn test_task_on_complete() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static OBSERVED: AtomicU32 = AtomicU32::new(0);
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+
+        let task = pin!(
+            Task::new("observed", async {
+                yield_me().await;
+                Ok::<u32, ()>(7)
+            })
+            .on_complete(|output| {
+                OBSERVED.store(*output.as_ref().unwrap(), Ordering::Relaxed);
+                CALLS.fetch_add(1, Ordering::Relaxed);
+            })
+        );
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(OBSERVED.load(Ordering::Relaxed), 7);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+        assert_eq!(handle.value, Some(Ok(7)));
+    }
+
+    #[test]
+    fn test_event_log() {
+        use super::event_log::{Event, EventKind, OverflowPolicy};
+
+        let mut buffer = [None; 16];
+        let task1 = pin!(Task::new("task1", async {
+            yield_me().await;
+        }));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", MyTestFuture::default()));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_event_log(&mut buffer, OverflowPolicy::Stop);
+
+        let id1 = executor.spawn(task1, &mut handle1).unwrap();
+        let id2 = executor.spawn(task2, &mut handle2).unwrap();
+        executor.run();
+
+        let event = |kind, id, pass| Event {
+            kind,
+            id,
+            label: None,
+            pass,
+        };
+        let expected = [
+            event(EventKind::Spawn, id1, 0),
+            event(EventKind::Spawn, id2, 0),
+            event(EventKind::Poll, id1, 1),
+            event(EventKind::Pending, id1, 1),
+            event(EventKind::Poll, id2, 1),
+            event(EventKind::Complete, id2, 1),
+            event(EventKind::Poll, id1, 2),
+            event(EventKind::Complete, id1, 2),
+        ];
+        assert!(executor.events().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_event_log_overflow() {
+        use super::event_log::{Event, EventKind, OverflowPolicy};
+
+        let yielding = || async {
+            for _ in 0..4 {
+                yield_me().await;
+            }
+        };
+        let kind_and_pass = |event: &Event| (event.kind, event.pass);
+
+        // the task is polled 5 times, producing 11 events in total
+        for (policy, first, last) in [
+            (
+                OverflowPolicy::Stop,
+                (EventKind::Spawn, 0),
+                (EventKind::Poll, 2),
+            ),
+            (
+                OverflowPolicy::OverwriteOldest,
+                (EventKind::Poll, 4),
+                (EventKind::Complete, 5),
+            ),
+        ] {
+            let mut buffer = [None; 4];
+            let task = pin!(Task::new("task", yielding()));
+            let mut handle = Handle::new();
+            let mut executor = Executor::<1>::new();
+            executor.set_event_log(&mut buffer, policy);
+
+            let result = executor.spawn(task, &mut handle);
+            assert!(result.is_ok());
+            executor.run();
+
+            assert_eq!(executor.events().count(), 4);
+            assert_eq!(executor.events().next().map(kind_and_pass), Some(first));
+            assert_eq!(executor.events().last().map(kind_and_pass), Some(last));
+        }
+    }
+
+    #[test]
+    fn test_task_future_accessors() {
+        let mut task = Task::new("inspected", MyTestFuture::default());
+        assert!(!task.future().0);
+
+        let mut executor = Executor::<1>::new();
+        assert_eq!(executor.block_on(poll_immediate(&mut task)), Some(()));
+        assert!(task.future().0);
+
+        task.future_mut().0 = false;
+        assert!(!task.future().0);
+    }
+
+    #[test]
+    fn test_yield_if() {
+        use super::helpers::yield_if;
+
+        let task = pin!(Task::new("task", async {
+            for counter in 0..8 {
+                yield_if(counter % 4 == 3).await;
+            }
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the task yields twice, so it completes on its 3rd poll
+        assert_eq!(executor.pass_count(), 3);
+    }
+
+    #[test]
+    fn test_run_until_predicate() {
+        let progress = Cell::new(0u32);
+        let counter = pin!(Task::new("counter", async {
+            for _ in 0..10 {
+                progress.set(progress.get() + 1);
+                yield_me().await;
+            }
+        }));
+        let mut counter_handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(counter, &mut counter_handle);
+        assert!(result.is_ok());
+
+        assert!(executor.run_until_predicate(&progress, |progress| progress.get() >= 4));
+        assert_eq!(progress.get(), 4);
+        assert_eq!(executor.pass_count(), 4);
+
+        // the task is left in its slot and keeps running afterwards
+        assert!(!executor.run_until_predicate(&progress, |progress| progress.get() > 10));
+        assert_eq!(progress.get(), 10);
+    }
+
+    #[test]
+    fn test_homogeneous_executor_index() {
+        const WORKERS: usize = 3;
+        let tasks = pin!(core::array::from_fn::<_, WORKERS, _>(|i| {
+            Task::new_nameless(async move {
+                for _ in 0..WORKERS - i {
+                    yield_me().await;
+                }
+
+                i + 1
+            })
+        }));
+        let mut executor = HomogeneousExecutor::<usize, WORKERS>::new();
+        let mut ids = [None; WORKERS];
+
+        for (task, id) in pin_each(tasks).into_iter().zip(ids.iter_mut()) {
+            let result = executor.spawn(task);
+            assert!(result.is_ok());
+            *id = result.ok();
+        }
+
+        let ids = ids.map(Option::unwrap);
+        assert!(ids.iter().all(|&id| executor[id].is_none()));
+        executor.run();
+
+        assert_eq!(ids.map(|id| executor[id]), [Some(1), Some(2), Some(3)]);
+
+        // draining moves the outputs out of the executor
+        let mut values = [None; WORKERS];
+        executor.drain_completed_values(&mut values);
+        assert_eq!(values, [Some(1), Some(2), Some(3)]);
+        assert!(ids.iter().all(|&id| executor[id].is_none()));
+    }
+
+    #[test]
+    fn test_step_task_until_pending() {
+        let progress = Cell::new(0u32);
+        let stepped = pin!(Task::new("stepped", async {
+            progress.set(1);
+            yield_me().await;
+            progress.set(2);
+            yield_me().await;
+            progress.set(3);
+        }));
+        let mut stepped_handle = Handle::new();
+        let frozen_polls = Cell::new(0u32);
+        let frozen = pin!(Task::new("frozen", async {
+            loop {
+                frozen_polls.set(frozen_polls.get() + 1);
+                yield_me().await;
+            }
+        }));
+        let mut frozen_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(stepped, &mut stepped_handle);
+        assert!(result.is_ok());
+        let id = result.unwrap();
+        let result = executor.spawn(frozen, &mut frozen_handle);
+        assert!(result.is_ok());
+
+        assert_eq!(executor.step_task_until_pending(id), Poll::Pending);
+        assert_eq!(progress.get(), 1);
+        assert_eq!(executor.step_task_until_pending(id), Poll::Pending);
+        assert_eq!(progress.get(), 2);
+        assert_eq!(executor.step_task_until_pending(id), Poll::Ready(()));
+        assert_eq!(progress.get(), 3);
+        assert_eq!(executor.step_task_until_pending(id), Poll::Ready(()));
+
+        assert_eq!(frozen_polls.get(), 0);
+        assert_eq!(executor.pass_count(), 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_shared_future() {
+        use super::helpers::shared;
+
+        let completions = Cell::new(0u32);
+        let mut executor = Executor::<1>::new();
+        let outputs = executor.block_on(async {
+            let first = shared(async {
+                yield_me().await;
+                completions.set(completions.get() + 1);
+                [1u8, 2, 3]
+            });
+            let second = first.clone();
+
+            // the first clone gets the inner future pending, the second one completes it
+            assert_eq!(poll_immediate(first.clone()).await, None);
+            let second_output = second.await;
+
+            (first.await, second_output)
+        });
+
+        assert_eq!(outputs.0, outputs.1);
+        assert_eq!(outputs.0, [1, 2, 3]);
+        assert_eq!(completions.get(), 1);
+    }
+
+    #[test]
+    fn test_abort_self() {
+        use super::event_log::{EventKind, OverflowPolicy};
+        use super::helpers::abort_self;
+
+        let resumed = Cell::new(false);
+        let aborting = pin!(Task::new("aborting", async {
+            yield_me().await;
+            abort_self().await;
+            resumed.set(true);
+            42
+        }));
+        let mut aborting_handle = Handle::new();
+        let sibling = pin!(Task::new("sibling", async {
+            for _ in 0..3 {
+                yield_me().await;
+            }
+
+            7
+        }));
+        let mut sibling_handle = Handle::new();
+        let mut buffer = [None; 16];
+        let mut executor = Executor::<2>::new();
+        executor.set_event_log(&mut buffer, OverflowPolicy::Stop);
+
+        let result = executor.spawn(aborting, &mut aborting_handle);
+        assert!(result.is_ok());
+        let aborting_id = result.unwrap();
+        let result = executor.spawn(sibling, &mut sibling_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the slot is freed on the second pass, the poll that awaited `abort_self`
+        assert!(executor.events().any(|event| event.kind == EventKind::Abort
+            && event.id == aborting_id
+            && event.pass == 2));
+        assert_eq!(
+            executor.step_task_until_pending(aborting_id),
+            Poll::Ready(())
+        );
+
+        assert!(!resumed.get());
+        assert_eq!(aborting_handle.value, None);
+        assert_eq!(sibling_handle.value, Some(7));
+    }
+
+    #[test]
+    fn test_max_poll_per_task_per_run() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static RUNAWAYS: AtomicU32 = AtomicU32::new(0);
+
+        fn on_runaway(name: &str) {
+            assert_eq!(name, "runaway");
+            RUNAWAYS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let yielding = |yields: usize| async move {
+            for _ in 0..yields {
+                yield_me().await;
+            }
+
+            yields
+        };
+        let runaway = pin!(Task::new("runaway", async {
+            loop {
+                yield_me().await;
+            }
+        }));
+        let mut runaway_handle = Handle::new();
+        let task1 = pin!(Task::new("task1", yielding(2)));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", yielding(4)));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_max_poll_per_task_per_run(5, on_runaway);
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(runaway, &mut runaway_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the runaway is cancelled on the pass after its 5th poll
+        assert_eq!(executor.pass_count(), 6);
+        assert_eq!(RUNAWAYS.load(Ordering::Relaxed), 1);
+        assert!(runaway_handle.value.is_none());
+        assert_eq!(handle1.value, Some(2));
+        assert_eq!(handle2.value, Some(4));
+    }
+
+    #[test]
+    fn test_spawn_erased_tasks() {
+        use super::task::ErasedTask;
+
+        let number = pin!(Task::new("number", async {
+            yield_me().await;
+            42u32
+        }));
+        let mut number_handle = Handle::new();
+        let text = pin!(Task::new("text", async { "hello" }));
+        let mut text_handle = Handle::new();
+        let extra = pin!(Task::new("extra", async {}));
+        let mut extra_handle = Handle::new();
+        let tasks: [ErasedTask; 2] = [
+            number.erase(&mut number_handle),
+            text.erase(&mut text_handle),
+        ];
+        let mut executor = Executor::<2>::new();
+
+        for task in tasks {
+            let result = executor.spawn_erased(task);
+            assert!(result.is_ok());
+        }
+
+        let result = executor.spawn_erased(extra.erase(&mut extra_handle));
+        assert_eq!(
+            result,
+            Err(Error::NoFreeSlots {
+                name: Some("extra")
+            })
+        );
+        executor.run();
+
+        assert_eq!(number_handle.value, Some(42));
+        assert_eq!(text_handle.value, Some("hello"));
+    }
+
+    #[test]
+    fn test_around_poll() {
+        use super::executor::TaskId;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        const BEFORE: usize = 0;
+        const PENDING: usize = 1;
+        const READY: usize = 2;
+
+        static TRACE: [AtomicUsize; 16] = [const { AtomicUsize::new(usize::MAX) }; 16];
+        static LEN: AtomicUsize = AtomicUsize::new(0);
+
+        fn push(id: TaskId, what: usize) {
+            TRACE[LEN.fetch_add(1, Ordering::Relaxed)]
+                .store(id.index() * 4 + what, Ordering::Relaxed);
+        }
+
+        let task1 = pin!(Task::new("task1", async {
+            yield_me().await;
+        }));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", async {}));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_around_poll(
+            |id| push(id, BEFORE),
+            |id, result| push(id, if result.is_ready() { READY } else { PENDING }),
+        );
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        executor.run();
+
+        let trace = TRACE[..LEN.load(Ordering::Relaxed)]
+            .iter()
+            .map(|entry| entry.load(Ordering::Relaxed));
+        // every poll is bracketed by the hooks of the same task
+        assert!(trace.eq([BEFORE, PENDING, 4 + BEFORE, 4 + READY, BEFORE, READY]));
+    }
+
+    #[test]
+    fn test_semaphore() {
+        use super::sync::Semaphore;
+
+        const WORKERS: usize = 3;
+        let semaphore = Semaphore::new(2);
+        let holders = Cell::new(0usize);
+        let max_holders = Cell::new(0usize);
+        let worker = || async {
+            let _permit = semaphore.acquire().await;
+            holders.set(holders.get() + 1);
+            max_holders.set(max_holders.get().max(holders.get()));
+
+            for _ in 0..2 {
+                yield_me().await;
+            }
+
+            holders.set(holders.get() - 1);
+        };
+        let tasks = pin!(core::array::from_fn::<_, WORKERS, _>(|_| {
+            Task::new_nameless(worker())
+        }));
+        let mut handles = [const { Handle::new() }; WORKERS];
+        let mut executor = Executor::<WORKERS>::new();
+
+        for (task, handle) in zip(pin_each(tasks), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        executor.run();
+
+        assert_eq!(max_holders.get(), 2);
+        assert_eq!(semaphore.available_permits(), 2);
+        assert!(handles.iter().all(|handle| handle.value.is_some()));
+    }
+
+    #[test]
+    fn test_empty_run_allowed_by_default() {
+        let mut executor = Executor::<1>::new();
+        executor.run();
+
+        assert_eq!(executor.pass_count(), 1);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "the executor is run without any spawned task")]
+    fn test_panic_on_empty_run() {
+        let mut executor = Executor::<1>::new();
+        executor.set_panic_on_empty_run(true);
+        executor.run();
+    }
+
+    #[test]
+    fn test_race_ok() {
+        use super::helpers::race_ok;
+
+        let polls = [const { Cell::new(0u32) }; 3];
+        let attempt = |index: usize, yields: u32, ok: bool| {
+            let polls = &polls[index];
+
+            async move {
+                for _ in 0..yields {
+                    polls.set(polls.get() + 1);
+                    yield_me().await;
+                }
+
+                polls.set(polls.get() + 1);
+                if ok { Ok(index) } else { Err(index) }
+            }
+        };
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.block_on(race_ok([
+            attempt(0, 0, false),
+            attempt(1, 3, true),
+            attempt(2, 1, false),
+        ]));
+        assert_eq!(result, Ok((1, 1)));
+        // the failed futures are not polled again
+        assert_eq!(polls.each_ref().map(Cell::get), [1, 4, 2]);
+
+        let result = executor.block_on(race_ok([attempt(0, 2, false), attempt(2, 0, false)]));
+        assert_eq!(result, Err(0));
+    }
+
+    #[test]
+    fn test_scheduler_snapshot() {
+        let short = pin!(Task::new("short", async {}));
+        let mut short_handle = Handle::new();
+        let long_polls = Cell::new(0u32);
+        let long = pin!(Task::new("long", async {
+            for _ in 0..3 {
+                long_polls.set(long_polls.get() + 1);
+                yield_me().await;
+            }
+        }));
+        let mut long_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let result = executor.spawn(short, &mut short_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(long, &mut long_handle);
+        assert!(result.is_ok());
+
+        let before = executor.snapshot();
+        assert!(executor.run_until_predicate(&long_polls, |polls| polls.get() == 1));
+        let after = executor.snapshot();
+
+        assert_eq!((before.pass_count, after.pass_count), (0, 1));
+        // the slot of the completed short task is free again
+        assert_eq!((before.next_slot, after.next_slot), (2, 0));
+        assert_eq!(
+            before
+                .slots
+                .map(|slot| (slot.occupied, slot.name, slot.polls)),
+            [
+                (true, Some("short"), 0),
+                (true, Some("long"), 0),
+                (false, None, 0)
+            ]
+        );
+        // the completed task frees its slot, the pending one stays
+        assert_eq!(
+            after
+                .slots
+                .map(|slot| (slot.occupied, slot.name, slot.polls)),
+            [
+                (false, Some("short"), 1),
+                (true, Some("long"), 1),
+                (false, None, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_handle_observers() {
+        use super::task::SharedHandle;
+
+        let handle = SharedHandle::new();
+        let observers = [&handle, &handle];
+        let task = pin!(Task::new("shared", async {
+            yield_me().await;
+            7u32
+        }));
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn_erased(task.erase_shared(&handle));
+        assert!(result.is_ok());
+        assert!(observers.iter().all(|observer| observer.get().is_none()));
+        executor.run();
+
+        assert!(observers.iter().all(|observer| observer.get() == Some(7)));
+        assert_eq!(observers[0].take(), Some(7));
+        assert_eq!(observers[1].get(), None);
+    }
+
+    #[test]
+    fn test_completion_callback_order() {
+        use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+        static ORDER: [AtomicU8; 4] = [const { AtomicU8::new(0) }; 4];
+        static LEN: AtomicUsize = AtomicUsize::new(0);
+
+        fn on_complete(name: &str) {
+            ORDER[LEN.fetch_add(1, Ordering::Relaxed)].store(name.as_bytes()[0], Ordering::Relaxed);
+        }
+
+        let yielding = |yields: usize| async move {
+            for _ in 0..yields {
+                yield_me().await;
+            }
+        };
+        let tasks = pin!([
+            Task::new("a", yielding(2)),
+            Task::new("b", yielding(0)),
+            Task::new("c", yielding(2)),
+            Task::new("d", yielding(1)),
+        ]);
+        let mut handles = [const { Handle::new() }; 4];
+        let mut executor = Executor::<4>::new();
+        executor.set_completion_callback(on_complete);
+
+        for (task, handle) in zip(pin_each(tasks), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        executor.run();
+
+        // pass order first, slot order within a pass
+        assert_eq!(LEN.load(Ordering::Relaxed), 4);
+        assert!(
+            ORDER
+                .iter()
+                .map(|name| name.load(Ordering::Relaxed))
+                .eq(*b"bdac")
+        );
+    }
+
+    #[test]
+    fn test_lazy() {
+        use super::helpers::lazy;
+
+        let created = Cell::new(false);
+        let future = lazy(|_| {
+            created.set(true);
+            async {
+                yield_me().await;
+                5
+            }
+        });
+        assert!(!created.get());
+
+        let mut executor = Executor::<1>::new();
+        let output = executor.block_on(async {
+            let mut future = core::pin::pin!(future);
+            assert!(!created.get());
+            assert_eq!(poll_immediate(future.as_mut()).await, None);
+            assert!(created.get());
+            future.await
+        });
+
+        assert_eq!(output, 5);
+    }
+
+    #[test]
+    fn test_watchdog_kick() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static KICKS: AtomicU32 = AtomicU32::new(0);
+
+        let task = pin!(Task::new("task", async {
+            for _ in 0..4 {
+                yield_me().await;
+            }
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+        executor.set_watchdog_kick(|| {
+            KICKS.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // one kick per pass, the task completes on its 5th poll
+        assert_eq!(executor.pass_count(), 5);
+        assert_eq!(KICKS.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_task_map_err() {
+        #[derive(Debug, PartialEq)]
+        enum AppError {
+            Sensor,
+        }
+
+        let failing = pin!(
+            Task::new("failing", async {
+                yield_me().await;
+                Err::<u32, ()>(())
+            })
+            .map_err(|()| AppError::Sensor)
+        );
+        let mut failing_handle = Handle::new();
+        let passing =
+            pin!(Task::new("passing", async { Ok::<u32, ()>(3) }).map_err(|()| AppError::Sensor));
+        let mut passing_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(failing, &mut failing_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(passing, &mut passing_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(failing_handle.value, Some(Err(AppError::Sensor)));
+        assert_eq!(passing_handle.value, Some(Ok(3)));
+    }
+
+    #[test]
+    fn test_spawn_atomic() {
+        let first = pin!(Task::new("first", async { 1 }));
+        let mut first_handle = Handle::new();
+        let second = pin!(Task::new("second", async { 2 }));
+        let mut second_handle = Handle::new();
+        let third = pin!(Task::new("third", async { 3 }));
+        let mut third_handle = Handle::new();
+        let fourth = pin!(Task::new("fourth", async {}));
+        let mut fourth_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let result = executor.spawn(first, &mut first_handle);
+        assert!(result.is_ok());
+
+        // two slots are left for a group of three
+        let before = executor.snapshot();
+        let result = executor.spawn_atomic([
+            second.erase(&mut second_handle),
+            third.erase(&mut third_handle),
+            fourth.erase(&mut fourth_handle),
+        ]);
+        assert_eq!(
+            result,
+            Err(Error::NoFreeSlots {
+                name: Some("fourth")
+            })
+        );
+        assert_eq!(executor.snapshot(), before);
+        executor.run();
+
+        assert_eq!(first_handle.value, Some(1));
+        assert_eq!(second_handle.value, None);
+        assert_eq!(third_handle.value, None);
+    }
+
+    #[test]
+    fn test_spawn_atomic_fits() {
+        use super::executor::TaskId;
+
+        let first = pin!(Task::new("first", async { 1 }));
+        let mut first_handle = Handle::new();
+        let second = pin!(Task::new("second", async { "2" }));
+        let mut second_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn_atomic([
+            first.erase(&mut first_handle),
+            second.erase(&mut second_handle),
+        ]);
+        assert_eq!(result.map(|ids| ids.map(TaskId::index)), Ok([0, 1]));
+        executor.run();
+
+        assert_eq!(first_handle.value, Some(1));
+        assert_eq!(second_handle.value, Some("2"));
+    }
+
+    #[test]
+    fn test_timeout_never_ready() {
+        use super::helpers::never;
+        use super::time::{TimedOut, timeout};
+
+        let clock = MockClock::default();
+        let waiting = pin!(Task::new("waiting", timeout(&clock, 2, never::<u32>())));
+        let mut waiting_handle = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            for _ in 0..4 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(waiting, &mut waiting_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(ticker, &mut ticker_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(waiting_handle.value, Some(Err(TimedOut)));
+    }
+
+    #[test]
+    fn test_cancel_group() {
+        const SENSORS: usize = 0;
+        const NETWORK: usize = 1;
+
+        let progress = Cell::new(0u32);
+        let worker = |output: u32| async move {
+            for _ in 0..3 {
+                yield_me().await;
+            }
+
+            output
+        };
+        let sensor1 = pin!(Task::new("sensor1", worker(1)));
+        let mut sensor1_handle = Handle::new();
+        let sensor2 = pin!(Task::new("sensor2", worker(2)));
+        let mut sensor2_handle = Handle::new();
+        let network = pin!(Task::new("network", worker(3)));
+        let mut network_handle = Handle::new();
+        let ungrouped = pin!(Task::new("ungrouped", async {
+            progress.set(progress.get() + 1);
+            yield_me().await;
+            progress.set(progress.get() + 1);
+            4
+        }));
+        let mut ungrouped_handle = Handle::new();
+        let mut executor = Executor::<4>::new();
+
+        let result = executor.spawn_in_group(SENSORS, sensor1, &mut sensor1_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn_in_group(NETWORK, network, &mut network_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn_in_group(SENSORS, sensor2, &mut sensor2_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(ungrouped, &mut ungrouped_handle);
+        assert!(result.is_ok());
+
+        assert!(executor.run_until_predicate(&progress, |progress| progress.get() == 1));
+        assert_eq!(executor.cancel_group(SENSORS), 2);
+        assert_eq!(executor.cancel_group(SENSORS), 0);
+        executor.run();
+
+        assert_eq!(sensor1_handle.value, None);
+        assert_eq!(sensor2_handle.value, None);
+        assert_eq!(network_handle.value, Some(3));
+        assert_eq!(ungrouped_handle.value, Some(4));
+    }
+
+    #[test]
+    fn test_quantum() {
+        use super::executor::TaskId;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static ORDER: [AtomicUsize; 16] = [const { AtomicUsize::new(usize::MAX) }; 16];
+        static LEN: AtomicUsize = AtomicUsize::new(0);
+
+        fn before(id: TaskId) {
+            ORDER[LEN.fetch_add(1, Ordering::Relaxed)].store(id.index(), Ordering::Relaxed);
+        }
+
+        let yielding = || async {
+            for _ in 0..5 {
+                yield_me().await;
+            }
+        };
+        let task1 = pin!(Task::new("task1", yielding()));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", yielding()));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_quantum(3);
+        executor.set_around_poll(before, |_, _| {});
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        executor.run();
+
+        let order = ORDER[..LEN.load(Ordering::Relaxed)]
+            .iter()
+            .map(|id| id.load(Ordering::Relaxed));
+        // each task needs 6 polls, so it completes at the end of its second quantum
+        assert!(order.eq([0, 0, 0, 1, 1, 1, 0, 0, 0, 1, 1, 1]));
+        assert_eq!(executor.pass_count(), 2);
+    }
+
+    #[test]
+    fn test_handle_on_set() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static STORED: AtomicU32 = AtomicU32::new(0);
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+
+        let task = pin!(Task::new("task", async {
+            yield_me().await;
+            5u32
+        }));
+        let mut handle = Handle::new();
+        handle.on_set(|value| {
+            STORED.store(*value, Ordering::Relaxed);
+            CALLS.fetch_add(1, Ordering::Relaxed);
+        });
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(STORED.load(Ordering::Relaxed), 5);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+        assert_eq!(handle.value, Some(5));
+    }
+
+    #[test]
+    fn test_shuffle_seeded_order() {
+        use super::executor::TaskId;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static ORDER: [AtomicUsize; 8] = [const { AtomicUsize::new(usize::MAX) }; 8];
+        static LEN: AtomicUsize = AtomicUsize::new(0);
+
+        fn before(id: TaskId) {
+            ORDER[LEN.fetch_add(1, Ordering::Relaxed)].store(id.index(), Ordering::Relaxed);
+        }
+
+        let tasks = pin!(core::array::from_fn::<_, 4, _>(|_| Task::new_nameless(
+            yield_me()
+        )));
+        let mut handles = [const { Handle::new() }; 4];
+        let mut executor = Executor::<4>::new();
+        executor.set_shuffle(42);
+        executor.set_around_poll(before, |_, _| {});
+
+        for (task, handle) in zip(pin_each(tasks), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        executor.run();
+
+        let order = ORDER[..LEN.load(Ordering::Relaxed)]
+            .iter()
+            .map(|id| id.load(Ordering::Relaxed));
+        // both passes are shuffled, each polling every task exactly once
+        assert!(order.eq([1, 3, 2, 0, 0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_shuffle_completes_all_tasks() {
+        const TASKS: usize = 5;
+
+        for seed in 0..8 {
+            let tasks = pin!(core::array::from_fn::<_, TASKS, _>(|i| {
+                Task::new_nameless(async move {
+                    for _ in 0..i {
+                        yield_me().await;
+                    }
+
+                    i
+                })
+            }));
+            let mut handles = [const { Handle::new() }; TASKS];
+            let mut executor = Executor::<TASKS>::new();
+            executor.set_shuffle(seed);
+
+            for (task, handle) in zip(pin_each(tasks), &mut handles) {
+                let result = executor.spawn(task, handle);
+                assert!(result.is_ok());
+            }
+
+            executor.run();
+
+            assert!(
+                (0..TASKS)
+                    .map(Some)
+                    .eq(handles.iter().map(|handle| handle.value))
+            );
+        }
+    }
+
+    #[test]
+    fn test_task_and_then() {
+        let steps = Cell::new(0u32);
+        let step = |value: u32, ok: bool| {
+            let steps = &steps;
+
+            async move {
+                steps.set(steps.get() + 1);
+                yield_me().await;
+                if ok { Ok(value) } else { Err(value) }
+            }
+        };
+        let succeeding = pin!(
+            Task::new("succeeding", step(1, true))
+                .and_then(|v| step(v + 1, true))
+                .and_then(|v| step(v + 1, true))
+        );
+        let mut succeeding_handle = Handle::new();
+        let failing = pin!(
+            Task::new("failing", step(10, true))
+                .and_then(|v| step(v + 1, false))
+                .and_then(|v| step(v + 1, true))
+        );
+        let mut failing_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(succeeding, &mut succeeding_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(failing, &mut failing_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(succeeding_handle.value, Some(Ok(3)));
+        assert_eq!(failing_handle.value, Some(Err(11)));
+        // the step after the failing one is never run
+        assert_eq!(steps.get(), 5);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        use super::helpers::checkpoint;
+
+        let checkpoints = || async {
+            for _ in 0..3 {
+                checkpoint().await;
+            }
+        };
+        let alone = pin!(Task::new("alone", checkpoints()));
+        let mut alone_handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(alone, &mut alone_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the only task never yields
+        assert_eq!(executor.pass_count(), 1);
+
+        let contended = pin!(Task::new("contended", checkpoints()));
+        let mut contended_handle = Handle::new();
+        let sibling = pin!(Task::new("sibling", async {
+            for _ in 0..5 {
+                yield_me().await;
+            }
+        }));
+        let mut sibling_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(contended, &mut contended_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(sibling, &mut sibling_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // each checkpoint yields while the sibling is pending
+        assert_eq!(executor.pass_count(), 6);
+        assert!(contended_handle.value.is_some());
+    }
+
+    #[test]
+    fn test_completion_queue() {
+        use super::completion_queue::{Completion, CompletionQueue};
+        use super::event_log::OverflowPolicy;
+
+        let yields = |count| async move {
+            for _ in 0..count {
+                yield_me().await;
+            }
+        };
+        let mut buffer = [None; 3];
+        let queue = CompletionQueue::new(&mut buffer, OverflowPolicy::Stop);
+        let task1 = pin!(Task::new("task1", yields(3)));
+        let task2 = pin!(Task::new("task2", yields(1)));
+        let task3 = pin!(Task::new("task3", yields(2)));
+        let task4 = pin!(Task::new("task4", yields(4)));
+        let mut handle1 = Handle::new();
+        let mut handle2 = Handle::new();
+        let mut handle3 = Handle::new();
+        let mut handle4 = Handle::new();
+        let mut executor = Executor::<4>::new();
+        executor.set_completion_queue(&queue);
+
+        let id1 = executor.spawn(task1, &mut handle1).unwrap();
+        let id2 = executor.spawn(task2, &mut handle2).unwrap();
+        let id3 = executor.spawn(task3, &mut handle3).unwrap();
+        let result = executor.spawn(task4, &mut handle4);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the last completion is dropped as the queue is full
+        assert_eq!(queue.len(), 3);
+        let completion = |id, name| {
+            Some(Completion {
+                id,
+                name: Some(name),
+            })
+        };
+        assert_eq!(queue.pop(), completion(id2, "task2"));
+        assert_eq!(queue.pop(), completion(id3, "task3"));
+        assert_eq!(queue.pop(), completion(id1, "task1"));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_stats_callback() {
+        use super::snapshot::Stats;
+        use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        static PASSES: [AtomicU64; 3] = [const { AtomicU64::new(0) }; 3];
+        static POLLS: [AtomicU64; 3] = [const { AtomicU64::new(0) }; 3];
+        static COMPLETIONS: [AtomicU64; 3] = [const { AtomicU64::new(0) }; 3];
+
+        fn on_stats(stats: &Stats) {
+            let call = CALLS.fetch_add(1, Ordering::Relaxed);
+            PASSES[call].store(stats.passes, Ordering::Relaxed);
+            POLLS[call].store(stats.polls, Ordering::Relaxed);
+            COMPLETIONS[call].store(stats.completions, Ordering::Relaxed);
+        }
+
+        let yields = |count| async move {
+            for _ in 0..count {
+                yield_me().await;
+            }
+        };
+        let task1 = pin!(Task::new("task1", yields(1)));
+        let mut handle1 = Handle::new();
+        let task2 = pin!(Task::new("task2", yields(5)));
+        let mut handle2 = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_stats_callback(2, on_stats);
+
+        let result = executor.spawn(task1, &mut handle1);
+        assert!(result.is_ok());
+        let result = executor.spawn(task2, &mut handle2);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the run takes 6 passes, so the callback fires after passes 2, 4 and 6
+        assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+        let load =
+            |counters: &[AtomicU64; 3]| counters.each_ref().map(|c| c.load(Ordering::Relaxed));
+        assert_eq!(load(&PASSES), [2, 4, 6]);
+        assert_eq!(load(&POLLS), [4, 6, 8]);
+        assert_eq!(load(&COMPLETIONS), [1, 1, 2]);
+        assert_eq!(
+            executor.stats(),
+            Stats {
+                passes: 6,
+                polls: 8,
+                completions: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_fuse() {
+        use core::future::{Future, ready};
+        use core::pin::Pin;
+        use core::task::{Context, Poll, Waker};
+
+        // `Ready` panics if it is polled after completion
+        let mut task = Task::new("fused", ready(7)).fuse();
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(!task.future().is_terminated());
+
+        let mut future = Pin::new(task.future_mut());
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(7));
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        assert!(task.future().is_terminated());
+    }
+
+    #[test]
+    fn test_reactor() {
+        use super::executor::TaskId;
+        use super::reactor::{Interest, Reactor, wait_ready};
+
+        struct MockReactor {
+            ready_after: u32,
+            consulted: Cell<u32>,
+            registered: Cell<Option<(TaskId, Interest)>>,
+        }
+
+        impl Reactor for MockReactor {
+            fn register(&self, id: TaskId, interest: Interest) {
+                self.registered.set(Some((id, interest)));
+            }
+
+            fn poll_ready(&self, interest: Interest) -> bool {
+                assert_eq!(interest, Interest(7));
+                self.consulted.set(self.consulted.get() + 1);
+                self.consulted.get() >= self.ready_after
+            }
+        }
+
+        let reactor = MockReactor {
+            ready_after: 3,
+            consulted: Cell::new(0),
+            registered: Cell::new(None),
+        };
+        let task = pin!(Task::new("io", async {
+            wait_ready(Interest(7)).await;
+            42
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+        executor.set_reactor(&reactor);
+
+        let id = executor.spawn(task, &mut handle).unwrap();
+        executor.run();
+
+        assert_eq!(reactor.registered.get(), Some((id, Interest(7))));
+        // the task is parked for the passes the reactor is not ready in
+        assert_eq!(executor.pass_count(), 4);
+        assert_eq!(executor.stats().polls, 2);
+        assert_eq!(handle.value, Some(42));
+    }
+
+    #[test]
+    fn test_shared_handle_into_future() {
+        use super::task::SharedHandle;
+        use core::future::IntoFuture;
+
+        let shared = SharedHandle::new();
+        let output = shared.into_future();
+        let producer = pin!(Task::new("producer", async {
+            yield_me().await;
+            21
+        }));
+        let consumer = pin!(Task::new("consumer", async move { output.await * 2 }));
+        let mut consumer_handle = Handle::new();
+        let other = pin!(Task::new("other", async { (&shared).await + 1 }));
+        let mut other_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let result = executor.spawn(consumer, &mut consumer_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(other, &mut other_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn_erased(producer.erase_shared(&shared));
+        assert!(result.is_ok());
+        executor.run();
+
+        // each awaiter receives a clone, and the output stays in the handle
+        assert_eq!(consumer_handle.value, Some(42));
+        assert_eq!(other_handle.value, Some(22));
+        assert_eq!(shared.get(), Some(21));
+    }
+
+    #[test]
+    fn test_spawn_guarded() {
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        static DROPPED_RAN: AtomicBool = AtomicBool::new(false);
+
+        let dropped_flag = Cell::new(false);
+        let kept_flag = Cell::new(false);
+        let dropped = pin!(Task::new("dropped", async {
+            DROPPED_RAN.store(true, Ordering::Relaxed);
+        }));
+        let mut dropped_handle = Handle::new();
+        let kept = pin!(Task::new("kept", async {
+            yield_me().await;
+            1
+        }));
+        let mut kept_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        {
+            let guard = executor
+                .spawn_guarded(dropped, &mut dropped_handle, &dropped_flag)
+                .unwrap();
+            assert_eq!(guard.id().index(), 0);
+        }
+        let guard = executor
+            .spawn_guarded(kept, &mut kept_handle, &kept_flag)
+            .unwrap();
+        executor.run();
+        drop(guard);
+
+        assert!(!DROPPED_RAN.load(Ordering::Relaxed));
+        assert_eq!(executor.snapshot().slots[0].polls, 0);
+        assert_eq!(dropped_handle.value, None);
+        assert_eq!(kept_handle.value, Some(1));
+    }
+
+    #[test]
+    fn test_throttle() {
+        use super::helpers::throttle;
+        use super::stream::{Stream, next};
+
+        /// Produces the current tick once per tick, being pending in between.
+        struct Ticker<'a> {
+            clock: &'a MockClock,
+            limit: u64,
+            ready: bool,
+        }
+
+        impl Stream for Ticker<'_> {
+            type Item = u64;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u64>> {
+                if !self.ready {
+                    self.ready = true;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+
+                self.ready = false;
+
+                if self.clock.now() == self.limit {
+                    return Poll::Ready(None);
+                }
+
+                self.clock.advance(1);
+                Poll::Ready(Some(self.clock.now()))
+            }
+        }
+
+        let clock = MockClock::default();
+        let ticker = Ticker {
+            clock: &clock,
+            limit: 11,
+            ready: false,
+        };
+        let mut executor = Executor::<1>::new();
+        let (emitted, count) = executor.block_on(async {
+            let mut throttled = throttle(ticker, &clock, 3);
+            let mut emitted = [0; 11];
+            let mut count = 0;
+
+            while let Some(tick) = next(&mut throttled).await {
+                emitted[count] = tick;
+                count += 1;
+            }
+
+            (emitted, count)
+        });
+
+        assert_eq!(emitted[..count], [1, 4, 7, 10]);
+    }
+
+    #[test]
+    fn test_max_latency() {
+        let clock = MockClock::default();
+        let waiting = pin!(Task::new("waiting", async {
+            yield_me().await;
+        }));
+        let mut waiting_handle = Handle::new();
+        let busy = pin!(Task::new("busy", async {
+            // keeps the executor busy for 5 ticks, then for 2 ticks
+            clock.advance(5);
+            yield_me().await;
+            clock.advance(2);
+        }));
+        let mut busy_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_time_source(&clock);
+
+        let waiting_id = executor.spawn(waiting, &mut waiting_handle).unwrap();
+        let busy_id = executor.spawn(busy, &mut busy_handle).unwrap();
+        assert_eq!(executor.max_latency(waiting_id), None);
+        executor.run();
+
+        // `waiting` yields on tick 0 and is polled again after `busy` has advanced to tick 5
+        assert_eq!(executor.max_latency(waiting_id), Some(5));
+        // `busy` waits for `waiting` only, which takes no ticks
+        assert_eq!(executor.max_latency(busy_id), Some(0));
+    }
+
+    #[test]
+    fn test_scope() {
+        use super::helpers::{pin_mut, scope};
+
+        let step = Cell::new(0);
+        let next_step = || {
+            step.set(step.get() + 1);
+            step.get()
+        };
+        let parent = pin!(Task::new("parent", async {
+            let first_done = Cell::new(0);
+            let second_done = Cell::new(0);
+            let first = async {
+                for _ in 0..3 {
+                    yield_me().await;
+                }
+                first_done.set(next_step());
+            };
+            let second = async {
+                yield_me().await;
+                second_done.set(next_step());
+            };
+            pin_mut!(first, second);
+
+            scope([first, second]).await;
+
+            // both children have finished before the parent gets past the scope
+            (first_done.get(), second_done.get(), next_step())
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(parent, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(handle.value, Some((2, 1, 3)));
+    }
+
+    #[test]
+    fn test_buffer_unordered() {
+        use super::helpers::buffer_unordered;
+
+        /// Completes with its value after being pending a number of times, tracking how many
+        /// instances are in flight.
+        struct Job<'a> {
+            value: u32,
+            delay: u32,
+            started: bool,
+            active: &'a Cell<usize>,
+            peak: &'a Cell<usize>,
+        }
+
+        impl Future for Job<'_> {
+            type Output = u32;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+                if !self.started {
+                    self.started = true;
+                    self.active.set(self.active.get() + 1);
+                    self.peak.set(self.peak.get().max(self.active.get()));
+                }
+
+                if self.delay == 0 {
+                    self.active.set(self.active.get() - 1);
+                    return Poll::Ready(self.value);
+                }
+
+                self.delay -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        let active = Cell::new(0);
+        let peak = Cell::new(0);
+        let delays = [3, 0, 2, 1, 4];
+        let mut jobs = core::array::from_fn::<_, 5, _>(|i| Job {
+            value: u32::try_from(i).unwrap() + 1,
+            delay: delays[i],
+            started: false,
+            active: &active,
+            peak: &peak,
+        });
+        let mut out = [None; 5];
+        let mut executor = Executor::<1>::new();
+        executor.block_on(buffer_unordered(&mut jobs, 2, &mut out));
+
+        assert_eq!(out, [Some(1), Some(2), Some(3), Some(4), Some(5)]);
+        assert_eq!(peak.get(), 2);
+        assert_eq!(active.get(), 0);
+    }
+
+    #[test]
+    fn test_spawn_after() {
+        let step = Cell::new(0);
+        let next_step = || {
+            step.set(step.get() + 1);
+            step.get()
+        };
+        let fetch = pin!(Task::new("fetch", async {
+            yield_me().await;
+            yield_me().await;
+            next_step()
+        }));
+        let mut fetch_handle = Handle::new();
+        let parse = pin!(Task::new("parse", async { next_step() }));
+        let mut parse_handle = Handle::new();
+        let store = pin!(Task::new("store", async { next_step() }));
+        let mut store_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        let fetch_id = executor.spawn(fetch, &mut fetch_handle).unwrap();
+        let parse_id = executor
+            .spawn_after(fetch_id, parse, &mut parse_handle)
+            .unwrap();
+        let result = executor.spawn_after(parse_id, store, &mut store_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // each stage is polled for the first time after the previous one has completed, which
+        // happens within the same pass as the stages occupy subsequent slots
+        let polls = executor.snapshot().slots.map(|slot| slot.polls);
+        assert_eq!(polls, [3, 1, 1]);
+        assert_eq!(executor.pass_count(), 3);
+        assert_eq!(fetch_handle.value, Some(1));
+        assert_eq!(parse_handle.value, Some(2));
+        assert_eq!(store_handle.value, Some(3));
+    }
+
+    #[test]
+    fn test_instrument() {
+        use super::event_log::{Event, EventKind, OverflowPolicy};
+
+        let mut buffer = [None; 8];
+        let read = pin!(Task::new("read", async {}).instrument("io"));
+        let mut read_handle = Handle::new();
+        let sum = pin!(Task::new("sum", async { 1 + 2 }).instrument("compute"));
+        let mut sum_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_event_log(&mut buffer, OverflowPolicy::Stop);
+
+        let read_id = executor.spawn(read, &mut read_handle).unwrap();
+        let sum_id = executor.spawn(sum, &mut sum_handle).unwrap();
+        executor.run();
+
+        let labelled = |id| {
+            executor
+                .events()
+                .filter(move |event: &&Event| event.id == id)
+                .map(|event| (event.kind, event.label))
+        };
+        let expected = |label| {
+            [EventKind::Spawn, EventKind::Poll, EventKind::Complete].map(|kind| (kind, Some(label)))
+        };
+        assert!(labelled(read_id).eq(expected("io")));
+        assert!(labelled(sum_id).eq(expected("compute")));
+        assert_eq!(executor.label(read_id), Some("io"));
+        assert_eq!(executor.label(sum_id), Some("compute"));
+    }
+
+    #[test]
+    fn test_yield_until_tick() {
+        use super::time::yield_until_tick;
+
+        let clock = MockClock::default();
+        let resumed_at = Cell::new(None);
+        let sleeper = pin!(Task::new("sleeper", async {
+            yield_until_tick(3).await;
+            resumed_at.set(Some(clock.now()));
+        }));
+        let mut sleeper_handle = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            for _ in 0..5 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_time_source(&clock);
+
+        let sleeper_id = executor.spawn(sleeper, &mut sleeper_handle).unwrap();
+        let result = executor.spawn(ticker, &mut ticker_handle);
+        assert!(result.is_ok());
+        executor.run_until_predicate(&clock, |clock| clock.now() >= 2);
+
+        // the sleeper is skipped while the clock is behind its deadline
+        assert_eq!(executor.deadline(sleeper_id), Some(3));
+        assert_eq!(executor.snapshot().slots[0].polls, 1);
+
+        executor.run();
+
+        // and polled on the first pass after the clock has reached it
+        assert_eq!(resumed_at.get(), Some(3));
+        assert_eq!(executor.snapshot().slots[0].polls, 2);
+        assert_eq!(executor.deadline(sleeper_id), None);
+    }
+
+    #[test]
+    fn test_ordered() {
+        use super::helpers::ordered;
+        use super::stream::next;
+
+        /// Completes with its value after being pending a number of times, recording when.
+        struct Countdown<'a> {
+            value: u32,
+            delay: u32,
+            completed: &'a RefCell<([u32; 4], usize)>,
+        }
+
+        impl Future for Countdown<'_> {
+            type Output = u32;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+                if self.delay == 0 {
+                    let mut completed = self.completed.borrow_mut();
+                    let (order, count) = &mut *completed;
+                    order[*count] = self.value;
+                    *count += 1;
+                    return Poll::Ready(self.value);
+                }
+
+                self.delay -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        let completed = RefCell::new(([0; 4], 0));
+        let mut futures = [3, 2, 1, 0].map(|delay| Countdown {
+            value: 4 - delay,
+            delay,
+            completed: &completed,
+        });
+        let mut buffer = [None; 4];
+        let mut executor = Executor::<1>::new();
+        let emitted = executor.block_on(async {
+            let mut outputs = ordered(&mut futures, &mut buffer);
+            let mut emitted = [0; 4];
+
+            for slot in &mut emitted {
+                *slot = next(&mut outputs).await.unwrap();
+            }
+            assert_eq!(next(&mut outputs).await, None);
+
+            emitted
+        });
+
+        // the futures complete in reverse order, but their outputs are produced in order
+        assert_eq!(completed.borrow().0, [4, 3, 2, 1]);
+        assert_eq!(emitted, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_abort_flag() {
+        use super::executor::RunStatus;
+        use super::sync::AbortFlag;
+
+        let flag = AbortFlag::new();
+        let killer = pin!(Task::new("killer", async {
+            yield_me().await;
+            flag.abort();
+        }));
+        let mut killer_handle = Handle::new();
+        let worker = pin!(Task::new("worker", async {
+            loop {
+                yield_me().await;
+            }
+        }));
+        let mut worker_handle: Handle<()> = Handle::new();
+        let polite = pin!(Task::new("polite", async {
+            while !flag.is_aborted() {
+                yield_me().await;
+            }
+        }));
+        let mut polite_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_abort_flag(&flag);
+        assert!(executor.abort_flag().is_some_and(|flag| !flag.is_aborted()));
+
+        let result = executor.spawn(killer, &mut killer_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(worker, &mut worker_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(polite, &mut polite_handle);
+        assert!(result.is_ok());
+        let status = executor.run();
+
+        // the flag is set in the second pass, and the third one cancels the worker
+        assert!(flag.is_aborted());
+        assert_eq!(status, RunStatus::Aborted { cancelled: 1 });
+        assert_eq!(executor.pass_count(), 3);
+        assert!(executor.snapshot().slots.iter().all(|slot| !slot.occupied));
+        assert_eq!(killer_handle.value, Some(()));
+        assert_eq!(worker_handle.value, None);
+        assert_eq!(polite_handle.value, Some(()));
+    }
+
+    #[test]
+    fn test_poll_with_waker() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use core::task::{RawWaker, RawWakerVTable, Waker};
+
+        static WAKES: AtomicUsize = AtomicUsize::new(0);
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |data| RawWaker::new(data, &VTABLE),
+            |_| {
+                WAKES.fetch_add(1, Ordering::Relaxed);
+            },
+            |_| {
+                WAKES.fetch_add(1, Ordering::Relaxed);
+            },
+            |_| {},
+        );
+
+        // SAFETY: the vtable functions do not use the data pointer.
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let notify = Notify::new();
+        let mut task = core::pin::pin!(Task::new("waiter", async {
+            notify.notified().await;
+            notify.notified().await;
+        }));
+
+        assert_eq!(task.as_mut().poll_with_waker(&waker), Poll::Pending);
+        assert_eq!(WAKES.load(Ordering::Relaxed), 0);
+
+        notify.notify_one();
+        assert_eq!(WAKES.load(Ordering::Relaxed), 1);
+        assert_eq!(task.as_mut().poll_with_waker(&waker), Poll::Pending);
+
+        notify.notify_one();
+        assert_eq!(WAKES.load(Ordering::Relaxed), 2);
+        assert_eq!(task.as_mut().poll_with_waker(&waker), Poll::Ready(()));
+    }
+
+    #[test]
+    fn test_select_loop() {
+        use super::helpers::{Selected, select_loop};
+        use super::time::delay;
+        use core::ops::ControlFlow;
+
+        let clock = MockClock::default();
+        let counter = Cell::new(0);
+        let task = pin!(Task::new("event_loop", async {
+            let mut events = [None; 8];
+            let mut handled = 0;
+
+            select_loop(
+                // a tick every 3 ticks of the clock
+                || delay(&clock, 3),
+                // a counter advancing the clock by a tick per step
+                || async {
+                    yield_me().await;
+                    clock.advance(1);
+                    counter.set(counter.get() + 1);
+                    counter.get()
+                },
+                |selected| {
+                    events[handled] = Some(match selected {
+                        Selected::First(()) => (clock.now(), None),
+                        Selected::Second(count) => (clock.now(), Some(count)),
+                    });
+                    handled += 1;
+
+                    if handled == events.len() {
+                        ControlFlow::Break(events)
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                },
+            )
+            .await
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the tick source fires every 3 ticks in between the counter steps
+        let expected = [
+            (1, Some(1)),
+            (2, Some(2)),
+            (3, Some(3)),
+            (3, None),
+            (4, Some(4)),
+            (5, Some(5)),
+            (6, Some(6)),
+            (6, None),
+        ]
+        .map(Some);
+        assert_eq!(handle.value, Some(expected));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_with_restart() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+        async fn flaky() -> u32 {
+            let attempt = ATTEMPTS.fetch_add(1, Ordering::Relaxed) + 1;
+            yield_me().await;
+            assert!(attempt > 2, "attempt {attempt} fails");
+            attempt
+        }
+
+        let task =
+            pin!(Task::new("flaky", flaky()).with_restart(|| Task::new("flaky", flaky()), 2));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let id = executor.spawn(task, &mut handle).unwrap();
+        executor.run();
+
+        // a restarted attempt is first polled within the poll its predecessor panicked in
+        assert_eq!(ATTEMPTS.load(Ordering::Relaxed), 3);
+        assert_eq!(executor.snapshot().slots[id.index()].polls, 4);
+        assert_eq!(handle.value, Some(3));
+    }
+
+    #[test]
+    fn test_poll_histogram() {
+        let histogram = |fifo: bool| {
+            let polls = Cell::new(0u32);
+            let worker = || async {
+                for _ in 0..5 {
+                    polls.set(polls.get() + 1);
+                    yield_me().await;
+                }
+            };
+            let tasks = pin!([(); 4].map(|()| Task::new("worker", worker())));
+            let mut handles = [const { Handle::new() }; 4];
+            let mut executor = Executor::<4>::new();
+
+            if fifo {
+                executor.set_concurrency_limit(1);
+            }
+
+            for (task, handle) in zip(pin_each(tasks), &mut handles) {
+                let result = executor.spawn(task, handle);
+                assert!(result.is_ok());
+            }
+
+            // stop the identical workloads midway, after 8 of their 20 yields
+            executor.run_until_predicate(&polls, |polls| polls.get() >= 8);
+
+            let mut out = [0; 4];
+            executor.poll_histogram(&mut out);
+            out
+        };
+
+        assert_eq!(histogram(false), [2, 2, 2, 2]);
+        assert_eq!(histogram(true), [6, 3, 0, 0]);
+    }
+
+    #[test]
+    fn test_select_with_default() {
+        use super::helpers::{poll_immediate, select_with_default};
+
+        let polls = Cell::new(0u32);
+        let receive = |yields: u32, value: u32| {
+            let polls = &polls;
+
+            async move {
+                for _ in 0..yields {
+                    polls.set(polls.get() + 1);
+                    yield_me().await;
+                }
+
+                polls.set(polls.get() + 1);
+                value
+            }
+        };
+        let mut executor = Executor::<1>::new();
+
+        executor.block_on(async {
+            // the default is returned on the first poll, without yielding to the executor
+            let selected = poll_immediate(select_with_default([receive(1, 1), receive(2, 2)]));
+            assert_eq!(selected.await, Some(0));
+            assert_eq!(polls.get(), 2);
+
+            let selected = select_with_default([receive(1, 1), receive(0, 2), receive(0, 3)]);
+            assert_eq!(selected.await, 2);
+            // the futures after the first ready one are not polled
+            assert_eq!(polls.get(), 4);
+        });
+    }
+
+    #[test]
+    fn test_coroutine() {
+        use super::coroutine::Coroutine;
+
+        let coroutine = Coroutine::new();
+        let task = pin!(Task::new("coroutine", async {
+            for value in 1..=3 {
+                coroutine.yield_value(value).await;
+            }
+
+            "done"
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+        let mut values = [None; 3];
+        let mut passes = [0; 3];
+        let mut count = 0;
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+
+        while executor.run_until_predicate(&coroutine, Coroutine::has_value) {
+            values[count] = coroutine.take();
+            passes[count] = executor.pass_count();
+            count += 1;
+        }
+
+        assert_eq!(values, [Some(1), Some(2), Some(3)]);
+        // each value is yielded in a pass of its own once the previous one is taken
+        assert_eq!(passes, [1, 2, 3]);
+        assert!(coroutine.take().is_none());
+        assert_eq!(executor.pass_count(), 4);
+        assert_eq!(handle.value, Some("done"));
+    }
+
+    #[test]
+    fn test_spawn_self_referential() {
+        use core::marker::PhantomPinned;
+        use core::task::Waker;
+
+        // a future pointing into itself, which a move between polls would leave dangling
+        struct SelfReferential {
+            value: u32,
+            pointer: *const u32,
+            _pinned: PhantomPinned,
+        }
+
+        impl Future for SelfReferential {
+            type Output = u32;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let this = unsafe { self.get_unchecked_mut() };
+
+                if this.pointer.is_null() {
+                    this.pointer = &raw const this.value;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+
+                assert_eq!(this.pointer, &raw const this.value, "the future has moved");
+                Poll::Ready(unsafe { *this.pointer })
+            }
+        }
+
+        let mut task = pin!(Task::new(
+            "self-referential",
+            SelfReferential {
+                value: 7,
+                pointer: core::ptr::null(),
+                _pinned: PhantomPinned,
+            }
+        ));
+        assert_eq!(task.as_mut().poll_with_waker(Waker::noop()), Poll::Pending);
+
+        // the task can only be reached through the pin from now on, so it is spawned in place
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(handle.value, Some(7));
+    }
+
+    #[test]
+    fn test_telemetry() {
+        use super::helpers::abort_self;
+        use super::telemetry::{Metric, Telemetry};
+        use super::time::yield_until_tick;
+
+        #[derive(Default)]
+        struct Tally {
+            counts: [Cell<u32>; 7],
+        }
+
+        impl Telemetry for Tally {
+            fn record(&self, metric: Metric) {
+                let index = match metric {
+                    Metric::Spawned { .. } => 0,
+                    Metric::PollStarted { .. } => 1,
+                    Metric::PollEnded { .. } => 2,
+                    Metric::Woken { .. } => 3,
+                    Metric::Completed { .. } => 4,
+                    Metric::Aborted { .. } => 5,
+                    Metric::Cancelled { .. } => 6,
+                };
+                self.counts[index].set(self.counts[index].get() + 1);
+            }
+        }
+
+        let tally = Tally::default();
+        let clock = MockClock::default();
+        let sleeper = pin!(Task::new("sleeper", yield_until_tick(2)));
+        let mut sleeper_handle = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            for _ in 0..3 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let cancelled = pin!(Task::new("cancelled", async {}));
+        let mut cancelled_handle = Handle::new();
+        let cancel = Cell::new(false);
+        let aborting = pin!(Task::new("aborting", abort_self()));
+        let mut aborting_handle = Handle::new();
+        let mut executor = Executor::<4>::new();
+        executor.set_time_source(&clock);
+        executor.set_telemetry(&tally);
+
+        let result = executor.spawn(sleeper, &mut sleeper_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(ticker, &mut ticker_handle);
+        assert!(result.is_ok());
+        // the guard is dropped right away, cancelling the task before its first poll
+        let result = executor.spawn_guarded(cancelled, &mut cancelled_handle, &cancel);
+        assert!(result.is_ok());
+        drop(result);
+        let result = executor.spawn(aborting, &mut aborting_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // spawned, polled, woken, completed, aborted and cancelled
+        assert_eq!(
+            tally.counts.each_ref().map(Cell::get),
+            [4, 7, 7, 1, 2, 1, 1]
+        );
+    }
+
+    #[test]
+    fn test_timeout_at() {
+        use super::helpers::never;
+        use super::time::{TimedOut, timeout_at};
+
+        let clock = MockClock::default();
+        let timed_out_at = [const { Cell::new(None) }; 2];
+        let waiting = pin!(Task::new("waiting", async {
+            for (deadline, timed_out_at) in zip([3, 5], &timed_out_at) {
+                let result = timeout_at(&clock, deadline, never::<()>()).await;
+                assert_eq!(result, Err(TimedOut));
+                timed_out_at.set(Some(clock.now()));
+            }
+        }));
+        let mut waiting_handle = Handle::new();
+        let ticker = pin!(Task::new("ticker", async {
+            for _ in 0..6 {
+                clock.advance(1);
+                yield_me().await;
+            }
+        }));
+        let mut ticker_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(waiting, &mut waiting_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(ticker, &mut ticker_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // each timeout fires exactly at its absolute deadline
+        assert_eq!(timed_out_at.each_ref().map(Cell::get), [Some(3), Some(5)]);
+    }
+
+    #[test]
+    fn test_debugger() {
+        use super::executor::{Control, TaskId};
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static POLLS: AtomicU32 = AtomicU32::new(0);
+
+        fn hook(_: TaskId, name: &str) -> Control {
+            if name == "skipped" {
+                return Control::Skip;
+            }
+
+            if POLLS.fetch_add(1, Ordering::Relaxed) < 2 {
+                Control::Continue
+            } else {
+                Control::Halt
+            }
+        }
+
+        let worker = pin!(Task::new("worker", async {
+            for _ in 0..4 {
+                yield_me().await;
+            }
+        }));
+        let mut worker_handle = Handle::new();
+        let skipped = pin!(Task::new("skipped", async {}));
+        let mut skipped_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_debugger(hook);
+
+        let result = executor.spawn(worker, &mut worker_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(skipped, &mut skipped_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the run halts on the third pass, before the third poll of the worker
+        let slots = executor.snapshot().slots;
+        assert_eq!(executor.pass_count(), 3);
+        assert_eq!(slots.map(|slot| slot.polls), [2, 0]);
+        // with both tasks still pending
+        assert_eq!(slots.map(|slot| slot.occupied), [true, true]);
+    }
+
+    #[test]
+    fn test_task_pool() {
+        use super::pool::TaskPool;
+
+        async fn count(counter: &Cell<u32>) {
+            yield_me().await;
+            counter.set(counter.get() + 1);
+        }
+
+        let counter = Cell::new(0);
+        let pool = pin!(TaskPool::<_, 2>::new("counter"));
+        let pool = pool.into_ref();
+        let mut executor = Executor::<6>::new();
+
+        for round in 1..=3 {
+            assert_eq!(pool.available(), 2);
+            assert!(pool.spawn(&mut executor, count(&counter)).is_ok());
+            assert!(pool.spawn(&mut executor, count(&counter)).is_ok());
+            assert_eq!(pool.available(), 0);
+            assert_eq!(
+                pool.spawn(&mut executor, count(&counter)),
+                Err(Error::NoFreeSlots {
+                    name: Some("counter")
+                })
+            );
+
+            executor.run();
+
+            // the completed tasks have returned their slots to the pool
+            assert_eq!(counter.get(), round * 2);
+        }
+
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn test_try_join_all() {
+        use super::helpers::try_join_all;
+
+        /// Resolves to its result after being pending a number of times, counting its polls.
+        struct Attempt<'a> {
+            result: Result<u32, &'static str>,
+            delay: u32,
+            polls: &'a Cell<u32>,
+        }
+
+        impl Future for Attempt<'_> {
+            type Output = Result<u32, &'static str>;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                self.polls.set(self.polls.get() + 1);
+
+                if self.delay == 0 {
+                    return Poll::Ready(self.result);
+                }
+
+                self.delay -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        let polls = [const { Cell::new(0) }; 3];
+        let results = [Ok(1), Err("failed"), Ok(3)];
+        let delays = [3, 1, 0];
+        let mut futures = core::array::from_fn::<_, 3, _>(|i| Attempt {
+            result: results[i],
+            delay: delays[i],
+            polls: &polls[i],
+        });
+        let mut out = [None; 3];
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.block_on(try_join_all(&mut futures, &mut out));
+
+        assert_eq!(result, Err("failed"));
+        // the join returns on the second poll, when the second future fails
+        assert_eq!(polls.each_ref().map(Cell::get), [2, 2, 1]);
+        // the first future is left unfinished and never polled again
+        assert_eq!(out, [None, None, Some(3)]);
+    }
+
+    #[test]
+    fn test_task_with_output_into() {
+        let mut sum: Option<u32> = None;
+        let mut greeting = Some("stale");
+        let adder = pin!(Task::new("adder", async {
+            yield_me().await;
+            2 + 3
+        }));
+        let greeter = pin!(Task::new("greeter", async { "hello" }));
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn_erased(adder.with_output_into(&mut sum));
+        assert!(result.is_ok());
+        let result = executor.spawn_erased(greeter.with_output_into(&mut greeting));
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(sum, Some(5));
+        assert_eq!(greeting, Some("hello"));
+    }
+
+    #[test]
+    fn test_nested_executor() {
+        let steps = Cell::new(0);
+        let tasks = pin!(core::array::from_fn::<_, 2, _>(|i| {
+            Task::new_nameless(async move {
+                for _ in 0..=i {
+                    yield_me().await;
+                }
+
+                i * 10
+            })
+        }));
+        let [first, second] = pin_each(tasks);
+        let mut handles = [const { Handle::new() }; 2];
+        let [first_handle, second_handle] = handles.each_mut();
+        let mut child = Executor::<2>::new();
+        assert!(child.spawn(first, first_handle).is_ok());
+        assert!(child.spawn(second, second_handle).is_ok());
+
+        let group = pin!(Task::new("group", child.nested()));
+        let mut group_handle = Handle::new();
+        let sibling = pin!(Task::new("sibling", async {
+            for _ in 0..5 {
+                steps.set(steps.get() + 1);
+                yield_me().await;
+            }
+        }));
+        let mut sibling_handle = Handle::new();
+        let mut parent = Executor::<2>::new();
+        assert!(parent.spawn(group, &mut group_handle).is_ok());
+        assert!(parent.spawn(sibling, &mut sibling_handle).is_ok());
+        parent.run();
+
+        assert_eq!(group_handle.value, Some(()));
+        assert_eq!(sibling_handle.value, Some(()));
+        assert_eq!(steps.get(), 5);
+        assert_eq!(handles.map(|handle| handle.value), [Some(0), Some(10)]);
+    }
+
+    #[test]
+    fn test_counted() {
+        use super::helpers::{PollCounter, counted, try_join_all};
+        use core::future::poll_fn;
+
+        let counters = [const { PollCounter::new() }; 3];
+        let mut futures = core::array::from_fn::<_, 3, _>(|i| {
+            let mut pending = i;
+
+            counted(
+                poll_fn(move |cx| {
+                    if pending == 0 {
+                        return Poll::Ready(Ok::<_, ()>(i));
+                    }
+
+                    pending -= 1;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }),
+                &counters[i],
+            )
+        });
+        let mut out = [None; 3];
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.block_on(try_join_all(&mut futures, &mut out));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(out, [Some(0), Some(1), Some(2)]);
+        // each future is polled until it completes and never again afterwards
+        assert_eq!(counters.each_ref().map(PollCounter::get), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_run_parked() {
+        use super::time::yield_until_tick;
+
+        let clock = MockClock { now: Cell::new(0) };
+        let mut sleeps = [None; 4];
+        let mut count = 0;
+        let fast = pin!(Task::new("fast", async {
+            yield_until_tick(3).await;
+            yield_until_tick(9).await;
+        }));
+        let mut fast_handle = Handle::new();
+        let slow = pin!(Task::new("slow", async { yield_until_tick(7).await }));
+        let mut slow_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_time_source(&clock);
+        assert!(executor.spawn(fast, &mut fast_handle).is_ok());
+        assert!(executor.spawn(slow, &mut slow_handle).is_ok());
+
+        executor.run_parked(|ticks| {
+            sleeps[count] = Some(ticks);
+            count += 1;
+            clock.advance(ticks.unwrap_or(1));
+        });
+
+        assert_eq!(fast_handle.value, Some(()));
+        assert_eq!(slow_handle.value, Some(()));
+        // the gaps between the deadlines at the ticks 3, 7 and 9
+        assert_eq!(sleeps, [Some(Some(3)), Some(Some(4)), Some(Some(2)), None]);
+        assert_eq!(clock.now(), 9);
+    }
+
+    #[test]
+    fn test_wake_tracking() {
+        use super::sync::Notify;
+        use core::future::poll_fn;
+
+        static WAKES: WakeTable<3> = WakeTable::new();
+
+        let notify = Notify::new();
+        let done = Cell::new(false);
+        let stalled = pin!(Task::new("stalled", poll_fn(|_| Poll::<()>::Pending)));
+        let mut stalled_handle = Handle::new();
+        let waiter = pin!(Task::new("waiter", async {
+            notify.notified().await;
+            done.set(true);
+        }));
+        let mut waiter_handle = Handle::new();
+        let notifier = pin!(Task::new("notifier", async {
+            for _ in 0..3 {
+                yield_me().await;
+            }
+
+            notify.notify_one();
+        }));
+        let mut notifier_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_wake_table(&WAKES);
+        assert!(executor.spawn(stalled, &mut stalled_handle).is_ok());
+        assert!(executor.spawn(waiter, &mut waiter_handle).is_ok());
+        assert!(executor.spawn(notifier, &mut notifier_handle).is_ok());
+
+        assert!(executor.run_until_predicate(&done, Cell::get));
+
+        let polls = executor.snapshot().slots.map(|slot| slot.polls);
+        // the stalled task never wakes itself, the waiter is woken by the notification only
+        assert_eq!(polls, [1, 2, 4]);
+        assert!(executor.snapshot().slots[0].occupied);
+    }
+
+    #[test]
+    fn test_run_once() {
+        use super::executor::RunStatus;
+
+        let tasks = pin!(core::array::from_fn::<_, 3, _>(|i| {
+            Task::new_nameless(async move {
+                for _ in 0..i {
+                    yield_me().await;
+                }
+            })
+        }));
+        let mut handles = [const { Handle::new() }; 3];
+        let mut executor = Executor::<3>::new();
+
+        for (task, handle) in pin_each(tasks).into_iter().zip(handles.iter_mut()) {
+            assert!(executor.spawn(task, handle).is_ok());
+        }
+
+        let mut statuses = [None; 4];
+
+        for status in &mut statuses {
+            *status = Some(executor.run_once());
+        }
+
+        assert_eq!(
+            statuses,
+            [
+                Some(RunStatus::Pending {
+                    remaining: 2,
+                    completed: 1
+                }),
+                Some(RunStatus::Pending {
+                    remaining: 1,
+                    completed: 1
+                }),
+                Some(RunStatus::AllComplete { completed: 1 }),
+                Some(RunStatus::AllComplete { completed: 0 }),
+            ]
+        );
+        assert_eq!(executor.pass_count(), 4);
+        assert!(handles.iter().all(|handle| handle.value.is_some()));
+    }
+
+    #[test]
+    fn test_idle_hook() {
+        use super::time::yield_until_tick;
+        use core::future::poll_fn;
+        use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+        static WAKES: WakeTable<2> = WakeTable::new();
+        static BLOCK_ON_WAKES: WakeTable<1> = WakeTable::new();
+        static NOW: AtomicU64 = AtomicU64::new(0);
+        static IDLES: AtomicU32 = AtomicU32::new(0);
+
+        // sleeps until the next tick of the clock, like `wfi` until a timer interrupt
+        fn idle() {
+            IDLES.fetch_add(1, Ordering::Relaxed);
+            NOW.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let now = || NOW.load(Ordering::Relaxed);
+        let sleeper = pin!(Task::new("sleeper", yield_until_tick(3)));
+        let mut sleeper_handle = Handle::new();
+        let worker = pin!(Task::new("worker", async {
+            yield_me().await;
+            yield_me().await;
+        }));
+        let mut worker_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_wake_table(&WAKES);
+        executor.set_time_source(&now);
+        executor.set_idle_hook(idle);
+        assert!(executor.spawn(sleeper, &mut sleeper_handle).is_ok());
+        assert!(executor.spawn(worker, &mut worker_handle).is_ok());
+        executor.run();
+
+        // no idling while the worker makes progress, then once per tick until the deadline
+        assert_eq!(worker_handle.value, Some(()));
+        assert_eq!(sleeper_handle.value, Some(()));
+        assert_eq!(IDLES.load(Ordering::Relaxed), 3);
+        assert_eq!(NOW.load(Ordering::Relaxed), 3);
+
+        let mut polls = 0;
+        let mut executor = Executor::<1>::new();
+        executor.set_wake_table(&BLOCK_ON_WAKES);
+        executor.set_idle_hook(idle);
+        let output = executor.block_on(poll_fn(|cx| {
+            polls += 1;
+
+            if polls <= 2 {
+                cx.waker().wake_by_ref();
+            } else if IDLES.load(Ordering::Relaxed) == 5 {
+                return Poll::Ready(polls);
+            }
+
+            Poll::Pending
+        }));
+
+        // the first two polls wake the future, the next two do not
+        assert_eq!(output, 5);
+        assert_eq!(IDLES.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_executor_len() {
+        let short = pin!(Task::new("short", async {}));
+        let mut short_handle = Handle::new();
+        let long = pin!(Task::new("long", async {
+            yield_me().await;
+            yield_me().await;
+        }));
+        let mut long_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+
+        assert!(executor.is_empty());
+        assert_eq!(executor.capacity(), 3);
+        assert!(executor.spawn(short, &mut short_handle).is_ok());
+        assert!(executor.spawn(long, &mut long_handle).is_ok());
+        assert_eq!(executor.len(), 2);
+
+        // the short task completes in the first pass and frees its slot
+        executor.run_once();
+        assert_eq!(executor.len(), 1);
+        assert!(!executor.is_empty());
+        assert!(!executor.snapshot().slots[0].occupied);
+
+        executor.run();
+        assert_eq!(executor.len(), 0);
+        assert!(executor.is_empty());
+        assert_eq!(executor.capacity(), 3);
+    }
+
+    #[test]
+    fn test_slot_reuse() {
+        let tasks = pin!(core::array::from_fn::<_, 4, _>(|i| {
+            Task::new_nameless(async move { i })
+        }));
+        let [first, second, third, fourth] = pin_each(tasks);
+        let mut handles = [const { Handle::new() }; 4];
+        let [first_handle, second_handle, third_handle, fourth_handle] = handles.each_mut();
+        let mut executor = Executor::<2>::new();
+
+        let first_id = executor.spawn(first, first_handle).unwrap();
+        let second_id = executor.spawn(second, second_handle).unwrap();
+        executor.run();
+        assert!(executor.is_empty());
+
+        // both slots are free again after the run
+        let third_id = executor.spawn(third, third_handle).unwrap();
+        assert_eq!(third_id.index(), first_id.index());
+        // the dependency has completed already, the task taking over its slot is not blocked
+        let fourth_id = executor
+            .spawn_after(second_id, fourth, fourth_handle)
+            .unwrap();
+        assert_eq!(fourth_id.index(), second_id.index());
+        executor.run();
+
+        assert!(executor.is_empty());
+        assert_eq!(
+            handles.map(|handle| handle.value),
+            [Some(0), Some(1), Some(2), Some(3)]
+        );
+    }
+
+    #[test]
+    fn test_try_spawn() {
+        let occupant = pin!(Task::new("occupant", async {}));
+        let mut occupant_handle = Handle::new();
+        let task = pin!(Task::new("task", async { 42 }));
+        let mut handle = Handle::new();
+        let mut full = Executor::<1>::new();
+        let mut free = Executor::<1>::new();
+        assert!(full.spawn(occupant, &mut occupant_handle).is_ok());
+
+        let Err(rejected) = full.try_spawn(task, &mut handle) else {
+            panic!("the task is spawned into a full executor");
+        };
+        assert_eq!(rejected.task.name, Some("task"));
+        assert!(free.spawn(rejected.task, rejected.handle).is_ok());
+        full.run();
+        free.run();
+
+        assert_eq!(occupant_handle.value, Some(()));
+        assert_eq!(handle.value, Some(42));
+    }
+
+    #[test]
+    fn test_task_id_generation() {
+        let tasks = pin!(core::array::from_fn::<_, 3, _>(|i| {
+            Task::new_nameless(async move {
+                yield_me().await;
+                i
+            })
+        }));
+        let [first, second, third] = pin_each(tasks);
+        let mut handles = [const { Handle::new() }; 3];
+        let [first_handle, second_handle, third_handle] = handles.each_mut();
+        let mut executor = Executor::<1>::new();
+
+        let first_id = executor.spawn(first, first_handle).unwrap();
+        executor.run();
+        let second_id = executor.spawn(second, second_handle).unwrap();
+
+        // the slot is reused, but the identifiers tell its tasks apart
+        assert_eq!(first_id.index(), second_id.index());
+        assert_ne!(first_id, second_id);
+        assert_eq!(second_id.generation(), first_id.generation() + 1);
+        // stepping the stale identifier leaves the new task alone
+        assert_eq!(executor.step_task_until_pending(first_id), Poll::Ready(()));
+        assert_eq!(executor.snapshot().slots[0].polls, 0);
+        assert_eq!(executor.step_task_until_pending(second_id), Poll::Pending);
+
+        // the dependency has completed, so the new occupant of its slot does not block the task
+        executor.run();
+        let third_id = executor
+            .spawn_after(second_id, third, third_handle)
+            .unwrap();
+        assert_eq!(executor.step_task_until_pending(third_id), Poll::Pending);
+        executor.run();
+
+        assert_eq!(
+            handles.map(|handle| handle.value),
+            [Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_cancel() {
+        /// Records that the future holding it has been dropped.
+        struct Resource<'a>(&'a Cell<bool>);
+
+        impl Drop for Resource<'_> {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let released = Cell::new(false);
+        let spinner = pin!(Task::new("spinner", async {
+            let _resource = Resource(&released);
+
+            loop {
+                yield_me().await;
+            }
+        }));
+        let mut spinner_handle: Handle<()> = Handle::new();
+        let worker = pin!(Task::new("worker", async {
+            yield_me().await;
+            7
+        }));
+        let mut worker_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        let spinner_id = executor.spawn(spinner, &mut spinner_handle).unwrap();
+        let worker_id = executor.spawn(worker, &mut worker_handle).unwrap();
+
+        executor.run_once();
+        assert!(!released.get());
+        assert!(executor.cancel(spinner_id));
+        // the future is dropped right away, while the executor is still alive
+        assert!(released.get());
+        assert!(!executor.cancel(spinner_id));
+        assert_eq!(executor.len(), 1);
+
+        executor.run();
+        assert!(!executor.cancel(worker_id));
+        assert_eq!(executor.snapshot().slots[0].polls, 1);
+        assert_eq!(spinner_handle.value, None);
+        assert_eq!(worker_handle.value, Some(7));
+    }
+    #[test]
+    fn test_handle_is_ready_take() {
+        #[derive(Debug, PartialEq)]
+        struct Buffer([u8; 3]);
+
+        let task = pin!(Task::new("task", async {
+            yield_me().await;
+            Buffer([1, 2, 3])
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        // the task yields on its first poll, so its handle stays empty
+        executor.run_once();
+        assert!(!handle.is_ready());
+        assert_eq!(handle.take(), None);
+
+        let task = pin!(Task::new("task", async { Buffer([4, 5, 6]) }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+        assert!(handle.is_ready());
+        assert_eq!(handle.take(), Some(Buffer([4, 5, 6])));
+        // the output is moved out, leaving the handle empty
+        assert!(!handle.is_ready());
+        assert_eq!(handle.take(), None);
+    }
+    #[test]
+    fn test_channel() {
+        use super::channel::Channel;
+
+        let mut channel = Channel::<u32, 3>::new();
+        let (mut sender, mut receiver) = channel.split();
+        let producer = pin!(Task::new("producer", async move {
+            for value in 0..10 {
+                sender.send(value).await;
+            }
+        }));
+        let mut producer_handle = Handle::new();
+        let consumer = pin!(Task::new("consumer", async move {
+            let mut values = [0; 10];
+
+            for slot in &mut values {
+                *slot = receiver.recv().await;
+            }
+
+            values
+        }));
+        let mut consumer_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+
+        let result = executor.spawn(producer, &mut producer_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(consumer, &mut consumer_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the producer fills the channel and waits for the consumer to free a slot several times
+        assert!(executor.pass_count() > 3);
+        assert_eq!(consumer_handle.value, Some([0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        assert!(channel.is_empty());
+    }
+    #[test]
+    fn test_mutex() {
+        use super::sync::Mutex;
+
+        let counter = Mutex::new(0u32);
+        let increment = || async {
+            for _ in 0..5 {
+                let mut guard = counter.lock().await;
+                let value = *guard;
+                // the lock is held across the yield, so the other task cannot interleave
+                yield_me().await;
+                *guard = value + 1;
+            }
+        };
+        let tasks = pin!(core::array::from_fn::<_, 2, _>(|_| Task::new(
+            "increment",
+            increment()
+        )));
+        let mut handles = [const { Handle::new() }; 2];
+        let mut executor = Executor::<2>::new();
+
+        for (task, handle) in zip(pin_each(tasks), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        executor.run();
+
+        assert!(!counter.is_locked());
+        assert_eq!(*super::block_on(counter.lock()), 10);
+    }
+    #[test]
+    fn test_wake_after_task_dropped() {
+        use super::helpers::never;
+        use super::sync::Notify;
+
+        static WAKES: WakeTable<1> = WakeTable::new();
+
+        let notify = Notify::new();
+
+        {
+            let task = pin!(Task::new("waiter", notify.notified()));
+            let mut handle = Handle::new();
+            let mut executor = Executor::<1>::new();
+            executor.set_wake_table(&WAKES);
+
+            let result = executor.spawn(task, &mut handle);
+            assert!(result.is_ok());
+            // the waiter leaves a clone of its waker in the notification
+            executor.run_once();
+        }
+
+        // the new task may claim the wake flag the waiter held, but the stale waker cannot set it
+        let task = pin!(Task::new("stalled", never()));
+        let mut handle = Handle::<()>::new();
+        let mut executor = Executor::<1>::new();
+        executor.set_wake_table(&WAKES);
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run_once();
+        notify.notify_one();
+        executor.run_once();
+
+        assert_eq!(executor.snapshot().slots[0].polls, 1);
+    }
+    #[test]
+    fn test_slot_reuse_clears_wake() {
+        use super::helpers::never;
+        use core::task::Waker;
+
+        static WAKES: WakeTable<1> = WakeTable::new();
+
+        let waker = Cell::new(None::<Waker>);
+        let first = pin!(Task::new(
+            "first",
+            core::future::poll_fn(|cx| {
+                // wake itself right before completing and keep a waker around
+                cx.waker().wake_by_ref();
+                waker.set(Some(cx.waker().clone()));
+                Poll::Ready(())
+            })
+        ));
+        let mut first_handle = Handle::new();
+        let second = pin!(Task::new("second", never()));
+        let mut second_handle = Handle::<()>::new();
+        let mut executor = Executor::<1>::new();
+        executor.set_wake_table(&WAKES);
+
+        let result = executor.spawn(first, &mut first_handle);
+        assert!(result.is_ok());
+        executor.run_once();
+        assert!(executor.is_empty());
+
+        let result = executor.spawn(second, &mut second_handle);
+        assert!(result.is_ok_and(|id| id.index() == 0));
+
+        // the new task is polled once on spawn and not again for the wake left by the first one
+        for _ in 0..3 {
+            executor.run_once();
+        }
+
+        assert_eq!(executor.snapshot().slots[0].polls, 1);
+
+        // a waker of the first task does not wake the task in its slot either
+        waker.take().expect("no waker").wake();
+        executor.run_once();
+        assert_eq!(executor.snapshot().slots[0].polls, 1);
+    }
+    #[test]
+    fn test_yield_count() {
+        static WAKES: WakeTable<2> = WakeTable::new();
+
+        let notify = Notify::new();
+        let yielding = pin!(Task::new("yielding", async {
+            for _ in 0..3 {
+                yield_me().await;
+            }
+
+            notify.notify_one();
+        }));
+        let mut yielding_handle = Handle::new();
+        let waiting = pin!(Task::new("waiting", notify.notified()));
+        let mut waiting_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_wake_table(&WAKES);
+
+        let yielding_id = executor.spawn(yielding, &mut yielding_handle).unwrap();
+        let waiting_id = executor.spawn(waiting, &mut waiting_handle).unwrap();
+        executor.run();
+
+        assert_eq!(executor.yield_count(yielding_id), Some(3));
+        // waiting for the notification is not a yield
+        assert_eq!(executor.yield_count(waiting_id), Some(0));
+        assert_eq!(executor.snapshot().slots[waiting_id.index()].polls, 2);
+    }
+    #[test]
+    fn test_nested_block_on_keeps_wake() {
+        use core::future::poll_fn;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static OUTER_WAKES: WakeTable<1> = WakeTable::new();
+        static INNER_WAKES: WakeTable<1> = WakeTable::new();
+
+        static IDLES: AtomicU32 = AtomicU32::new(0);
+
+        fn idle() {
+            IDLES.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut outer = Executor::<1>::new();
+        outer.set_wake_table(&OUTER_WAKES);
+        outer.set_idle_hook(idle);
+        let mut outer_polls = 0;
+        let output = outer.block_on(poll_fn(|cx| {
+            outer_polls += 1;
+
+            if outer_polls > 1 {
+                return Poll::Ready(outer_polls);
+            }
+
+            // the outer future wakes itself before running a nested call to completion
+            cx.waker().wake_by_ref();
+            let mut inner = Executor::<1>::new();
+            inner.set_wake_table(&INNER_WAKES);
+            inner.set_idle_hook(idle);
+            let mut inner_polls = 0;
+            inner.block_on(poll_fn(|cx| {
+                inner_polls += 1;
+
+                if inner_polls > 1 {
+                    return Poll::Ready(());
+                }
+
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }));
+
+            Poll::Pending
+        }));
+
+        // the wake of the outer future survives the nested call, so nothing idles
+        assert_eq!(output, 2);
+        assert_eq!(IDLES.load(Ordering::Relaxed), 0);
+    }
+    #[test]
+    fn test_max_latency_from_wake() {
+        static WAKES: WakeTable<3> = WakeTable::new();
+
+        let clock = MockClock::default();
+        let notify = Notify::new();
+        let busy = pin!(Task::new("busy", async {
+            yield_me().await;
+            // keeps the executor busy for 4 ticks on the second pass
+            clock.advance(4);
+        }));
+        let mut busy_handle = Handle::new();
+        let waiting = pin!(Task::new("waiting", notify.notified()));
+        let mut waiting_handle = Handle::new();
+        let notifier = pin!(Task::new("notifier", async {
+            clock.advance(3);
+            notify.notify_one();
+        }));
+        let mut notifier_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_wake_table(&WAKES);
+        executor.set_time_source(&clock);
+
+        assert!(executor.spawn(busy, &mut busy_handle).is_ok());
+        let waiting_id = executor.spawn(waiting, &mut waiting_handle).unwrap();
+        assert!(executor.spawn(notifier, &mut notifier_handle).is_ok());
+        executor.run();
+
+        // `waiting` starts waiting on tick 0, is woken on tick 3 and polled after `busy` on tick 7
+        assert_eq!(executor.max_latency(waiting_id), Some(4));
+    }
+    #[test]
+    fn test_checkpoint_ignores_waiting_siblings() {
+        use super::helpers::checkpoint;
+
+        static WAKES: WakeTable<2> = WakeTable::new();
+
+        let notify = Notify::new();
+        let waiting = pin!(Task::new("waiting", notify.notified()));
+        let mut waiting_handle = Handle::new();
+        let working = pin!(Task::new("working", async {
+            for _ in 0..3 {
+                checkpoint().await;
+            }
+
+            notify.notify_one();
+        }));
+        let mut working_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_wake_table(&WAKES);
+
+        let result = executor.spawn(waiting, &mut waiting_handle);
+        assert!(result.is_ok());
+        let working_id = executor.spawn(working, &mut working_handle).unwrap();
+        executor.run();
+
+        // the sibling waits for the notification, so no checkpoint yields to it
+        assert_eq!(executor.yield_count(working_id), Some(0));
+        assert_eq!(executor.pass_count(), 2);
+    }
+    #[test]
+    fn test_concurrency_limit_waiting_tasks() {
+        static WAKES: WakeTable<TASKS> = WakeTable::new();
+
+        const TASKS: usize = 4;
+        let notifies = [const { Notify::new() }; 2];
+        let first = pin!(Task::new("first", notifies[0].notified()));
+        let mut first_handle = Handle::new();
+        let second = pin!(Task::new("second", notifies[1].notified()));
+        let mut second_handle = Handle::new();
+        let tasks = pin!(core::array::from_fn::<_, 2, _>(|_| Task::new(
+            "yielding",
+            yield_me()
+        )));
+        let mut handles = [const { Handle::new() }; 2];
+        let mut executor = Executor::<TASKS>::new();
+        executor.set_wake_table(&WAKES);
+        executor.set_concurrency_limit(2);
+
+        let result = executor.spawn(first, &mut first_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(second, &mut second_handle);
+        assert!(result.is_ok());
+
+        for (task, handle) in zip(pin_each(tasks), &mut handles) {
+            let result = executor.spawn(task, handle);
+            assert!(result.is_ok());
+        }
+
+        for _ in 0..3 {
+            executor.run_once();
+        }
+
+        // the first two tasks are in flight while they wait, so the others are held back
+        let polls = executor.snapshot().slots.map(|slot| slot.polls);
+        assert_eq!(polls, [1, 1, 0, 0]);
+
+        notifies[0].notify_one();
+        executor.run_once();
+
+        // the first task completes, which lets the third one start
+        let polls = executor.snapshot().slots.map(|slot| slot.polls);
+        assert_eq!(polls, [2, 1, 1, 0]);
+
+        notifies[1].notify_one();
+        executor.run();
+
+        assert_eq!(
+            executor.snapshot().slots.map(|slot| slot.polls),
+            [2, 2, 2, 2]
+        );
+    }
+    #[test]
+    fn test_task_id_generation_wrap() {
+        let tasks = pin!(core::array::from_fn::<_, 3, _>(|_| Task::new_nameless(
+            yield_me()
+        )));
+        let [first, second, third] = pin_each(tasks);
+        let mut handles = [const { Handle::new() }; 3];
+        let [first_handle, second_handle, third_handle] = handles.each_mut();
+        let mut executor = Executor::<1>::new();
+        executor.set_generation(0, u32::MAX - 1);
+
+        let first_id = executor.spawn(first, first_handle).unwrap();
+        assert_eq!(first_id.generation(), u32::MAX);
+        executor.run();
+
+        // the generation wraps around to zero
+        let second_id = executor.spawn(second, second_handle).unwrap();
+        assert_eq!(second_id.generation(), 0);
+        assert_ne!(first_id, second_id);
+        // the identifier from before the wrap-around is still stale
+        assert_eq!(executor.step_task_until_pending(first_id), Poll::Ready(()));
+        assert!(!executor.cancel(first_id));
+        assert_eq!(executor.yield_count(first_id), None);
+        assert_eq!(executor.snapshot().slots[0].polls, 0);
+        assert_eq!(executor.step_task_until_pending(second_id), Poll::Pending);
+        executor.run();
+
+        let third_id = executor.spawn(third, third_handle).unwrap();
+        assert_eq!(third_id.generation(), 1);
+        assert!(!executor.cancel(second_id));
+        executor.run();
+
+        assert_eq!(handles.map(|handle| handle.value), [Some(()); 3]);
+    }
+    #[test]
+    fn test_channel_stuck_consumer() {
+        use super::channel::Channel;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static WARNINGS: AtomicU32 = AtomicU32::new(0);
+
+        fn on_stuck(passes: u32) {
+            assert_eq!(passes, 3);
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut channel = Channel::<u32, 2>::new();
+        channel.set_stuck_warning(3, on_stuck);
+        let (mut sender, mut receiver) = channel.split();
+        let monitored = receiver.channel();
+        let producer = pin!(Task::new("producer", async move {
+            for value in 0..6 {
+                sender.send(value).await;
+            }
+        }));
+        let mut producer_handle = Handle::new();
+        let consumer = pin!(Task::new("consumer", async move {
+            let mut sum = 0;
+
+            for _ in 0..6 {
+                // a slow consumer, leaving the channel full for several passes
+                for _ in 0..4 {
+                    yield_me().await;
+                }
+
+                sum += receiver.recv().await;
+            }
+
+            sum
+        }));
+        let mut consumer_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_depth_monitor(monitored);
+
+        let result = executor.spawn(producer, &mut producer_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(consumer, &mut consumer_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        assert_eq!(consumer_handle.value, Some(15));
+        assert_eq!(monitored.peak_depth(), 2);
+        // the channel stays full while the consumer yields before each of the first receives
+        assert!(WARNINGS.load(Ordering::Relaxed) >= 3);
+    }
+    #[test]
+    fn test_mutex_waiters_do_not_spin() {
+        use super::sync::Mutex;
+
+        static WAKES: WakeTable<3> = WakeTable::new();
+
+        let counter = Mutex::new(0u32);
+        let holder = async {
+            let mut guard = counter.lock().await;
+
+            for _ in 0..5 {
+                yield_me().await;
+            }
+
+            *guard += 1;
+        };
+        let waiter = || async {
+            *counter.lock().await += 1;
+        };
+        let holder = pin!(Task::new("holder", holder));
+        let mut holder_handle = Handle::new();
+        let first = pin!(Task::new("first", waiter()));
+        let mut first_handle = Handle::new();
+        let second = pin!(Task::new("second", waiter()));
+        let mut second_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_wake_table(&WAKES);
+
+        let result = executor.spawn(holder, &mut holder_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(first, &mut first_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(second, &mut second_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // each waiter keeps its waker registered while the lock is held, so it is polled once to
+        // wait and once after the unlock instead of on every pass
+        let polls = executor.snapshot().slots.map(|slot| slot.polls);
+        assert_eq!(polls, [6, 2, 2]);
+        assert_eq!(*super::block_on(counter.lock()), 3);
+    }
+    #[test]
+    fn test_task_pool_abort_frees_slot() {
+        use super::helpers::abort_self;
+        use super::pool::TaskPool;
+
+        async fn give_up(counter: &Cell<u32>) {
+            counter.set(counter.get() + 1);
+            abort_self().await;
+            counter.set(counter.get() + 100);
+        }
+
+        let counter = Cell::new(0);
+        let pool = pin!(TaskPool::<_, 1>::new("give_up"));
+        let pool = pool.into_ref();
+        let mut executor = Executor::<2>::new();
+
+        for round in 1..=2 {
+            assert!(pool.spawn(&mut executor, give_up(&counter)).is_ok());
+            assert_eq!(pool.available(), 0);
+            executor.run();
+
+            // the aborted task never completes, but dropping its future returns the slot
+            assert_eq!(pool.available(), 1);
+            assert_eq!(counter.get(), round);
+        }
+    }
+    #[test]
+    fn test_mutex_waiter_polled_while_registered() {
+        use super::sync::Mutex;
+        use core::future::poll_fn;
+
+        static WAKES: WakeTable<3> = WakeTable::new();
+
+        let counter = Mutex::new(0u32);
+        let holder = pin!(Task::new("holder", async {
+            let _guard = counter.lock().await;
+
+            for _ in 0..10 {
+                yield_me().await;
+            }
+        }));
+        let mut holder_handle = Handle::new();
+        let waiter = pin!(Task::new("waiter", async {
+            *counter.lock().await += 1;
+        }));
+        let mut waiter_handle = Handle::new();
+        let restless = pin!(Task::new("restless", async {
+            let mut lock = counter.lock();
+            // waits for the lock while waking itself on every poll, e.g. to check a timeout
+            let mut guard = poll_fn(|cx| {
+                let poll = Pin::new(&mut lock).poll(cx);
+
+                if poll.is_pending() {
+                    cx.waker().wake_by_ref();
+                }
+
+                poll
+            })
+            .await;
+            *guard += 1;
+        }));
+        let mut restless_handle = Handle::new();
+        let mut executor = Executor::<3>::new();
+        executor.set_wake_table(&WAKES);
+
+        let result = executor.spawn(holder, &mut holder_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(waiter, &mut waiter_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(restless, &mut restless_handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the restless task keeps a single entry, so it never displaces the waiter
+        let polls = executor.snapshot().slots.map(|slot| slot.polls);
+        assert_eq!(polls[1], 2);
+        assert_eq!(*super::block_on(counter.lock()), 2);
+    }
+    #[test]
+    fn test_notify_one_wakes_single_waiter() {
+        static WAKES: WakeTable<2> = WakeTable::new();
+
+        let notify = Notify::new();
+        let first = pin!(Task::new("first", notify.notified()));
+        let mut first_handle = Handle::new();
+        let second = pin!(Task::new("second", notify.notified()));
+        let mut second_handle = Handle::new();
+        let mut executor = Executor::<2>::new();
+        executor.set_wake_table(&WAKES);
+
+        let result = executor.spawn(first, &mut first_handle);
+        assert!(result.is_ok());
+        let result = executor.spawn(second, &mut second_handle);
+        assert!(result.is_ok());
+        executor.run_once();
+
+        notify.notify_one();
+        executor.run_once();
+
+        // only the first waiter is woken, the second stays registered without being polled
+        let snapshot = executor.snapshot();
+        assert_eq!(snapshot.slots.map(|slot| slot.polls), [2, 1]);
+        assert_eq!(snapshot.slots.map(|slot| slot.occupied), [false, true]);
+
+        notify.notify_one();
+        executor.run();
+
+        assert_eq!(first_handle.value, Some(()));
+        assert_eq!(second_handle.value, Some(()));
+    }
+    #[test]
+    fn test_around_poll_abort_self() {
+        use super::executor::TaskId;
+        use super::helpers::abort_self;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static READY: AtomicU32 = AtomicU32::new(0);
+
+        fn after(_: TaskId, result: Poll<()>) {
+            if result.is_ready() {
+                READY.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let task = pin!(Task::new("quitter", async {
+            abort_self().await;
+            42
+        }));
+        let mut handle = Handle::new();
+        let mut executor = Executor::<1>::new();
+        executor.set_around_poll(|_| {}, after);
+
+        let result = executor.spawn(task, &mut handle);
+        assert!(result.is_ok());
+        executor.run();
+
+        // the task leaves the executor with its only poll, which is reported as finished
+        assert_eq!(READY.load(Ordering::Relaxed), 1);
+        assert_eq!(handle.value, None);
+    }
 }
@@ -0,0 +1,201 @@
+//! # `TaskPool` implementation
+//!
+//! Workloads spawning many short-lived tasks of the same type would need a new [`Task`] value,
+//! pinned somewhere on the stack, for every spawn. This module provides [`TaskPool`], a fixed
+//! number of task slots allocated once and reused: each spawn takes a free slot, builds the task
+//! there around a fresh future and spawns it, and the slot returns to the pool as soon as its task
+//! completes or its future is dropped. This is object pooling applied to tasks, without any heap
+//! allocation.
+//!
+//! The pool is pinned, as the tasks in its slots are polled in place. It is shared by reference
+//! with the executor, which borrows each spawned task for as long as the executor lives. A slot is
+//! only handed out again once its task has completed, been cancelled or aborted itself, when the
+//! executor has already removed it.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! use core::cell::Cell;
+//! use core::pin::pin;
+//! use miniloop::pool::TaskPool;
+//!
+//! async fn add(sum: &Cell<u32>, value: u32) {
+//!     sum.set(sum.get() + value);
+//! }
+//!
+//! let sum = Cell::new(0);
+//! let pool = pin!(TaskPool::<_, 2>::new("adder"));
+//! let pool = pool.into_ref();
+//! let mut executor = Executor::<4>::new();
+//!
+//! for values in [[1, 2], [3, 4]] {
+//!     for value in values {
+//!         pool.spawn(&mut executor, add(&sum, value)).expect("Failed to spawn task");
+//!     }
+//!
+//!     executor.run();
+//! }
+//!
+//! assert_eq!(sum.get(), 10);
+//! ```
+use crate::executor::{Error, Executor, TaskId};
+use crate::task::{Handle, Task};
+
+use core::cell::{Cell, UnsafeCell};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A future returning its pool slot once the inner future completes or is dropped.
+///
+/// The flag of the slot is referenced through a raw pointer, as the future is stored in the slot
+/// itself: a borrow of the pool would have to outlive the pool for the drop check to accept
+/// dropping the future.
+pub struct Pooled<F> {
+    /// The future of the task.
+    future: F,
+    /// The flag marking the slot of the task as taken.
+    taken: *const Cell<bool>,
+}
+
+impl<F> Pooled<F> {
+    /// Marks the slot of the task as free.
+    fn release(&self) {
+        // SAFETY: the flag lives in the same pinned slot as the task holding this future, so it
+        // stays in place for as long as the future exists.
+        unsafe { (*self.taken).set(false) };
+    }
+}
+
+impl<F: Future<Output = ()>> Future for Pooled<F> {
+    type Output = ();
+
+    /// Polls the inner future and frees the slot once it completes.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` if the inner future completed.
+    /// * `Poll::Pending` otherwise.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `Pooled`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        if future.poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        // the executor removes the task right after this poll, before the slot can be taken again
+        this.release();
+        Poll::Ready(())
+    }
+}
+
+impl<F> Drop for Pooled<F> {
+    /// Frees the slot of a task that is dropped without completing, i.e. cancelled by the executor
+    /// or aborted with [`abort_self`](crate::helpers::abort_self).
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// A slot of a [`TaskPool`].
+struct Slot<'a, F: Future<Output = ()>> {
+    /// The task spawned from the slot last. Declared before `taken`, so the task is dropped while
+    /// the flag its future points to is still alive.
+    task: UnsafeCell<Option<Task<'a, Pooled<F>>>>,
+    /// Whether the task in the slot has been spawned and has not completed yet.
+    taken: Cell<bool>,
+    /// The handle the task is linked with. The tasks produce no output, so it is never read.
+    handle: UnsafeCell<Handle<()>>,
+}
+
+/// A pool of `N` reusable slots for tasks running futures of type `F`.
+///
+/// All tasks of the pool share its name. A task cancelled by the executor or aborting itself
+/// returns its slot as well, as the executor drops its future.
+///
+/// # Type Parameters
+/// - `'a`: The lifetime of the executor the tasks are spawned into.
+/// - `F`: The future type of the tasks, e.g. the future returned by an `async fn`.
+/// - `N`: The number of slots.
+pub struct TaskPool<'a, F: Future<Output = ()>, const N: usize> {
+    /// The name of the tasks spawned from the pool.
+    name: &'a str,
+    /// The task slots.
+    slots: [Slot<'a, F>; N],
+}
+
+impl<'a, F: Future<Output = ()> + 'a, const N: usize> TaskPool<'a, F, N> {
+    /// Creates a new `TaskPool` with all slots free.
+    ///
+    /// # Parameters
+    ///
+    /// * `name`:
+    ///   The name of the tasks spawned from the pool.
+    #[must_use]
+    pub const fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            slots: [const {
+                Slot {
+                    task: UnsafeCell::new(None),
+                    taken: Cell::new(false),
+                    handle: UnsafeCell::new(Handle::new()),
+                }
+            }; N],
+        }
+    }
+
+    /// Returns the number of free slots.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.slots.iter().filter(|slot| !slot.taken.get()).count()
+    }
+
+    /// Builds a task around `future` in a free slot of the pool and spawns it into `executor`.
+    ///
+    /// The slot returns to the pool once the task completes, is cancelled or aborts itself.
+    ///
+    /// # Returns
+    ///
+    /// The [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// * `NoFreeSlots` - if there is no free slot either in the pool or in the executor. The error
+    ///   carries the name of the pool.
+    pub fn spawn<const TASK_ARRAY_SIZE: usize>(
+        self: Pin<&'a Self>,
+        executor: &mut Executor<'a, TASK_ARRAY_SIZE>,
+        future: F,
+    ) -> Result<TaskId, Error<'a>> {
+        let pool = self.get_ref();
+        let Some(slot) = pool.slots.iter().find(|slot| !slot.taken.get()) else {
+            return Err(Error::NoFreeSlots {
+                name: Some(pool.name),
+            });
+        };
+
+        // SAFETY: a free slot is referenced by no executor, as its last task has completed or has
+        // had its future dropped, and has been removed. The slot is pinned along with the pool, so
+        // the new task is dropped in place once it is replaced or the pool is dropped.
+        let (task, handle) = unsafe {
+            let task = (*slot.task.get()).insert(Task::new(
+                pool.name,
+                Pooled {
+                    future,
+                    taken: &raw const slot.taken,
+                },
+            ));
+
+            (Pin::new_unchecked(task), &mut *slot.handle.get())
+        };
+
+        let id = executor.spawn(task, handle)?;
+        slot.taken.set(true);
+
+        Ok(id)
+    }
+}
@@ -0,0 +1,126 @@
+//! # Reactor interface
+//!
+//! Real runtimes are split in two: the executor polls tasks, while a reactor watches I/O sources
+//! and tells the executor which tasks can make progress. This module provides the reactor half of
+//! that split as the [`Reactor`] trait, so the executor can be connected to any source of
+//! readiness events, e.g. interrupt flags of peripherals or a mock in tests.
+//!
+//! A task waits for an I/O source with [`wait_ready`], naming the source and the events it is
+//! interested in with an [`Interest`]. The task is then parked: the executor registers the
+//! interest with the reactor set by
+//! [`Executor::set_reactor`](crate::executor::Executor::set_reactor), consults the reactor on each
+//! scheduling pass and only polls the task again once its interest is satisfied.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::{Executor, TaskId};
+//! # use miniloop::task::{Handle, Task};
+//! use core::cell::Cell;
+//! use core::pin::pin;
+//! use miniloop::reactor::{Interest, Reactor, wait_ready};
+//!
+//! const UART_RX: Interest = Interest(0);
+//!
+//! struct Uart {
+//!     received: Cell<bool>,
+//! }
+//!
+//! impl Reactor for Uart {
+//!     fn register(&self, _id: TaskId, _interest: Interest) {
+//!         // enable the receive interrupt here
+//!     }
+//!
+//!     fn poll_ready(&self, interest: Interest) -> bool {
+//!         interest == UART_RX && self.received.get()
+//!     }
+//! }
+//!
+//! let uart = Uart { received: Cell::new(true) };
+//! let task = pin!(Task::new("rx", async {
+//!     wait_ready(UART_RX).await;
+//!     // read the received byte
+//! }));
+//! let mut handle = Handle::new();
+//! let mut executor = Executor::<1>::new();
+//! executor.set_reactor(&uart);
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//! executor.run();
+//! ```
+use crate::executor::{TaskId, poll_data};
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// An I/O source and the events a task waits for on it.
+///
+/// The meaning of the value is defined by the [`Reactor`] in use, e.g. the number of a peripheral
+/// or a bit mask of events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interest(pub u32);
+
+/// A source of readiness events for tasks parked by [`wait_ready`].
+pub trait Reactor {
+    /// Called by the executor when the task with the given `id` parks waiting for `interest`.
+    ///
+    /// The reactor can use it to start watching the I/O source, e.g. to enable an interrupt.
+    fn register(&self, id: TaskId, interest: Interest);
+
+    /// Called by the executor on each scheduling pass for every task parked on `interest`, and
+    /// before [`Executor::run_parked`](crate::executor::Executor::run_parked) idles the system.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if `interest` is satisfied and the parked task can be polled again.
+    /// * `false` if the task has to stay parked.
+    fn poll_ready(&self, interest: Interest) -> bool;
+}
+
+/// A future that parks the task until its interest is satisfied, created by [`wait_ready`].
+struct WaitReady {
+    /// The source and the events the task waits for.
+    interest: Interest,
+    /// A flag indicating whether the task has been parked already.
+    parked: bool,
+}
+
+impl Future for WaitReady {
+    type Output = ();
+
+    /// Parks the task on the first poll and completes once the executor polls it again.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` if the task has been parked already.
+    /// * `Poll::Pending` if the task needs to be parked.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.parked {
+            return Poll::Ready(());
+        }
+
+        if let Some(data) = poll_data(cx.waker()) {
+            data.interest.set(Some(this.interest));
+        }
+
+        this.parked = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Waits until the I/O source named by `interest` is ready.
+///
+/// The executor polls the task again only once its [`Reactor`] reports the interest as satisfied.
+/// Without a reactor, e.g. when the task is run by
+/// [`HomogeneousExecutor`](crate::homogeneous::HomogeneousExecutor) or [`block_on`](crate::block_on),
+/// the returned future behaves like [`yield_me`](crate::helpers::yield_me) and the task has to
+/// check the readiness of the source itself.
+pub fn wait_ready(interest: Interest) -> impl Future<Output = ()> {
+    WaitReady {
+        interest,
+        parked: false,
+    }
+}
@@ -16,7 +16,7 @@
 //! - `StackBox` for safely wrapping and pinning stack-based values.
 //! - Type alias `StackBoxFuture` for stack-based pinned trait objects implementing `Future`.
 
-use crate::task::TaskFuture;
+use crate::task::{TaskFuture, TaskOutput};
 
 use core::cell::OnceCell;
 use core::pin::Pin;
@@ -47,21 +47,17 @@ impl<'a, T: ?Sized> StackBox<'a, T> {
     /// Creates a new `StackBox` containing a pinned reference to the provided value.
     ///
     /// # Arguments
-    /// - `value`: A mutable reference to the value to be stored. The reference must have the
-    ///   appropriate lifetime `'a`.
+    /// - `value`: A pinned mutable reference to the value to be stored. The reference must have
+    ///   the appropriate lifetime `'a`.
     ///
     /// # Returns
     /// A `StackBox` containing a pinned mutable reference to the provided value.
     ///
-    /// # Safety
-    /// This function uses `Pin::new_unchecked`, which is unsafe because it assumes
-    /// that the value being pinned will not move for the duration of the pin.
-    /// Ensure that the value cannot be moved out of the `StackBox`.
-    pub fn new(value: &'a mut T) -> Self {
+    /// The value is pinned by the caller, e.g. with [`pin!`](core::pin::pin), so it is guaranteed
+    /// not to move until it is dropped, even after the `StackBox` is gone.
+    pub fn new(value: Pin<&'a mut T>) -> Self {
         let new_box = StackBox::default();
-        new_box
-            .value
-            .get_or_init(|| unsafe { Pin::new_unchecked(value) });
+        new_box.value.get_or_init(|| value);
 
         new_box
     }
@@ -77,3 +73,11 @@ impl<'a, T: ?Sized> StackBox<'a, T> {
 /// # Type Parameters
 /// - `'a`: The lifetime of the reference to the stored future.
 pub type StackBoxFuture<'a> = StackBox<'a, dyn TaskFuture + 'a>;
+
+/// A type alias for a `StackBox` containing a task that hands its output of type `O` to the
+/// executor.
+///
+/// # Type Parameters
+/// - `'a`: The lifetime of the reference to the stored task.
+/// - `O`: The output type of the task's future.
+pub type StackBoxOutput<'a, O> = StackBox<'a, dyn TaskOutput<O> + 'a>;
@@ -0,0 +1,68 @@
+//! # Scheduler snapshot implementation
+//!
+//! This module provides [`SchedulerSnapshot`], a copy of the observable scheduling state of an
+//! [`Executor`](crate::executor::Executor): which slots are occupied, by which tasks, how often
+//! they have been polled and where the next task will be spawned. The futures themselves cannot
+//! be captured, but the metadata is plain data, so snapshots can be logged or compared with each
+//! other to see how a run has progressed. [`Stats`] sums the state up into a few counters.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! let task = pin!(Task::new("task", async {}));
+//! let mut handle = Handle::new();
+//! let mut executor = Executor::<2>::new();
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//!
+//! let before = executor.snapshot();
+//! executor.run();
+//! let after = executor.snapshot();
+//!
+//! assert!(before.slots[0].occupied && !after.slots[0].occupied);
+//! assert_eq!(after.slots[0].polls, 1);
+//! assert_eq!(after.slots[0].name, Some("task"));
+//! ```
+
+/// The scheduling state of a single executor slot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotSnapshot<'a> {
+    /// Whether a task occupies the slot.
+    pub occupied: bool,
+    /// The name of the task spawned into the slot last, kept after the task completes.
+    pub name: Option<&'a str>,
+    /// The number of times the task spawned into the slot last has been polled.
+    pub polls: u64,
+    /// The tick at which the task was last polled, see
+    /// [`Executor::last_polled`](crate::executor::Executor::last_polled).
+    pub last_polled: Option<u64>,
+}
+
+/// The scheduling state of an executor, taken by
+/// [`Executor::snapshot`](crate::executor::Executor::snapshot).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchedulerSnapshot<'a, const TASK_ARRAY_SIZE: usize> {
+    /// The state of each executor slot.
+    pub slots: [SlotSnapshot<'a>; TASK_ARRAY_SIZE],
+    /// The index of the slot the next task will be spawned into, `TASK_ARRAY_SIZE` if all slots
+    /// are occupied.
+    pub next_slot: usize,
+    /// The number of scheduling passes made, see
+    /// [`Executor::pass_count`](crate::executor::Executor::pass_count).
+    pub pass_count: u64,
+}
+
+/// The counters of an executor summed up over all its tasks, taken by
+/// [`Executor::stats`](crate::executor::Executor::stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of scheduling passes made, see
+    /// [`Executor::pass_count`](crate::executor::Executor::pass_count).
+    pub passes: u64,
+    /// The number of task polls made.
+    pub polls: u64,
+    /// The number of tasks completed.
+    pub completions: u64,
+}
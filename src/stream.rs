@@ -0,0 +1,88 @@
+//! # `Stream` implementation
+//!
+//! A stream is the asynchronous counterpart of an iterator: it produces a sequence of values,
+//! and the next value may not be available yet. This module provides the [`Stream`] trait, a
+//! future to await the next item with and a stream made from an iterator.
+//!
+//! Combinators over streams live in the [`helpers`](crate::helpers) module.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! use miniloop::stream::{iter, next};
+//!
+//! let mut executor = Executor::<1>::new();
+//! let sum = executor.block_on(async {
+//!     let mut stream = iter([1, 2, 3]);
+//!     let mut sum = 0;
+//!
+//!     while let Some(item) = next(&mut stream).await {
+//!         sum += item;
+//!     }
+//!
+//!     sum
+//! });
+//! assert_eq!(sum, 6);
+//! ```
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A sequence of values produced asynchronously.
+pub trait Stream {
+    /// The type of the values the stream produces.
+    type Item;
+
+    /// Attempts to pull out the next value of the stream.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Some(item))` if the next value is available.
+    /// * `Poll::Ready(None)` if the stream has ended. It should not be polled anymore.
+    /// * `Poll::Pending` if the next value is not available yet. The stream wakes the task once
+    ///   it may be able to make progress.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+/// A future resolving to the next value of a stream, created by [`next`].
+pub struct Next<'a, S: ?Sized> {
+    stream: &'a mut S,
+}
+
+impl<S: Stream + Unpin + ?Sized> Future for Next<'_, S> {
+    type Output = Option<S::Item>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().stream).poll_next(cx)
+    }
+}
+
+/// Waits for the next value of the `stream`.
+///
+/// The returned future resolves to `None` once the stream has ended.
+pub fn next<S: Stream + Unpin + ?Sized>(stream: &mut S) -> Next<'_, S> {
+    Next { stream }
+}
+
+/// A stream producing the items of an iterator, created by [`iter`].
+pub struct Iter<I> {
+    iter: I,
+}
+
+impl<I> Unpin for Iter<I> {}
+
+impl<I: Iterator> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().iter.next())
+    }
+}
+
+/// Converts an iterator into a stream whose values are always immediately available.
+pub fn iter<I: IntoIterator>(iter: I) -> Iter<I::IntoIter> {
+    Iter {
+        iter: iter.into_iter(),
+    }
+}
@@ -0,0 +1,462 @@
+//! # Synchronization primitives
+//!
+//! This module provides primitives that let tasks running on the same executor coordinate with
+//! each other. The executor is single-threaded and cooperative, so the primitives rely on
+//! [`Cell`] for their shared state instead of atomics: a task can only observe or modify the
+//! state while it is being polled, and no other task is polled at the same time.
+//!
+//! - [`Notify`] - edge-triggered notification of a single waiting task
+//! - [`Semaphore`] - a limited number of permits shared between tasks
+//! - [`Mutex`] - exclusive access to a value shared between tasks across `.await` points
+//! - [`AbortFlag`] - a kill switch stopping the executor and everything it runs
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! use miniloop::sync::Notify;
+//! let notify = Notify::new();
+//! let mut executor = Executor::<2>::new();
+//! let waiter = pin!(Task::new("waiter", async {
+//!     notify.notified().await;
+//!     println!("Notified!");
+//! }));
+//! let mut waiter_handle = Handle::new();
+//! let notifier = pin!(Task::new("notifier", async {
+//!     notify.notify_one();
+//! }));
+//! let mut notifier_handle = Handle::new();
+//! executor.spawn(waiter, &mut waiter_handle).expect("Failed to spawn task");
+//! executor.spawn(notifier, &mut notifier_handle).expect("Failed to spawn task");
+//! executor.run();
+//! ```
+use core::cell::{Cell, UnsafeCell};
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+/// The number of waiting tasks a [`Notify`], [`Semaphore`] or [`Mutex`] keeps the wakers of.
+pub(crate) const WAITERS: usize = 4;
+
+/// The wakers of up to `N` tasks waiting for a shared resource.
+///
+/// Every waiter keeps its own entry until it is woken, so waiters do not displace each other and
+/// stay pending without being polled. Only once all entries are taken does a new waiter displace
+/// the waker in the first entry, which is woken so that its task registers again instead of never
+/// being woken at all. More than `N` waiters thus keep polling each other, but no task is lost.
+pub(crate) struct WaitList<const N: usize> {
+    /// The registered wakers, `None` for a free entry.
+    wakers: [Cell<Option<Waker>>; N],
+}
+
+impl<const N: usize> Default for WaitList<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> WaitList<N> {
+    /// Creates an empty list.
+    pub(crate) const fn new() -> Self {
+        const { assert!(N > 0, "a `WaitList` needs at least one entry") };
+
+        Self {
+            wakers: [const { Cell::new(None) }; N],
+        }
+    }
+
+    /// Registers `waker` to be woken by the next [`WaitList::wake_all`].
+    ///
+    /// A waker of a task that is already registered replaces the stored one. The wakers are
+    /// compared after cloning, as the waker the executor polls a task with differs from the
+    /// clones it hands out, which all refer to the wake flag of the task.
+    pub(crate) fn register(&self, waker: &Waker) {
+        let waker = waker.clone();
+        let mut free = None;
+
+        for (index, entry) in self.wakers.iter().enumerate() {
+            match entry.take() {
+                Some(stored) if stored.will_wake(&waker) => {
+                    entry.set(Some(waker));
+                    return;
+                }
+                Some(stored) => entry.set(Some(stored)),
+                None => {
+                    free.get_or_insert(index);
+                }
+            }
+        }
+
+        let displaced = self.wakers[free.unwrap_or(0)].replace(Some(waker));
+
+        if let Some(displaced) = displaced {
+            displaced.wake();
+        }
+    }
+
+    /// Wakes and removes a single registered waker, if any.
+    pub(crate) fn wake_one(&self) {
+        if let Some(waker) = self.wakers.iter().find_map(Cell::take) {
+            waker.wake();
+        }
+    }
+
+    /// Wakes and removes all registered wakers.
+    ///
+    /// All waiters are woken, as a woken waiter may have been dropped in the meantime. The one
+    /// polled first takes the resource, the rest find it taken and register again.
+    pub(crate) fn wake_all(&self) {
+        for entry in &self.wakers {
+            if let Some(waker) = entry.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// An edge-triggered notification shared between tasks.
+///
+/// Unlike a level-triggered flag that stays set once raised, a notification is consumed by the
+/// waiter that observes it. `Notify` holds at most a single permit:
+///
+/// - [`Notify::notify_one`] stores the permit and wakes one waiting task, if any.
+/// - [`Notify::notified`] resolves as soon as it finds a permit, consuming it.
+///
+/// Notifying while nobody waits is therefore not lost: the next `notified().await` completes
+/// immediately. Notifying several times before anybody waits still leaves a single permit, so
+/// only one waiter gets through.
+///
+/// If several tasks wait at the same time, a notification wakes one of them and leaves the rest
+/// waiting without being polled. The permit goes to the waiter polled first after the
+/// notification, which is usually the woken one.
+#[derive(Default)]
+pub struct Notify {
+    /// Set when a notification has not been consumed yet.
+    permit: Cell<bool>,
+    /// The wakers of the waiting tasks.
+    waiters: WaitList<WAITERS>,
+}
+
+impl Notify {
+    /// Creates a new `Notify` without a stored permit.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            permit: Cell::new(false),
+            waiters: WaitList::new(),
+        }
+    }
+
+    /// Notifies a single waiting task.
+    ///
+    /// If no task is waiting, the permit is stored and consumed by the next call to
+    /// [`Notify::notified`]. Permits do not accumulate.
+    pub fn notify_one(&self) {
+        self.permit.set(true);
+        self.waiters.wake_one();
+    }
+
+    /// Waits for a notification.
+    ///
+    /// The returned future resolves once a permit is available and consumes it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::sync::Notify;
+    /// let notify = Notify::new();
+    /// notify.notify_one();
+    /// // the permit is stored, so waiting completes right away
+    /// Executor::<1>::new().block_on(notify.notified());
+    /// ```
+    #[must_use]
+    pub fn notified(&self) -> Notified<'_> {
+        Notified { notify: self }
+    }
+}
+
+/// A future returned by [`Notify::notified`].
+pub struct Notified<'a> {
+    notify: &'a Notify,
+}
+
+impl Drop for Notified<'_> {
+    /// Passes the notification on to another waiter if the waiter woken for it is dropped before
+    /// consuming the permit.
+    fn drop(&mut self) {
+        if self.notify.permit.get() {
+            self.notify.waiters.wake_one();
+        }
+    }
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.notify.permit.replace(false) {
+            return Poll::Ready(());
+        }
+
+        self.notify.waiters.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// A counting semaphore limiting the number of tasks inside a section at the same time.
+///
+/// The semaphore holds a number of permits. [`Semaphore::acquire`] waits until a permit is
+/// available and takes it, handing out a [`SemaphorePermit`] that returns the permit to the
+/// semaphore when dropped. Sharing `&Semaphore` between tasks bounds how many of them use a limited
+/// resource, e.g. connections, at once.
+///
+/// Waiters are not queued: when a permit is returned, the waiter that is polled first takes it.
+/// With the executor polling its slots in order, a task in a lower slot may therefore overtake a
+/// task that has been waiting for longer.
+pub struct Semaphore {
+    /// The number of available permits.
+    permits: Cell<usize>,
+    /// The wakers of the waiting tasks.
+    waiters: WaitList<WAITERS>,
+}
+
+impl Semaphore {
+    /// Creates a new `Semaphore` with the given number of available `permits`.
+    #[must_use]
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: Cell::new(permits),
+            waiters: WaitList::new(),
+        }
+    }
+
+    /// Returns the number of permits that are currently available.
+    #[must_use]
+    pub fn available_permits(&self) -> usize {
+        self.permits.get()
+    }
+
+    /// Waits for a permit.
+    ///
+    /// The returned future resolves once a permit is available and takes it. The permit is held
+    /// until the returned [`SemaphorePermit`] is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::sync::Semaphore;
+    /// let semaphore = Semaphore::new(1);
+    /// Executor::<1>::new().block_on(async {
+    ///     let permit = semaphore.acquire().await;
+    ///     assert_eq!(semaphore.available_permits(), 0);
+    ///     drop(permit);
+    ///     assert_eq!(semaphore.available_permits(), 1);
+    /// });
+    /// ```
+    #[must_use]
+    pub fn acquire(&self) -> Acquire<'_> {
+        Acquire { semaphore: self }
+    }
+}
+
+/// A future returned by [`Semaphore::acquire`].
+pub struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = SemaphorePermit<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        let permits = semaphore.permits.get();
+
+        if permits > 0 {
+            semaphore.permits.set(permits - 1);
+            return Poll::Ready(SemaphorePermit { semaphore });
+        }
+
+        semaphore.waiters.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// A permit taken from a [`Semaphore`], returned to it when dropped.
+#[must_use = "the permit is returned to the semaphore right away if it is not kept"]
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        let semaphore = self.semaphore;
+        semaphore.permits.set(semaphore.permits.get() + 1);
+
+        semaphore.waiters.wake_all();
+    }
+}
+
+/// A mutual exclusion lock protecting a value shared between tasks.
+///
+/// A task holding the [`MutexGuard`] returned by [`Mutex::lock`] has exclusive access to the
+/// value, even across `.await` points where other tasks are polled. Tasks trying to lock the mutex
+/// in the meantime stay pending until the guard is dropped, which unlocks the mutex and wakes the
+/// waiting tasks.
+///
+/// As with [`Semaphore`], waiters are not queued: when the mutex is unlocked, the waiter that is
+/// polled first takes the lock.
+#[derive(Default)]
+pub struct Mutex<T> {
+    /// Set while a guard is alive.
+    locked: Cell<bool>,
+    /// The wakers of the waiting tasks.
+    waiters: WaitList<WAITERS>,
+    /// The protected value.
+    value: UnsafeCell<T>,
+}
+
+impl<T> Mutex<T> {
+    /// Creates a new unlocked `Mutex` protecting `value`.
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: Cell::new(false),
+            waiters: WaitList::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Returns `true` if the mutex is locked.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.locked.get()
+    }
+
+    /// Waits for the lock.
+    ///
+    /// The returned future resolves once the mutex is unlocked and locks it. The mutex stays locked
+    /// until the returned [`MutexGuard`] is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use miniloop::executor::Executor;
+    /// # use miniloop::sync::Mutex;
+    /// let mutex = Mutex::new(0);
+    /// Executor::<1>::new().block_on(async {
+    ///     let mut guard = mutex.lock().await;
+    ///     *guard += 1;
+    ///     assert!(mutex.is_locked());
+    ///     drop(guard);
+    ///     assert!(!mutex.is_locked());
+    /// });
+    /// assert_eq!(mutex.into_inner(), 1);
+    /// ```
+    #[must_use]
+    pub fn lock(&self) -> Lock<'_, T> {
+        Lock { mutex: self }
+    }
+
+    /// Consumes the mutex and returns the protected value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+/// A future returned by [`Mutex::lock`].
+pub struct Lock<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<'a, T> Future for Lock<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mutex = self.mutex;
+
+        if !mutex.locked.replace(true) {
+            return Poll::Ready(MutexGuard { mutex });
+        }
+
+        mutex.waiters.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// Exclusive access to the value of a [`Mutex`], unlocking it when dropped.
+#[must_use = "the mutex is unlocked right away if the guard is not kept"]
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the mutex stays locked while the guard is alive, so no other guard exists and
+        // the value is only accessed through this one.
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the mutex stays locked while the guard is alive, so no other guard exists and
+        // the value is only accessed through this one.
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let mutex = self.mutex;
+        mutex.locked.set(false);
+
+        mutex.waiters.wake_all();
+    }
+}
+
+/// A kill switch shared between the executor and its tasks.
+///
+/// Once the flag is set with [`AbortFlag::abort`], the executor it is installed in with
+/// [`Executor::set_abort_flag`](crate::executor::Executor::set_abort_flag) cancels all its tasks at
+/// the start of the next scheduling pass and [`Executor::run`](crate::executor::Executor::run)
+/// returns. Long-running tasks holding a reference to the flag can check it with
+/// [`AbortFlag::is_aborted`] to stop on their own before that. The flag stays set until it is
+/// [reset](AbortFlag::reset).
+#[derive(Default)]
+pub struct AbortFlag {
+    /// Set once an abort has been requested.
+    aborted: Cell<bool>,
+}
+
+impl AbortFlag {
+    /// Creates a new `AbortFlag` that is not set.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            aborted: Cell::new(false),
+        }
+    }
+
+    /// Sets the flag, requesting everything that checks it to stop.
+    pub fn abort(&self) {
+        self.aborted.set(true);
+    }
+
+    /// Returns `true` if the flag is set.
+    #[must_use]
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    /// Clears the flag, so the executor can run new tasks again.
+    pub fn reset(&self) {
+        self.aborted.set(false);
+    }
+}
@@ -11,39 +11,351 @@
 //! run on the stack. The major components are:
 //!
 //! 1. [`Task`]: Represents a named asynchronous operation.
+//! 2. [`ErasedTask`]: A task with its future type erased, so tasks with different futures can be
+//!    stored together.
 //!
 //! ## Examples
 //!
 //! ### Creating a `Task`
 //!
 //! ```rust
-//! use miniloop::task::Task;
+//! use miniloop::task::{Handle, Task};
 //!
 //! let task_name = "example_task";
 //! // Example future, replace `()` with actual future logic
 //! let task = Task::new(task_name, async { () });
 //! ```
 
-use core::future::Future;
+use crate::sbox::{StackBox, StackBoxFuture};
+use crate::sync::{WAITERS, WaitList};
+use crate::time::{TimeSource, Timeout, timeout};
+
+use core::any::Any;
+use core::cell::Cell;
+use core::future::{Future, IntoFuture};
 use core::pin::Pin;
-use core::task::{Context, Poll, ready};
+use core::task::{Context, Poll, Waker, ready};
+#[cfg(feature = "std")]
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
 
+/// A `Handle` receives the output of a task.
+///
+/// A handle is not bound to any task when it is created. [`Executor::spawn`] links the handle
+/// with the task it is spawned with, and the task stores its output in the handle once it
+/// completes.
+///
+/// [`Executor::spawn`]: crate::executor::Executor::spawn
 pub struct Handle<T> {
+    /// The output of the linked task, `None` until the task completes.
     pub value: Option<T>,
+    /// A callback invoked once the output is stored.
+    on_set: Option<fn(&T)>,
 }
 
 impl<T> Default for Handle<T> {
     fn default() -> Self {
-        Self { value: None }
+        Self::new()
+    }
+}
+
+impl<T> Handle<T> {
+    /// Creates an empty handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 42 }));
+    /// let mut handle = Handle::new();
+    /// assert!(handle.value.is_none());
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// # assert_eq!(handle.value, Some(42));
+    /// ```
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            value: None,
+            on_set: None,
+        }
+    }
+
+    /// Sets the callback to be invoked when the linked task stores its output in the handle.
+    ///
+    /// The callback runs right after the output is stored, while the executor is still polling
+    /// the task, so the owner of the handle can react to the completion without checking the
+    /// handle. The callback must be set before the handle is linked with a task by
+    /// [`Executor::spawn`](crate::executor::Executor::spawn).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 42 }));
+    /// let mut handle = Handle::new();
+    /// handle.on_set(|value| println!("stored {value}"));
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// ```
+    pub fn on_set(&mut self, cb: fn(&T)) {
+        self.on_set = Some(cb);
+    }
+
+    /// Returns the output of the linked task or `default` if the task has not completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 42 }));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.unwrap_or(0), 42);
+    ///
+    /// let incomplete = Handle::<i32>::new();
+    /// assert_eq!(incomplete.unwrap_or(0), 0);
+    /// ```
+    pub fn unwrap_or(self, default: T) -> T {
+        self.value.unwrap_or(default)
+    }
+
+    /// Returns the output of the linked task or computes it with `f` if the task has not
+    /// completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 42 }));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.unwrap_or_else(|| 0), 42);
+    ///
+    /// let incomplete = Handle::<i32>::new();
+    /// assert_eq!(incomplete.unwrap_or_else(|| -1), -1);
+    /// ```
+    pub fn unwrap_or_else(self, f: impl FnOnce() -> T) -> T {
+        self.value.unwrap_or_else(f)
+    }
+
+    /// Returns `true` if the linked task has completed and its output is stored in the handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 42 }));
+    /// let mut handle = Handle::new();
+    /// assert!(!handle.is_ready());
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert!(handle.is_ready());
+    /// ```
+    #[must_use]
+    pub const fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Takes the output of the linked task out of the handle, leaving `None` in its place.
+    ///
+    /// Unlike [`Handle::unwrap_or`], the handle is kept, so it can be checked again in a
+    /// super-loop after the output has been consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { [1u8, 2, 3] }));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.take(), Some([1, 2, 3]));
+    /// assert_eq!(handle.take(), None);
+    /// ```
+    pub const fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+}
+
+/// A handle that receives the output of a task and can be read through shared references.
+///
+/// A [`Handle`] is borrowed mutably by the task it is linked with, so it has a single owner and
+/// cannot be read until the executor is done with the task. A `SharedHandle` is linked by a shared
+/// reference with [`Task::erase_shared`] instead, so any number of observers can hold it while the
+/// task runs and read the output as soon as it is stored.
+///
+/// The output is stored with interior mutability through a [`Cell`]. This is sound because a
+/// `Cell` is not [`Sync`]: the handle, the task writing to it and all its readers live on the
+/// thread that runs the executor, and the task only writes to it while it is polled, never at the
+/// same time as a reader accesses it. The `Cell` never hands out references to the output, only
+/// copies or the output itself.
+///
+/// # Examples
+///
+/// ```
+/// use core::pin::pin;
+/// use miniloop::executor::Executor;
+/// use miniloop::task::{SharedHandle, Task};
+///
+/// let handle = SharedHandle::new();
+/// let observer = &handle;
+/// let task = pin!(Task::new("example_task", async { 42 }));
+/// let mut executor = Executor::<1>::new();
+/// executor
+///     .spawn_erased(task.erase_shared(&handle))
+///     .expect("Failed to spawn task");
+/// assert_eq!(observer.get(), None);
+/// executor.run();
+/// assert_eq!(observer.get(), Some(42));
+/// ```
+///
+/// Other tasks can wait for the output by awaiting a reference to the handle. Each awaiter
+/// receives a clone of the output, which stays in the handle for the other observers:
+///
+/// ```
+/// use core::pin::pin;
+/// use miniloop::executor::Executor;
+/// use miniloop::task::{Handle, SharedHandle, Task};
+///
+/// let handle = SharedHandle::new();
+/// let output = &handle;
+/// let producer = pin!(Task::new("producer", async { 42 }));
+/// let consumer = pin!(Task::new("consumer", async { output.await + 1 }));
+/// let mut consumer_handle = Handle::new();
+/// let mut executor = Executor::<2>::new();
+/// executor
+///     .spawn(consumer, &mut consumer_handle)
+///     .expect("Failed to spawn task");
+/// executor
+///     .spawn_erased(producer.erase_shared(&handle))
+///     .expect("Failed to spawn task");
+/// executor.run();
+/// assert_eq!(consumer_handle.value, Some(43));
+/// ```
+pub struct SharedHandle<T> {
+    /// The output of the linked task, `None` until the task completes.
+    value: Cell<Option<T>>,
+    /// The wakers of the tasks awaiting the output.
+    waiters: WaitList<WAITERS>,
+}
+
+impl<T> Default for SharedHandle<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+impl<T> SharedHandle<T> {
+    /// Creates an empty shared handle.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            value: Cell::new(None),
+            waiters: WaitList::new(),
+        }
+    }
+
+    /// Returns a copy of the output of the linked task, `None` until the task completes.
+    #[must_use]
+    pub fn get(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        self.value.get()
+    }
+
+    /// Takes the output of the linked task out of the handle, leaving `None` in its place.
+    #[must_use]
+    pub fn take(&self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Stores the output of the linked task and wakes the tasks awaiting it, if any.
+    fn set(&self, value: T) {
+        self.value.set(Some(value));
+        self.waiters.wake_all();
+    }
+}
+
+impl<'a, T: Clone> IntoFuture for &'a SharedHandle<T> {
+    type Output = T;
+    type IntoFuture = WaitOutput<'a, T>;
+
+    /// Converts the handle reference into a future resolving to a clone of the output of the
+    /// linked task.
+    ///
+    /// The output stays in the handle, so any number of awaiters and observers receive it. Use
+    /// [`SharedHandle::take`] to move it out instead.
+    fn into_future(self) -> Self::IntoFuture {
+        WaitOutput { handle: self }
+    }
+}
+
+/// A future waiting for the output of the task linked with a [`SharedHandle`], created by
+/// awaiting a reference to the handle.
+pub struct WaitOutput<'a, T> {
+    handle: &'a SharedHandle<T>,
+}
+
+impl<T: Clone> Future for WaitOutput<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let handle = self.handle;
+
+        // the `Cell` hands out no references, so the output is taken out to clone it
+        if let Some(value) = handle.value.take() {
+            handle.value.set(Some(value.clone()));
+            return Poll::Ready(value);
+        }
+
+        handle.waiters.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// The handle a task stores its output in.
+enum HandleLink<'a, T> {
+    /// A handle borrowed exclusively by the task.
+    Exclusive(&'a mut Handle<T>),
+    /// A handle shared with observers.
+    Shared(&'a SharedHandle<T>),
+    /// A plain variable of the caller.
+    Output(&'a mut Option<T>),
+}
+
 /// A `Task` represents a named asynchronous operation.
 ///
 /// # Examples
 ///
 /// ```
-/// use miniloop::task::Task;
+/// use miniloop::task::{Handle, Task};
 ///
 /// let task_name = "example_task";
 /// // Example future, replace `()` with actual future logic
@@ -52,17 +364,24 @@ impl<T> Default for Handle<T> {
 pub struct Task<'a, F: Future> {
     /// A string that holds the name of the task.
     pub name: Option<&'a str>,
-    /// A future representing the asynchronous operation associated with the task.
-    pub future: F,
-    handle: Option<&'a mut Handle<F::Output>>,
+    /// A future representing the asynchronous operation associated with the task, `None` once the
+    /// task has been cancelled and the future dropped.
+    future: Option<F>,
+    handle: Option<HandleLink<'a, F::Output>>,
+    /// Data the task can read while it is polled by the executor.
+    context: Option<&'static dyn Any>,
+    /// A label categorizing the task in the events of the executor.
+    label: Option<&'static str>,
 }
 
 impl<'a, F: Future> Task<'a, F> {
     const fn new_impl(name: Option<&'a str>, future: F) -> Self {
         Self {
             name,
-            future,
+            future: Some(future),
             handle: None,
+            context: None,
+            label: None,
         }
     }
     /// Creates a new `Task` with the specified name and future.
@@ -80,7 +399,7 @@ impl<'a, F: Future> Task<'a, F> {
     /// # Examples
     ///
     /// ```
-    /// use miniloop::task::Task;
+    /// use miniloop::task::{Handle, Task};
     /// let task = Task::new("example_task", async {});
     /// ```
     pub const fn new(name: &'a str, future: F) -> Self {
@@ -102,31 +421,563 @@ impl<'a, F: Future> Task<'a, F> {
     /// # Example
     ///
     /// ```rust
-    /// # use miniloop::task::Task;
+    /// # use miniloop::task::{Handle, Task};
     /// let instance = Task::new_nameless(async {});
     /// ```
     pub const fn new_nameless(future: F) -> Self {
         Self::new_impl(None, future)
     }
 
-    /// Creates a default handle for the task's output.
+    /// Returns a reference to the task's future.
     ///
-    /// # Returns
+    /// Useful to inspect the state of the future in tests. Once the task is spawned, the executor
+    /// holds it pinned and the task cannot be accessed anymore, so the accessor is only usable
+    /// before [`Executor::spawn`](crate::executor::Executor::spawn) or when the task is polled by
+    /// other means.
     ///
-    /// A new instance of [`Handle`] with its value set to `None`.
+    /// # Examples
+    ///
+    /// ```
+    /// use core::future::{ready, Ready};
+    /// use miniloop::task::Task;
+    ///
+    /// let task = Task::new("example_task", ready(42));
+    /// let future: &Ready<i32> = task.future();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the task has been cancelled by the executor, which drops the future.
+    #[must_use]
+    pub const fn future(&self) -> &F {
+        match &self.future {
+            Some(future) => future,
+            None => panic!("the future of a cancelled task has been dropped"),
+        }
+    }
+
+    /// Returns a mutable reference to the task's future.
+    ///
+    /// As with [`Task::future`], the accessor is only usable before the task is spawned. The future
+    /// has not been pinned at that point, so it is safe to modify it or even replace it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the task has been cancelled by the executor, which drops the future.
+    pub const fn future_mut(&mut self) -> &mut F {
+        match &mut self.future {
+            Some(future) => future,
+            None => panic!("the future of a cancelled task has been dropped"),
+        }
+    }
+
+    /// Polls the task once with the given `waker` instead of the executor's one.
+    ///
+    /// The waker of the executor only marks the task to be polled again, so a test driving the
+    /// task through the executor cannot observe whether a future registers the waker and wakes it
+    /// when it can make progress. Polling with a custom waker, e.g. one counting its wakes, lets
+    /// unit tests check exactly that. As with
+    /// [`Executor::spawn`](crate::executor::Executor::spawn), the output is stored in the linked
+    /// handle once the future completes.
+    ///
+    /// The task has to be pinned first, e.g. with [`pin!`](core::pin::pin), so it cannot be
+    /// spawned afterwards.
     ///
     /// # Examples
     ///
     /// ```
-    /// use miniloop::task::{Task, Handle};
+    /// use core::pin::pin;
+    /// use core::task::{Poll, Waker};
+    /// use miniloop::task::Task;
+    ///
+    /// let mut task = pin!(Task::new("example_task", async {}));
+    /// assert_eq!(task.as_mut().poll_with_waker(Waker::noop()), Poll::Ready(()));
+    /// ```
+    pub fn poll_with_waker(self: Pin<&mut Self>, waker: &Waker) -> Poll<()> {
+        self.poll(&mut Context::from_waker(waker))
+    }
+
+    /// Attaches a context to the task.
+    ///
+    /// The context is available to the task's future while the executor polls it, through
+    /// [`current_context`](crate::helpers::current_context). This lets tasks share configuration
+    /// without passing it to every function explicitly.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - A reference to the value the task can access during polling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::helpers::current_context;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// static LIMIT: u32 = 10;
+    ///
+    /// let task = pin!(Task::new("example_task", async {
+    ///     current_context::<u32>().await.copied()
+    /// })
+    /// .with_context(&LIMIT));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(Some(10)));
+    /// ```
+    #[must_use]
+    pub fn with_context(mut self, context: &'static dyn Any) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Attaches a label to the task that the executor includes in the events of the task.
+    ///
+    /// Unlike the name, which identifies a single task, a label categorizes tasks, e.g. `"io"` or
+    /// `"compute"`, so that traces can be filtered by the kind of work. The label is included in
+    /// each [`Event`](crate::event_log::Event) recorded for the task and can be looked up by the
+    /// poll hooks with [`Executor::label`](crate::executor::Executor::label).
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label of the task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("read_sensor", async {}).instrument("io"));
+    /// let mut handle = Handle::new();
+    /// let mut executor = Executor::<1>::new();
+    /// let id = executor.spawn(task, &mut handle).expect("Failed to spawn task");
+    /// assert_eq!(executor.label(id), Some("io"));
+    /// ```
+    #[must_use]
+    pub fn instrument(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Chains a follow-up future to the task.
+    ///
+    /// The returned task runs the task's future first and then feeds its output into `f` to build
+    /// the follow-up future, which runs next. The output of the follow-up future is stored in the
+    /// handle of the returned task. The name and the context of the task are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure building the follow-up future from the output of the task's future.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 21u32 }).chain(|v| async move { v * 2 }));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(42));
+    /// ```
+    #[must_use]
+    pub fn chain<G, C>(self, f: C) -> Task<'a, Chain<F, C, G>>
+    where
+        C: FnOnce(F::Output) -> G,
+        G: Future,
+    {
+        self.map_future(|future| Chain {
+            state: ChainState::First {
+                future,
+                next: Some(f),
+            },
+        })
+    }
+
+    /// Chains a fallible follow-up future to a task producing a [`Result`].
+    ///
+    /// The returned task runs the task's future first. If it succeeds, its value is fed into `f`
+    /// to build the follow-up future, which runs next and whose output is stored in the handle.
+    /// If it fails, the follow-up future is never built and the error is stored in the handle
+    /// right away, just like `?` propagates errors in synchronous code. The name and the context of
+    /// the task are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure building the follow-up future from the value of the task's future.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { Ok::<u32, &str>(21) })
+    ///     .and_then(|v| async move { v.checked_mul(2).ok_or("overflow") }));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(Ok(42)));
+    /// ```
+    #[must_use]
+    pub fn and_then<T, U, E, G, C>(self, f: C) -> Task<'a, AndThen<F, C, G>>
+    where
+        F: Future<Output = Result<T, E>>,
+        C: FnOnce(T) -> G,
+        G: Future<Output = Result<U, E>>,
+    {
+        self.map_future(|future| AndThen {
+            state: ChainState::First {
+                future,
+                next: Some(f),
+            },
+        })
+    }
+
+    /// Limits the time the task is allowed to run.
+    ///
+    /// The returned task stores `Ok(output)` in its handle if the task's future completes within
+    /// `ticks` ticks of the time `source`, counted from the first poll. Otherwise the task completes
+    /// without waiting for the future any longer and the handle receives `Err(TimedOut)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The time source the ticks are counted with.
+    /// * `ticks` - The number of ticks the task is allowed to run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::helpers::yield_me;
+    /// use miniloop::task::{Handle, Task};
+    /// use miniloop::time::TimedOut;
+    ///
+    /// let clock = || 0;
+    /// let task = pin!(Task::new("example_task", async {
+    ///     loop {
+    ///         yield_me().await;
+    ///     }
+    /// })
+    /// .with_timeout(&clock, 0));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(Err(TimedOut)));
+    /// ```
+    #[must_use]
+    pub fn with_timeout<S>(self, source: &'a S, ticks: u64) -> Task<'a, Timeout<'a, S, F>>
+    where
+        S: TimeSource + ?Sized,
+    {
+        self.map_future(|future| timeout(source, ticks, future))
+    }
+
+    /// Attaches a hook observing the task's output.
+    ///
+    /// The executor only sees tasks with their output type erased, so its callbacks cannot
+    /// inspect the output. The hook runs with the typed output right when the task's future
+    /// completes, before the output is stored in the handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - A function called with a reference to the output of the task's future.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { 42 })
+    ///     .on_complete(|value| println!("completed with {value}")));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(42));
+    /// ```
+    #[must_use]
+    pub fn on_complete(self, hook: fn(&F::Output)) -> Task<'a, OnComplete<F>> {
+        self.map_future(|future| OnComplete { future, hook })
+    }
+
+    /// Transforms the error of a task producing a [`Result`].
+    ///
+    /// The returned task stores `Ok(value)` in its handle unchanged and `Err(f(error))` in place
+    /// of `Err(error)`. This helps to unify the error types of a set of tasks. The name and the
+    /// context of the task are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A function converting the error of the task's future.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// let task = pin!(Task::new("example_task", async { Err::<u32, ()>(()) })
+    ///     .map_err(|()| "failed"));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(Err("failed")));
+    /// ```
+    #[must_use]
+    pub fn map_err<T, E, E2>(self, f: fn(E) -> E2) -> Task<'a, MapErr<F, E, E2>>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        self.map_future(|future| MapErr { future, f })
+    }
+
+    /// Restarts the task from scratch when it panics, up to `max_restarts` times.
+    ///
+    /// Like a supervisor in Erlang, the returned task does not let a crash take it down for
+    /// good: each poll runs inside [`catch_unwind`], and when the future panics it is dropped and
+    /// replaced with the future of a fresh task built by `factory`. The restarted task stays in
+    /// the same executor slot and stores its output in the same handle; the name, the context and
+    /// the handle of the task built by `factory` are ignored. Once the restarts are used up, the
+    /// next panic is propagated. The name and the context of the task are kept.
+    ///
+    /// The state the future shares with other tasks is not rolled back on a restart, so make sure
+    /// it is still valid after a panic.
+    ///
+    /// Only available with the `std` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `factory` - A function building the task to restart with.
+    /// * `max_restarts` - The maximum number of restarts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use core::sync::atomic::{AtomicU32, Ordering};
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{Handle, Task};
+    ///
+    /// static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+    ///
+    /// async fn connect() -> u32 {
+    ///     let attempt = ATTEMPTS.fetch_add(1, Ordering::Relaxed) + 1;
+    ///     assert!(attempt > 1, "the first attempt fails");
+    ///     attempt
+    /// }
+    ///
+    /// let task = pin!(Task::new("connect", connect())
+    ///     .with_restart(|| Task::new("connect", connect()), 3));
+    /// let mut handle = Handle::new();
+    /// # let mut executor = Executor::<1>::new();
+    /// # let _ = executor.spawn(task, &mut handle);
+    /// # executor.run();
+    /// assert_eq!(handle.value, Some(2));
+    /// ```
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_restart(
+        self,
+        factory: fn() -> Self,
+        max_restarts: u32,
+    ) -> Task<'a, Restart<'a, F>> {
+        self.map_future(|future| Restart {
+            future,
+            factory,
+            restarts_left: max_restarts,
+        })
+    }
+
+    /// Makes the task's future safe to poll after it has completed.
+    ///
+    /// Polling a future again after it has returned `Poll::Ready` is not allowed: many futures
+    /// panic or misbehave. The returned task drops its future on completion and returns
+    /// `Poll::Pending` from every later poll instead, so combinators that might poll a finished
+    /// future again, like a hand-written select, can do so safely. The name and the context of the
+    /// task are kept.
+    ///
+    /// # Examples
     ///
-    /// let task = Task::new("example_task", async { 42 });
-    /// let handle = task.create_handle();
-    /// assert!(handle.value.is_none());
+    /// ```
+    /// use core::future::ready;
+    /// use core::pin::Pin;
+    /// use core::task::{Context, Poll, Waker};
+    /// use miniloop::task::Task;
+    ///
+    /// let mut task = Task::new("example_task", ready(42)).fuse();
+    /// let mut cx = Context::from_waker(Waker::noop());
+    /// let future = Pin::new(task.future_mut());
+    ///
+    /// assert_eq!(future.poll(&mut cx), Poll::Ready(42));
+    /// assert!(task.future().is_terminated());
     /// ```
     #[must_use]
+    pub fn fuse(self) -> Task<'a, Fuse<F>> {
+        self.map_future(|future| Fuse {
+            future: Some(future),
+        })
+    }
+
+    /// Erases the type of the task's future, linking the task with the `handle` that receives its
+    /// output.
+    ///
+    /// Tasks with different futures, and even different output types, have different types, so
+    /// they cannot be stored in the same collection. The erased task borrows the task pinned in
+    /// place and has the same type for every future, so it can be stored in arrays and spawned
+    /// later with [`Executor::spawn_erased`](crate::executor::Executor::spawn_erased).
+    ///
+    /// The task stays borrowed, and therefore pinned, for the whole lifetime `'a`: it cannot be
+    /// moved even after the erased task is dropped. The handle is linked right away, so it stays
+    /// borrowed for `'a` as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - A mutable reference to a [`Handle`] that stores the output of the task's future.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::{ErasedTask, Handle, Task};
+    ///
+    /// let number = pin!(Task::new("number", async { 42 }));
+    /// let mut number_handle = Handle::new();
+    /// let text = pin!(Task::new("text", async { "hello" }));
+    /// let mut text_handle = Handle::new();
+    /// let tasks: [ErasedTask; 2] = [
+    ///     number.erase(&mut number_handle),
+    ///     text.erase(&mut text_handle),
+    /// ];
+    /// # let mut executor = Executor::<2>::new();
+    /// # for task in tasks {
+    /// #     executor.spawn_erased(task).expect("Failed to spawn task");
+    /// # }
+    /// # executor.run();
+    /// ```
+    pub fn erase(mut self: Pin<&'a mut Self>, handle: &'a mut Handle<F::Output>) -> ErasedTask<'a>
+    where
+        F: 'a,
+    {
+        self.as_mut().link_handle(handle);
+
+        ErasedTask {
+            name: self.name,
+            task: StackBox::new(self),
+        }
+    }
+
+    /// Erases the type of the task's future like [`Task::erase`], linking the task with a
+    /// [`SharedHandle`] instead.
+    ///
+    /// The handle is only borrowed through a shared reference, so it can be read by any number of
+    /// observers while the task is running.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - A reference to a [`SharedHandle`] that stores the output of the task's future.
+    pub fn erase_shared(
+        mut self: Pin<&'a mut Self>,
+        handle: &'a SharedHandle<F::Output>,
+    ) -> ErasedTask<'a>
+    where
+        F: 'a,
+    {
+        self.as_mut().link(HandleLink::Shared(handle));
+
+        ErasedTask {
+            name: self.name,
+            task: StackBox::new(self),
+        }
+    }
+
+    /// Erases the type of the task's future like [`Task::erase`], storing the output of the task
+    /// straight in `dest` instead of a [`Handle`].
+    ///
+    /// This is a lighter alternative to a handle when the output goes to an existing variable.
+    /// The task is spawned with [`Executor::spawn_erased`].
+    ///
+    /// # Arguments
+    ///
+    /// * `dest` - A reference to the variable that stores the output of the task's future.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use miniloop::executor::Executor;
+    /// use miniloop::task::Task;
+    ///
+    /// let mut answer = None;
+    /// let task = pin!(Task::new("answer", async { 42 }));
+    /// let mut executor = Executor::<1>::new();
+    /// executor
+    ///     .spawn_erased(task.with_output_into(&mut answer))
+    ///     .expect("Failed to spawn task");
+    /// executor.run();
+    ///
+    /// assert_eq!(answer, Some(42));
+    /// ```
+    ///
+    /// [`Executor::spawn_erased`]: crate::executor::Executor::spawn_erased
+    pub fn with_output_into(
+        mut self: Pin<&'a mut Self>,
+        dest: &'a mut Option<F::Output>,
+    ) -> ErasedTask<'a>
+    where
+        F: 'a,
+    {
+        self.as_mut().link(HandleLink::Output(dest));
+
+        ErasedTask {
+            name: self.name,
+            task: StackBox::new(self),
+        }
+    }
+
+    /// Builds a task around a new future made from the task's future, keeping the name and the
+    /// context of the task.
+    fn map_future<G: Future>(self, f: impl FnOnce(F) -> G) -> Task<'a, G> {
+        Task {
+            name: self.name,
+            future: self.future.map(f),
+            handle: None,
+            context: self.context,
+            label: self.label,
+        }
+    }
+
+    /// Creates a default handle for the task's output.
+    ///
+    /// Despite being called on a task, the handle is not bound to it: any handle of a matching
+    /// output type can be spawned with the task, which makes the method misleading.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of [`Handle`] with its value set to `None`.
+    #[deprecated(
+        since = "0.5.0",
+        note = "handles are linked with tasks by `Executor::spawn`, use `Handle::new` instead"
+    )]
+    #[must_use]
     pub fn create_handle(&self) -> Handle<F::Output> {
-        Handle::default()
+        Handle::new()
     }
 
     /// Links a mutable reference to a [`Handle`] with the task.
@@ -138,21 +989,28 @@ impl<'a, F: Future> Task<'a, F> {
     /// # Examples
     ///
     /// ```
+    /// use core::pin::pin;
     /// use miniloop::executor::Executor;
-    /// use miniloop::task::{Task, Handle};
+    /// use miniloop::task::{Handle, Task};
     ///
-    /// let mut task = Task::new("example_task", async { 42 });
-    /// let mut handle = task.create_handle();
+    /// let task = pin!(Task::new("example_task", async { 42 }));
+    /// let mut handle = Handle::new();
     /// // run executor
     /// # const TASK_ARRAY_SIZE: usize = 1;
     /// # let mut executor = Executor::<TASK_ARRAY_SIZE>::new();
-    /// # let _ = executor.spawn(&mut task, &mut handle);
+    /// # let _ = executor.spawn(task, &mut handle);
     /// # executor.run();
     ///
     /// assert!(handle.value.is_some_and(|v| v == 42));
     /// ```
-    pub(crate) fn link_handle(&mut self, handle: &'a mut Handle<F::Output>) {
-        self.handle = Some(handle);
+    pub(crate) fn link_handle(self: Pin<&mut Self>, handle: &'a mut Handle<F::Output>) {
+        self.link(HandleLink::Exclusive(handle));
+    }
+
+    /// Stores the `link` to the handle receiving the task's output.
+    fn link(self: Pin<&mut Self>, link: HandleLink<'a, F::Output>) {
+        // SAFETY: only the future is pinned structurally, the handle link is never pinned.
+        unsafe { self.get_unchecked_mut() }.handle = Some(link);
     }
 }
 
@@ -164,27 +1022,291 @@ impl<T: Future> Future for Task<'_, T> {
         // SAFETY:
         // 1. `this.future` is never moved out of `Runner` after this line.
         // 2. `this.future` is not used to create a `Pin<&mut T>` anywhere else.
-        let mut future = unsafe { Pin::new_unchecked(&mut this.future) };
-        let res = ready!(future.as_mut().poll(cx));
+        let Some(future) = this.future.as_mut() else {
+            // the task has been cancelled
+            return Poll::Pending;
+        };
+        let res = ready!(unsafe { Pin::new_unchecked(future) }.poll(cx));
 
-        if let Some(handle) = this.handle.as_mut() {
-            handle.value = Some(res);
+        match this.handle.as_mut() {
+            Some(HandleLink::Exclusive(handle)) => {
+                let value = handle.value.insert(res);
+
+                if let Some(cb) = handle.on_set {
+                    cb(value);
+                }
+            }
+            Some(HandleLink::Shared(handle)) => handle.set(res),
+            Some(HandleLink::Output(dest)) => **dest = Some(res),
+            None => {}
         }
 
         Poll::Ready(())
     }
 }
 
+/// A task with its future type erased, created by [`Task::erase`].
+pub struct ErasedTask<'a> {
+    /// The name of the task.
+    name: Option<&'a str>,
+    /// The pinned task.
+    task: StackBoxFuture<'a>,
+}
+
+impl<'a> ErasedTask<'a> {
+    /// Returns the name of the task.
+    #[must_use]
+    pub fn name(&self) -> Option<&'a str> {
+        self.name
+    }
+
+    /// Consumes the erased task returning the pinned task.
+    pub(crate) fn into_inner(self) -> StackBoxFuture<'a> {
+        self.task
+    }
+}
+
+/// The state of a [`Chain`] future.
+enum ChainState<F, C, G> {
+    /// The first future is running, the closure building the second one is waiting for its output.
+    First { future: F, next: Option<C> },
+    /// The second future is running.
+    Second(G),
+}
+
+/// A future that runs two futures one after another, created by [`Task::chain`].
+pub struct Chain<F, C, G> {
+    state: ChainState<F, C, G>,
+}
+
+impl<F, C, G> Future for Chain<F, C, G>
+where
+    F: Future,
+    C: FnOnce(F::Output) -> G,
+    G: Future,
+{
+    type Output = G::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the futures stored in the state are never moved out of it. They are only
+        // dropped in place when the state is replaced.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        loop {
+            match &mut this.state {
+                ChainState::First { future, next } => {
+                    let output = ready!(unsafe { Pin::new_unchecked(future) }.poll(cx));
+                    let next = next
+                        .take()
+                        .expect("`Chain` polled after the first future completed");
+                    this.state = ChainState::Second(next(output));
+                }
+                ChainState::Second(future) => {
+                    return unsafe { Pin::new_unchecked(future) }.poll(cx);
+                }
+            }
+        }
+    }
+}
+
+/// A future that runs a fallible future after another one succeeds, created by
+/// [`Task::and_then`].
+pub struct AndThen<F, C, G> {
+    state: ChainState<F, C, G>,
+}
+
+impl<F, T, U, E, C, G> Future for AndThen<F, C, G>
+where
+    F: Future<Output = Result<T, E>>,
+    C: FnOnce(T) -> G,
+    G: Future<Output = Result<U, E>>,
+{
+    type Output = Result<U, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the futures stored in the state are never moved out of it. They are only
+        // dropped in place when the state is replaced.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        loop {
+            match &mut this.state {
+                ChainState::First { future, next } => {
+                    let value = ready!(unsafe { Pin::new_unchecked(future) }.poll(cx))?;
+                    let next = next
+                        .take()
+                        .expect("`AndThen` polled after the first future completed");
+                    this.state = ChainState::Second(next(value));
+                }
+                ChainState::Second(future) => {
+                    return unsafe { Pin::new_unchecked(future) }.poll(cx);
+                }
+            }
+        }
+    }
+}
+
+/// A future that passes the output of the inner future to a hook, created by
+/// [`Task::on_complete`].
+pub struct OnComplete<F: Future> {
+    future: F,
+    hook: fn(&F::Output),
+}
+
+impl<F: Future> Future for OnComplete<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `OnComplete`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let output = ready!(unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx));
+        (this.hook)(&output);
+
+        Poll::Ready(output)
+    }
+}
+
+/// A future that converts the error of the inner future, created by [`Task::map_err`].
+pub struct MapErr<F, E, E2> {
+    future: F,
+    f: fn(E) -> E2,
+}
+
+impl<F, T, E, E2> Future for MapErr<F, E, E2>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<T, E2>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `MapErr`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let output = ready!(unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx));
+
+        Poll::Ready(output.map_err(this.f))
+    }
+}
+
+/// A future that returns `Poll::Pending` once the inner future has completed, created by
+/// [`Task::fuse`].
+pub struct Fuse<F> {
+    future: Option<F>,
+}
+
+impl<F> Fuse<F> {
+    /// Returns `true` if the inner future has completed and must not be polled anymore.
+    #[must_use]
+    pub fn is_terminated(&self) -> bool {
+        self.future.is_none()
+    }
+}
+
+impl<F: Future> Future for Fuse<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `Fuse`, it is only dropped in place.
+        let this = unsafe { self.get_unchecked_mut() };
+        let Some(future) = this.future.as_mut() else {
+            return Poll::Pending;
+        };
+        let output = ready!(unsafe { Pin::new_unchecked(future) }.poll(cx));
+        this.future = None;
+
+        Poll::Ready(output)
+    }
+}
+
+/// A future restarting the inner future when it panics, created by [`Task::with_restart`].
+#[cfg(feature = "std")]
+pub struct Restart<'a, F: Future> {
+    future: F,
+    factory: fn() -> Task<'a, F>,
+    restarts_left: u32,
+}
+
+#[cfg(feature = "std")]
+impl<F: Future> Future for Restart<'_, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `Restart`. A future that panicked is dropped in
+        // place and its replacement is written into the same place.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        loop {
+            let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+            match catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+                Ok(poll) => return poll,
+                Err(payload) if this.restarts_left == 0 => resume_unwind(payload),
+                Err(_) => {
+                    this.restarts_left -= 1;
+                    this.future = (this.factory)()
+                        .future
+                        .expect("a new task holds its future");
+                }
+            }
+        }
+    }
+}
+
 pub(crate) trait TaskName {
     fn name(&self) -> Option<&str>;
+    fn label(&self) -> Option<&'static str>;
 }
 
 impl<T: Future> TaskName for Task<'_, T> {
     fn name(&self) -> Option<&str> {
         self.name
     }
+
+    fn label(&self) -> Option<&'static str> {
+        self.label
+    }
 }
 
-pub(crate) trait TaskFuture: Future<Output = ()> + TaskName {}
+pub(crate) trait TaskContext {
+    fn context(&self) -> Option<&'static dyn Any>;
+}
+
+impl<T: Future> TaskContext for Task<'_, T> {
+    fn context(&self) -> Option<&'static dyn Any> {
+        self.context
+    }
+}
+
+pub(crate) trait TaskCancel {
+    /// Drops the task's future in place, releasing the resources it holds.
+    fn drop_future(self: Pin<&mut Self>);
+}
+
+impl<T: Future> TaskCancel for Task<'_, T> {
+    fn drop_future(self: Pin<&mut Self>) {
+        // SAFETY: the future is pinned structurally and never moved out of the task. Setting the
+        // pinned `Option` to `None` drops the future in place, as the pinning guarantee requires.
+        unsafe { self.map_unchecked_mut(|this| &mut this.future) }.set(None);
+    }
+}
+
+pub(crate) trait TaskFuture:
+    Future<Output = ()> + TaskName + TaskContext + TaskCancel
+{
+}
 
 impl<T: Future> TaskFuture for Task<'_, T> {}
+
+/// A task whose future output is handed to the executor instead of a linked [`Handle`].
+pub(crate) trait TaskOutput<O>: TaskName + TaskContext {
+    /// Polls the task's future and returns its output once it is ready.
+    fn poll_output(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<O>;
+}
+
+impl<T: Future> TaskOutput<T::Output> for Task<'_, T> {
+    fn poll_output(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T::Output> {
+        // SAFETY: `future` is never moved out of the task.
+        match unsafe { self.map_unchecked_mut(|this| &mut this.future) }.as_pin_mut() {
+            Some(future) => future.poll(cx),
+            None => Poll::Pending,
+        }
+    }
+}
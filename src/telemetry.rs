@@ -0,0 +1,76 @@
+//! # Telemetry sink
+//!
+//! The executor reports what it does through several channels, such as the callbacks set with
+//! [`Executor::set_completion_callback`](crate::executor::Executor::set_completion_callback) or
+//! [`Executor::set_around_poll`](crate::executor::Executor::set_around_poll) and the event log.
+//! This module provides a single channel carrying all of it: the executor pushes every
+//! [`Metric`] into the [`Telemetry`] sink set by
+//! [`Executor::set_telemetry`](crate::executor::Executor::set_telemetry), and the sink routes the
+//! metrics wherever the application wants them, e.g. to a logger, a ring buffer or counters.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::cell::Cell;
+//! use core::pin::pin;
+//! use miniloop::telemetry::{Metric, Telemetry};
+//!
+//! #[derive(Default)]
+//! struct PollCounter {
+//!     polls: Cell<u32>,
+//! }
+//!
+//! impl Telemetry for PollCounter {
+//!     fn record(&self, metric: Metric) {
+//!         if let Metric::PollStarted { .. } = metric {
+//!             self.polls.set(self.polls.get() + 1);
+//!         }
+//!     }
+//! }
+//!
+//! let counter = PollCounter::default();
+//! let task = pin!(Task::new("task", async {}));
+//! let mut handle = Handle::new();
+//! let mut executor = Executor::<1>::new();
+//! executor.set_telemetry(&counter);
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//! executor.run();
+//!
+//! assert_eq!(counter.polls.get(), 1);
+//! ```
+use crate::executor::TaskId;
+
+/// A telemetry event reported by the executor.
+///
+/// Each metric carries the task it refers to and the tick it happened at, read from the
+/// [`TimeSource`](crate::time::TimeSource) of the executor. The tick is `None` if the executor
+/// has no time source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// A task has been spawned into a slot.
+    Spawned { id: TaskId, tick: Option<u64> },
+    /// A task is about to be polled.
+    PollStarted { id: TaskId, tick: Option<u64> },
+    /// A poll of a task has returned. A [`Metric::Completed`] or [`Metric::Aborted`] follows if
+    /// the task has left the executor during the poll.
+    PollEnded { id: TaskId, tick: Option<u64> },
+    /// A task waiting for its reactor interest or its deadline has become runnable again.
+    Woken { id: TaskId, tick: Option<u64> },
+    /// A task has completed and has been removed.
+    Completed { id: TaskId, tick: Option<u64> },
+    /// A task has aborted itself during a poll and has been removed.
+    Aborted { id: TaskId, tick: Option<u64> },
+    /// A task has been cancelled by the executor and removed.
+    Cancelled { id: TaskId, tick: Option<u64> },
+}
+
+/// A sink for the metrics of an executor.
+pub trait Telemetry {
+    /// Called by the executor for every metric, in the order the events happen.
+    ///
+    /// The call is made while the executor is in the middle of its work, so the sink should only
+    /// store or forward the metric.
+    fn record(&self, metric: Metric);
+}
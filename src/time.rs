@@ -0,0 +1,293 @@
+//! # Time helpers
+//!
+//! This module lets tasks wait for time to pass without blocking the executor. The crate has no
+//! notion of a clock on its own, so time is provided by a [`TimeSource`]: anything that can report
+//! a monotonically increasing tick counter, e.g. a hardware timer or the `SysTick` counter on a
+//! microcontroller. What a tick means is up to the time source.
+//!
+//! - [`delay`] - wait for a number of ticks relative to now
+//! - [`sleep_until`] - wait until the time source reaches an absolute tick
+//! - [`yield_until_tick`] - yield, asking the executor not to poll the task before a tick
+//! - [`timeout`] - give up on a future that does not complete in time
+//! - [`timeout_at`] - give up on a future that does not complete by an absolute tick
+//!
+//! Waiting is cooperative: the waiting task yields to the executor until the deadline is reached.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use miniloop::executor::Executor;
+//! # use miniloop::task::{Handle, Task};
+//! use core::pin::pin;
+//! use miniloop::time::{sleep_until, TimeSource};
+//!
+//! fn ticks() -> u64 {
+//!     // read a hardware timer here
+//!     # 0
+//! }
+//!
+//! let mut executor = Executor::<1>::new();
+//! let task = pin!(Task::new("periodic", async {
+//!     const PERIOD: u64 = 100;
+//!     let mut next = ticks();
+//!
+//!     loop {
+//!         next += PERIOD;
+//!         // computing the next deadline from the previous one keeps the loop free of drift
+//!         sleep_until(&ticks, next).await;
+//!         // periodic work
+//!     }
+//! }));
+//! let mut handle = Handle::new();
+//! executor.spawn(task, &mut handle).expect("Failed to spawn task");
+//! executor.run();
+//! ```
+use crate::executor::poll_data;
+use crate::helpers::yield_me;
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A source of the current time expressed in ticks.
+pub trait TimeSource {
+    /// Returns the current tick count.
+    fn now(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> TimeSource for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// Checks whether the tick counter has reached the `target` tick.
+///
+/// The comparison treats the counter as wrapping: `target` counts as reached if it lies at most
+/// half of the counter range behind `now`. That keeps deadlines computed with `wrapping_add`
+/// correct when the counter overflows.
+///
+/// # Example
+/// ```rust
+/// # use miniloop::time::is_reached;
+/// assert!(is_reached(10, 10));
+/// assert!(!is_reached(9, 10));
+/// // the deadline was set right before the counter wrapped around
+/// assert!(is_reached(2, u64::MAX - 1));
+/// ```
+#[must_use]
+pub const fn is_reached(now: u64, target: u64) -> bool {
+    now.wrapping_sub(target) <= u64::MAX / 2
+}
+
+/// Asynchronously waits until the time `source` reaches the absolute `target` tick.
+///
+/// Scheduling against absolute ticks avoids the drift that accumulates in periodic loops built on
+/// top of [`delay`], as the time spent doing the work is not added to the period.
+///
+/// # Example
+/// ```no_run
+/// # use miniloop::time::{sleep_until, TimeSource};
+/// async fn task(clock: &impl TimeSource) {
+///     sleep_until(clock, 1_000).await;
+///     // the clock has reached tick 1000
+/// }
+/// ```
+pub async fn sleep_until<T: TimeSource + ?Sized>(source: &T, target: u64) {
+    while !is_reached(source.now(), target) {
+        yield_me().await;
+    }
+}
+
+/// A future yielding once with a deadline for the executor, created by [`yield_until_tick`].
+struct YieldUntil {
+    /// The tick the task should not be polled before.
+    tick: u64,
+    /// A flag indicating whether the future has yielded once.
+    yielded: bool,
+}
+
+impl Future for YieldUntil {
+    type Output = ();
+
+    /// Yields on the first poll, leaving the deadline for the executor.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(())` if the future has already yielded.
+    /// * `Poll::Pending` if the future needs to yield.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.yielded {
+            return Poll::Ready(());
+        }
+
+        if let Some(data) = poll_data(cx.waker()) {
+            data.deadline.set(Some(this.tick));
+        }
+
+        this.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Yields once, asking the executor not to poll the task again before its time source reaches
+/// `tick`.
+///
+/// Unlike [`sleep_until`], which polls the time source itself on every pass until the tick is
+/// reached, the task yields a single time and leaves the deadline to the executor. The executor
+/// records it per slot, skips the task while its
+/// [time source](crate::executor::Executor::set_time_source) is behind `tick` and polls it on the
+/// first pass after. Knowing the deadlines of all sleeping tasks allows to compute how long the
+/// executor has nothing to do.
+///
+/// The deadline refers to the time source of the executor. Without a time source, or when the
+/// task is not run by [`Executor::run`](crate::executor::Executor::run), the returned future
+/// behaves like [`yield_me`] and the task is polled again right away.
+///
+/// # Example
+/// ```no_run
+/// # use miniloop::time::{yield_until_tick, TimeSource};
+/// async fn task(clock: &impl TimeSource) {
+///     yield_until_tick(clock.now() + 100).await;
+///     // the executor's clock has reached the tick
+/// }
+/// ```
+pub fn yield_until_tick(tick: u64) -> impl Future<Output = ()> {
+    YieldUntil {
+        tick,
+        yielded: false,
+    }
+}
+
+/// Asynchronously waits for `ticks` ticks of the time `source` to pass.
+///
+/// # Example
+/// ```no_run
+/// # use miniloop::time::{delay, TimeSource};
+/// async fn task(clock: &impl TimeSource) {
+///     delay(clock, 10).await;
+///     // at least 10 ticks have passed
+/// }
+/// ```
+pub async fn delay<T: TimeSource + ?Sized>(source: &T, ticks: u64) {
+    let target = source.now().wrapping_add(ticks);
+
+    sleep_until(source, target).await;
+}
+
+/// The error returned by [`Timeout`] when the deadline passes before the future completes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedOut;
+
+/// A future that completes with an error if the inner future does not complete in time.
+///
+/// Created by [`timeout`], [`timeout_at`] or [`Task::with_timeout`](crate::task::Task::with_timeout).
+pub struct Timeout<'s, S: ?Sized, F> {
+    /// The time source the deadline refers to.
+    source: &'s S,
+    /// The future being timed.
+    future: F,
+    /// The number of ticks the future is given, counted from its first poll.
+    ticks: u64,
+    /// The tick by which the future has to complete, computed on the first poll unless given
+    /// upfront by [`timeout_at`].
+    deadline: Option<u64>,
+}
+
+impl<S: TimeSource + ?Sized, F: Future> Future for Timeout<'_, S, F> {
+    type Output = Result<F::Output, TimedOut>;
+
+    /// Polls the inner future and checks the deadline if it is still pending.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll::Ready(Ok(output))` if the inner future completed.
+    /// * `Poll::Ready(Err(TimedOut))` if the deadline has been reached.
+    /// * `Poll::Pending` otherwise.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `Timeout`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let deadline = *this
+            .deadline
+            .get_or_insert_with(|| this.source.now().wrapping_add(this.ticks));
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        if let Poll::Ready(output) = future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        if is_reached(this.source.now(), deadline) {
+            return Poll::Ready(Err(TimedOut));
+        }
+
+        // the deadline has to be checked again even if the inner future does not wake the task
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Requires `future` to complete within `ticks` ticks of the time `source`.
+///
+/// The ticks are counted from the first poll of the returned future. If `future` has not
+/// completed by then, the returned future resolves to `Err(TimedOut)` and `future` is not polled
+/// anymore.
+///
+/// # Example
+/// ```no_run
+/// # use miniloop::helpers::yield_me;
+/// # use miniloop::time::{timeout, TimeSource};
+/// async fn task(clock: &impl TimeSource) {
+///     match timeout(clock, 100, async { yield_me().await; 42 }).await {
+///         Ok(value) => { /* completed in time */ }
+///         Err(_) => { /* took more than 100 ticks */ }
+///     }
+/// }
+/// ```
+pub fn timeout<S, F>(source: &S, ticks: u64, future: F) -> Timeout<'_, S, F>
+where
+    S: TimeSource + ?Sized,
+    F: Future,
+{
+    Timeout {
+        source,
+        future,
+        ticks,
+        deadline: None,
+    }
+}
+
+/// Requires `future` to complete by the absolute tick `deadline` of the time `source`.
+///
+/// Unlike [`timeout`], the deadline does not depend on when the returned future is first polled.
+/// Timeouts chained against fixed deadlines therefore do not drift, however late each of them
+/// starts. If `future` has not completed once `deadline` is reached, the returned future resolves
+/// to `Err(TimedOut)` and `future` is not polled anymore. The deadline is compared the same
+/// wrapping-safe way as in [`sleep_until`].
+///
+/// # Example
+/// ```no_run
+/// # use miniloop::helpers::yield_me;
+/// # use miniloop::time::{timeout_at, TimeSource};
+/// async fn task(clock: &impl TimeSource) {
+///     let deadline = clock.now() + 100;
+///
+///     // both steps together have to complete within 100 ticks
+///     let first = timeout_at(clock, deadline, async { yield_me().await; 1 }).await;
+///     let second = timeout_at(clock, deadline, async { yield_me().await; 2 }).await;
+/// }
+/// ```
+pub fn timeout_at<S, F>(source: &S, deadline: u64, future: F) -> Timeout<'_, S, F>
+where
+    S: TimeSource + ?Sized,
+    F: Future,
+{
+    Timeout {
+        source,
+        future,
+        ticks: 0,
+        deadline: Some(deadline),
+    }
+}
@@ -0,0 +1,148 @@
+//! # Wake tracking
+//!
+//! A waker handed out by the executor can be cloned and stored anywhere, e.g. in a
+//! [`Notify`](crate::sync::Notify), and woken long after its task has left the executor, after
+//! the executor itself is gone, or from another thread. It therefore cannot point into the task or
+//! the executor. Instead, the wakes of the tasks of an executor are tracked in a [`WakeTable`]
+//! living in a `static`, given to the executor with
+//! [`Executor::set_wake_table`](crate::executor::Executor::set_wake_table). The table holds a flag
+//! for every slot of the executor, and the wakers of a task point to the flag of its slot.
+//!
+//! A flag counts the tasks spawned into its slot, and the wakers carry the low bits of the count.
+//! Waking a waker of a previous task of the slot does nothing, unless the count has wrapped around
+//! since, in which case the current task is polled once spuriously, which is harmless.
+//!
+//! The flags only need atomic loads and stores, which are available on targets without
+//! compare-and-swap instructions as well.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use miniloop::executor::Executor;
+//! use miniloop::wake::WakeTable;
+//!
+//! static WAKES: WakeTable<2> = WakeTable::new();
+//!
+//! let mut executor = Executor::<2>::new();
+//! executor.set_wake_table(&WAKES);
+//! ```
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// The number of low bits of the task count of a flag carried by its wakers.
+const GENERATION_BITS: u32 = 4;
+
+/// The mask of the task count in the waker data.
+const GENERATION_MASK: usize = (1 << GENERATION_BITS) - 1;
+
+/// The wake flags of an executor with `N` slots.
+///
+/// A table is given to a single executor at a time. Executors sharing a table clear the wakes of
+/// each other's tasks, which then may never be polled again.
+pub struct WakeTable<const N: usize> {
+    /// The flag of each slot of the executor.
+    slots: [WakeFlag; N],
+    /// The flag of the future run by [`Executor::block_on`](crate::executor::Executor::block_on).
+    block_on: WakeFlag,
+}
+
+impl<const N: usize> Default for WakeTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> WakeTable<N> {
+    /// Creates a table with no wakes recorded.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { WakeFlag::new() }; N],
+            block_on: WakeFlag::new(),
+        }
+    }
+
+    /// Returns the flag of the slot with the given `index`.
+    pub(crate) fn slot(&self, index: usize) -> &WakeFlag {
+        &self.slots[index]
+    }
+
+    /// Returns the flag of the future run by `block_on`.
+    pub(crate) fn block_on(&self) -> &WakeFlag {
+        &self.block_on
+    }
+}
+
+/// The wake flag of a slot. Aligned, so the low bits of its address are free to carry the task
+/// count in the waker data.
+#[repr(align(16))]
+pub(crate) struct WakeFlag {
+    /// The number of tasks spawned into the slot, wrapping around.
+    generation: AtomicU8,
+    /// Set while the task has been woken since it last cleared the flag.
+    woken: AtomicBool,
+}
+
+const _: () = assert!(align_of::<WakeFlag>() > GENERATION_MASK);
+
+impl WakeFlag {
+    const fn new() -> Self {
+        Self {
+            generation: AtomicU8::new(0),
+            woken: AtomicBool::new(false),
+        }
+    }
+
+    /// Hands the flag to a new task, ignoring the wakers of the previous ones. The flag starts
+    /// out woken.
+    ///
+    /// Only the executor owning the table writes the task count, so it is incremented without a
+    /// compare-and-swap.
+    pub(crate) fn claim(&self) {
+        let generation = self.generation.load(Ordering::Relaxed).wrapping_add(1);
+
+        self.generation.store(generation, Ordering::Release);
+        self.woken.store(true, Ordering::Release);
+    }
+
+    /// Returns the data of the wakers setting this flag for the current task, see [`wake`].
+    pub(crate) fn waker_data(&'static self) -> *const () {
+        let generation = usize::from(self.generation.load(Ordering::Relaxed)) & GENERATION_MASK;
+
+        core::ptr::from_ref(self)
+            .cast::<()>()
+            .map_addr(|addr| addr | generation)
+    }
+
+    /// Checks whether the flag has been woken since it was last cleared.
+    pub(crate) fn is_woken(&self) -> bool {
+        self.woken.load(Ordering::Acquire)
+    }
+
+    /// Clears the flag, so only the wakes from now on are observed.
+    pub(crate) fn clear(&self) {
+        self.woken.store(false, Ordering::Release);
+    }
+}
+
+/// Sets the flag the waker `data` refers to, unless the flag has been claimed by another task
+/// since.
+///
+/// # Safety
+///
+/// `data` must have been returned by [`WakeFlag::waker_data`].
+pub(crate) unsafe fn wake(data: *const ()) {
+    let generation = data.addr() & GENERATION_MASK;
+
+    // SAFETY: the data points to a flag of a `WakeTable` living in a `static`, with the task count
+    // in the low bits freed by the alignment of the flag.
+    let flag = unsafe {
+        &*data
+            .map_addr(|addr| addr & !GENERATION_MASK)
+            .cast::<WakeFlag>()
+    };
+
+    if usize::from(flag.generation.load(Ordering::Acquire)) & GENERATION_MASK == generation {
+        flag.woken.store(true, Ordering::Release);
+    }
+}